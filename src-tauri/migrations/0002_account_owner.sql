@@ -0,0 +1,7 @@
+-- Migration 0002: Add owner column to accounts table
+-- Date: October 8, 2025
+
+ALTER TABLE accounts ADD COLUMN owner TEXT;
+
+-- Existing accounts default to Ioan
+UPDATE accounts SET owner = 'Ioan' WHERE owner IS NULL;
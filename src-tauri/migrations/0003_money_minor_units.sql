@@ -0,0 +1,37 @@
+-- Migration 0003: Store amounts and balances as integer minor units
+-- REAL amounts drift after many additions; 12.34 RON is now stored as 1234 bani.
+-- Decimal places per currency must match money::minor_unit_exponent.
+
+-- Accounts: balance REAL -> INTEGER
+ALTER TABLE accounts ADD COLUMN balance_minor INTEGER NOT NULL DEFAULT 0;
+
+UPDATE accounts SET balance_minor = CAST(ROUND(balance * (
+    CASE
+        WHEN currency IN ('JPY', 'KRW', 'ISK', 'CLP', 'VND', 'XAF', 'XOF') THEN 1
+        WHEN currency IN ('BHD', 'IQD', 'JOD', 'KWD', 'LYD', 'OMR', 'TND') THEN 1000
+        ELSE 100
+    END
+)) AS INTEGER);
+
+ALTER TABLE accounts DROP COLUMN balance;
+ALTER TABLE accounts RENAME COLUMN balance_minor TO balance;
+
+-- Transactions: amount REAL -> INTEGER, currency copied from the owning account
+ALTER TABLE transactions ADD COLUMN currency TEXT NOT NULL DEFAULT 'RON';
+ALTER TABLE transactions ADD COLUMN amount_minor INTEGER NOT NULL DEFAULT 0;
+
+UPDATE transactions SET currency = COALESCE(
+    (SELECT a.currency FROM accounts a WHERE a.id = transactions.account_id),
+    'RON'
+);
+
+UPDATE transactions SET amount_minor = CAST(ROUND(amount * (
+    CASE
+        WHEN currency IN ('JPY', 'KRW', 'ISK', 'CLP', 'VND', 'XAF', 'XOF') THEN 1
+        WHEN currency IN ('BHD', 'IQD', 'JOD', 'KWD', 'LYD', 'OMR', 'TND') THEN 1000
+        ELSE 100
+    END
+)) AS INTEGER);
+
+ALTER TABLE transactions DROP COLUMN amount;
+ALTER TABLE transactions RENAME COLUMN amount_minor TO amount;
@@ -0,0 +1,18 @@
+-- Migration 0004: Transfers between accounts
+-- A transfer links an expense leg on the source account with an income leg on the
+-- destination account. Legs carry transfer_id so reports can leave them out.
+
+CREATE TABLE transfers (
+    id TEXT PRIMARY KEY,
+    from_transaction_id TEXT NOT NULL UNIQUE,
+    to_transaction_id TEXT NOT NULL UNIQUE,
+    exchange_rate TEXT,
+    created_at TEXT NOT NULL,
+    updated_at TEXT NOT NULL,
+    FOREIGN KEY (from_transaction_id) REFERENCES transactions (id),
+    FOREIGN KEY (to_transaction_id) REFERENCES transactions (id)
+);
+
+ALTER TABLE transactions ADD COLUMN transfer_id TEXT;
+
+CREATE INDEX idx_transactions_transfer_id ON transactions (transfer_id);
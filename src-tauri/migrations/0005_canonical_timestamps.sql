@@ -0,0 +1,26 @@
+-- Migration 0005: Canonical timestamps
+-- Rewrite every timestamp in the canonical format used by timestamp.rs:
+-- UTC, millisecond precision, e.g. 2024-05-01T10:00:00.000Z.
+-- Values SQLite cannot parse are left untouched so nothing is lost.
+
+UPDATE accounts SET
+    created_at = COALESCE(strftime('%Y-%m-%dT%H:%M:%fZ', created_at), created_at),
+    updated_at = COALESCE(strftime('%Y-%m-%dT%H:%M:%fZ', updated_at), updated_at);
+
+UPDATE categories SET
+    created_at = COALESCE(strftime('%Y-%m-%dT%H:%M:%fZ', created_at), created_at);
+
+UPDATE transactions SET
+    date = COALESCE(strftime('%Y-%m-%dT%H:%M:%fZ', date), date),
+    created_at = COALESCE(strftime('%Y-%m-%dT%H:%M:%fZ', created_at), created_at),
+    updated_at = COALESCE(strftime('%Y-%m-%dT%H:%M:%fZ', updated_at), updated_at);
+
+UPDATE tags SET
+    created_at = COALESCE(strftime('%Y-%m-%dT%H:%M:%fZ', created_at), created_at);
+
+UPDATE transaction_tags SET
+    created_at = COALESCE(strftime('%Y-%m-%dT%H:%M:%fZ', created_at), created_at);
+
+UPDATE transfers SET
+    created_at = COALESCE(strftime('%Y-%m-%dT%H:%M:%fZ', created_at), created_at),
+    updated_at = COALESCE(strftime('%Y-%m-%dT%H:%M:%fZ', updated_at), updated_at);
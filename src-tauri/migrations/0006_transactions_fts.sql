@@ -0,0 +1,29 @@
+-- Migration 0006: Full-text search over transaction descriptions
+-- A standalone FTS5 table keyed by transaction id rather than external content on rowid,
+-- because transactions has no INTEGER PRIMARY KEY and VACUUM may renumber its rowids.
+-- remove_diacritics 2 makes "plata" match "Plată" and "stefanesti" match "ȘTEFĂNEȘTI".
+
+CREATE VIRTUAL TABLE transactions_fts USING fts5(
+    transaction_id UNINDEXED,
+    description,
+    tokenize = 'unicode61 remove_diacritics 2',
+    prefix = '2 3'
+);
+
+INSERT INTO transactions_fts (transaction_id, description)
+SELECT id, description FROM transactions;
+
+-- Triggers keep the index in sync for every write path, including imports
+-- and rows removed by ON DELETE CASCADE from accounts.
+CREATE TRIGGER transactions_fts_insert AFTER INSERT ON transactions BEGIN
+    INSERT INTO transactions_fts (transaction_id, description) VALUES (new.id, new.description);
+END;
+
+CREATE TRIGGER transactions_fts_update AFTER UPDATE OF id, description ON transactions BEGIN
+    DELETE FROM transactions_fts WHERE transaction_id = old.id;
+    INSERT INTO transactions_fts (transaction_id, description) VALUES (new.id, new.description);
+END;
+
+CREATE TRIGGER transactions_fts_delete AFTER DELETE ON transactions BEGIN
+    DELETE FROM transactions_fts WHERE transaction_id = old.id;
+END;
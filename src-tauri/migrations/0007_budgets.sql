@@ -0,0 +1,23 @@
+-- Migration 0007: Category budgets
+-- A budget limits spending in one category and currency per month or year, optionally
+-- narrowed to accounts of one owner or to transactions with one tag.
+
+CREATE TABLE budgets (
+    id TEXT PRIMARY KEY,
+    category_id TEXT NOT NULL,
+    owner TEXT,
+    tag_id TEXT,
+    period TEXT NOT NULL CHECK (period IN ('monthly', 'yearly')),
+    amount INTEGER NOT NULL CHECK (amount > 0),
+    currency TEXT NOT NULL,
+    rollover INTEGER NOT NULL DEFAULT 0,
+    warning_percent INTEGER NOT NULL DEFAULT 80 CHECK (warning_percent BETWEEN 1 AND 100),
+    start_date TEXT NOT NULL,
+    created_at TEXT NOT NULL,
+    updated_at TEXT NOT NULL,
+    FOREIGN KEY (category_id) REFERENCES categories (id) ON DELETE CASCADE,
+    FOREIGN KEY (tag_id) REFERENCES tags (id) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_budgets_category_id ON budgets (category_id);
+CREATE INDEX idx_transactions_category_date ON transactions (category_id, date);
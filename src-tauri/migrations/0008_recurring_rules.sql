@@ -0,0 +1,45 @@
+-- Migration 0008: Recurring transaction schedules
+-- A rule is a transaction template plus a schedule. Each scheduled date that has been
+-- dealt with gets a row in recurring_occurrences, which keeps generation idempotent
+-- and records skipped or edited occurrences.
+
+CREATE TABLE recurring_rules (
+    id TEXT PRIMARY KEY,
+    account_id TEXT NOT NULL,
+    category_id TEXT NOT NULL,
+    amount INTEGER NOT NULL CHECK (amount > 0),
+    currency TEXT NOT NULL,
+    description TEXT NOT NULL,
+    transaction_type TEXT NOT NULL CHECK (transaction_type IN ('income', 'expense')),
+    tag_ids TEXT NOT NULL DEFAULT '[]',
+    frequency TEXT NOT NULL CHECK (frequency IN ('daily', 'weekly', 'monthly', 'yearly')),
+    interval INTEGER NOT NULL DEFAULT 1 CHECK (interval >= 1),
+    weekday INTEGER CHECK (weekday BETWEEN 0 AND 6),
+    week_of_month INTEGER CHECK (week_of_month IN (-1, 1, 2, 3, 4, 5)),
+    start_date TEXT NOT NULL,
+    end_date TEXT,
+    max_occurrences INTEGER CHECK (max_occurrences >= 1),
+    active INTEGER NOT NULL DEFAULT 1,
+    created_at TEXT NOT NULL,
+    updated_at TEXT NOT NULL,
+    FOREIGN KEY (account_id) REFERENCES accounts (id) ON DELETE CASCADE,
+    FOREIGN KEY (category_id) REFERENCES categories (id) ON DELETE CASCADE
+);
+
+CREATE TABLE recurring_occurrences (
+    rule_id TEXT NOT NULL,
+    occurrence_date TEXT NOT NULL,
+    status TEXT NOT NULL CHECK (status IN ('pending', 'generated', 'skipped')),
+    transaction_id TEXT,
+    amount INTEGER,
+    description TEXT,
+    category_id TEXT,
+    created_at TEXT NOT NULL,
+    updated_at TEXT NOT NULL,
+    PRIMARY KEY (rule_id, occurrence_date),
+    FOREIGN KEY (rule_id) REFERENCES recurring_rules (id) ON DELETE CASCADE,
+    FOREIGN KEY (transaction_id) REFERENCES transactions (id) ON DELETE SET NULL,
+    FOREIGN KEY (category_id) REFERENCES categories (id) ON DELETE SET NULL
+);
+
+CREATE INDEX idx_recurring_occurrences_transaction_id ON recurring_occurrences (transaction_id);
@@ -0,0 +1,11 @@
+-- Migration 0009: Saved CSV column mappings
+-- One profile per bank export layout. The mapping itself (delimiter, encoding, columns,
+-- date formats) is stored as JSON so new options do not need a schema change.
+
+CREATE TABLE csv_profiles (
+    id TEXT PRIMARY KEY,
+    name TEXT NOT NULL UNIQUE,
+    mapping TEXT NOT NULL,
+    created_at TEXT NOT NULL,
+    updated_at TEXT NOT NULL
+);
@@ -0,0 +1,21 @@
+-- Migration 0010: Bank statement identifiers and balances
+-- external_id keeps the bank's own transaction id (OFX FITID) so re-importing a statement
+-- matches exactly instead of by date, amount and description. statement_balances holds the
+-- balance a bank reported at a date, to reconcile against the ledger.
+
+ALTER TABLE transactions ADD COLUMN external_id TEXT;
+
+CREATE UNIQUE INDEX idx_transactions_external_id
+    ON transactions (account_id, external_id)
+    WHERE external_id IS NOT NULL;
+
+CREATE TABLE statement_balances (
+    id TEXT PRIMARY KEY,
+    account_id TEXT NOT NULL,
+    balance_date TEXT NOT NULL,
+    amount INTEGER NOT NULL,
+    currency TEXT NOT NULL,
+    created_at TEXT NOT NULL,
+    UNIQUE (account_id, balance_date),
+    FOREIGN KEY (account_id) REFERENCES accounts (id) ON DELETE CASCADE
+);
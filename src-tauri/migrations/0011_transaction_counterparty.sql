@@ -0,0 +1,7 @@
+-- Migration 0011: Counterparty and value date on imported transactions
+-- Filled from bank statements that carry them (camt.053); NULL for manual entries.
+-- date stays the booking date, value_date is when the bank applied the amount.
+
+ALTER TABLE transactions ADD COLUMN value_date TEXT;
+ALTER TABLE transactions ADD COLUMN counterparty_name TEXT;
+ALTER TABLE transactions ADD COLUMN counterparty_iban TEXT;
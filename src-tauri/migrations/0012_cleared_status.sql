@@ -0,0 +1,5 @@
+-- Migration 0012: Cleared status of transactions
+-- Mirrors the QIF C field: uncleared, cleared (*/c) or reconciled (X/R) against a statement.
+
+ALTER TABLE transactions ADD COLUMN cleared_status TEXT NOT NULL DEFAULT 'uncleared'
+    CHECK (cleared_status IN ('uncleared', 'cleared', 'reconciled'));
@@ -0,0 +1,17 @@
+-- Migration 0013: Duplicate matching for imports
+-- Imports skip rows whose fingerprint matches an existing transaction and hold back rows that
+-- only resemble one for review. duplicate_settings is a single row that sets how loose that
+-- resemblance may be; amount_tolerance is in minor units.
+
+CREATE TABLE duplicate_settings (
+    id INTEGER PRIMARY KEY CHECK (id = 1),
+    fuzzy_enabled INTEGER NOT NULL DEFAULT 1,
+    date_tolerance_days INTEGER NOT NULL DEFAULT 3,
+    amount_tolerance INTEGER NOT NULL DEFAULT 0,
+    min_similarity REAL NOT NULL DEFAULT 0.5,
+    updated_at TEXT NOT NULL
+);
+
+INSERT INTO duplicate_settings (id, updated_at) VALUES (1, strftime('%Y-%m-%dT%H:%M:%fZ', 'now'));
+
+CREATE INDEX idx_transactions_account_date ON transactions (account_id, date);
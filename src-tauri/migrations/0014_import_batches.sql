@@ -0,0 +1,28 @@
+-- Migration 0014: Import batches
+-- Every import is recorded as a batch with the source file and the transactions it inserted,
+-- so a wrong import can be reverted as a whole. Statement balances saved by an import point
+-- at their batch and are removed with it.
+
+CREATE TABLE import_batches (
+    id TEXT PRIMARY KEY,
+    file_name TEXT,
+    file_hash TEXT,
+    account_id TEXT,
+    inserted INTEGER NOT NULL DEFAULT 0,
+    skipped INTEGER NOT NULL DEFAULT 0,
+    created_at TEXT NOT NULL,
+    reverted_at TEXT,
+    FOREIGN KEY (account_id) REFERENCES accounts (id) ON DELETE SET NULL
+);
+
+CREATE TABLE import_batch_transactions (
+    batch_id TEXT NOT NULL,
+    transaction_id TEXT NOT NULL,
+    PRIMARY KEY (batch_id, transaction_id),
+    FOREIGN KEY (batch_id) REFERENCES import_batches (id) ON DELETE CASCADE,
+    FOREIGN KEY (transaction_id) REFERENCES transactions (id) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_import_batch_transactions_transaction_id ON import_batch_transactions (transaction_id);
+
+ALTER TABLE statement_balances ADD COLUMN import_batch_id TEXT REFERENCES import_batches (id) ON DELETE SET NULL;
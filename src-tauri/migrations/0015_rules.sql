@@ -0,0 +1,18 @@
+-- Migration 0015: Categorization rules
+-- Rules match transactions by description, payee, amount range, account and type and set
+-- category, tags, owner or description. Conditions and actions are stored as JSON like CSV
+-- profiles. Lower priority values run first. transactions.owner attributes a transaction to
+-- a person when it differs from the account owner.
+
+CREATE TABLE rules (
+    id TEXT PRIMARY KEY,
+    name TEXT NOT NULL,
+    priority INTEGER NOT NULL DEFAULT 0,
+    enabled INTEGER NOT NULL DEFAULT 1,
+    conditions TEXT NOT NULL,
+    actions TEXT NOT NULL,
+    created_at TEXT NOT NULL,
+    updated_at TEXT NOT NULL
+);
+
+ALTER TABLE transactions ADD COLUMN owner TEXT;
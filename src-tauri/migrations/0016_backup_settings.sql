@@ -0,0 +1,17 @@
+-- Migration 0016: Automatic backups
+-- backup_settings is a single row for the background backup task: a backup on startup, on
+-- shutdown and every interval_hours while enabled. Older backups are thinned out
+-- grandfather-father-son style, keeping the newest backup of each of the last keep_daily days,
+-- keep_weekly weeks and keep_monthly months.
+
+CREATE TABLE backup_settings (
+    id INTEGER PRIMARY KEY CHECK (id = 1),
+    enabled INTEGER NOT NULL DEFAULT 1,
+    interval_hours INTEGER NOT NULL DEFAULT 24,
+    keep_daily INTEGER NOT NULL DEFAULT 7,
+    keep_weekly INTEGER NOT NULL DEFAULT 4,
+    keep_monthly INTEGER NOT NULL DEFAULT 12,
+    updated_at TEXT NOT NULL
+);
+
+INSERT INTO backup_settings (id, updated_at) VALUES (1, strftime('%Y-%m-%dT%H:%M:%fZ', 'now'));
@@ -0,0 +1,22 @@
+-- Migration 0017: Transfer category type
+-- The "Transfer intern" category holds both legs of a transfer, so it is neither income nor
+-- expense. Categories get a third type, 'transfer', which budgets and per-type reports leave
+-- out. SQLite cannot change a CHECK constraint in place, so the table is rebuilt.
+
+CREATE TABLE categories_new (
+    id TEXT PRIMARY KEY,
+    name TEXT NOT NULL,
+    color TEXT NOT NULL DEFAULT '#3B82F6',
+    icon TEXT NOT NULL DEFAULT '💰',
+    category_type TEXT NOT NULL CHECK (category_type IN ('income', 'expense', 'transfer')),
+    created_at TEXT NOT NULL
+);
+
+INSERT INTO categories_new (id, name, color, icon, category_type, created_at)
+SELECT id, name, color, icon,
+       CASE WHEN name = 'Transfer intern' THEN 'transfer' ELSE category_type END,
+       created_at
+FROM categories;
+
+DROP TABLE categories;
+ALTER TABLE categories_new RENAME TO categories;
@@ -0,0 +1,592 @@
+// backup.rs
+// Consistent backups of the live database. VACUUM INTO writes a snapshot through SQLite
+// itself, so a write in flight (or WAL content) is never torn; the copy is then checked with
+// PRAGMA integrity_check and described by a JSON manifest stored next to it. Restores validate
+// the candidate, back up the current database first and swap the file with the pool closed.
+// A background task takes automatic backups and thins them out grandfather-father-son style.
+
+use chrono::{DateTime, Datelike, Duration, Local, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use sqlx::sqlite::{SqliteConnectOptions, SqliteConnection, SqliteRow};
+use sqlx::{ConnectOptions, Connection, FromRow, Row};
+use std::collections::{BTreeMap, HashSet};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::sync::Mutex;
+use crate::database::Database;
+use crate::error::AppError;
+use crate::migrations::{latest_version, run_migrations};
+use crate::timestamp;
+
+const SQLITE_HEADER: &[u8; 16] = b"SQLite format 3\0";
+// Tables of the initial schema; any MoneyZen database has them whatever its version
+const EXPECTED_TABLES: [&str; 5] = ["accounts", "categories", "transactions", "tags", "transaction_tags"];
+// How often the background task re-reads its settings while waiting for the next backup
+const SCHEDULE_POLL: std::time::Duration = std::time::Duration::from_secs(15 * 60);
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct BackupManifest {
+    pub app_version: String,
+    pub schema_version: i64,
+    pub created_at: DateTime<Utc>,
+    pub file_name: String,
+    pub size_bytes: u64,
+    pub sha256: String,
+    pub row_counts: BTreeMap<String, i64>,
+    #[serde(default)]
+    pub automatic: bool, // only automatic backups are subject to the retention policy
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct BackupInfo {
+    pub path: String,
+    pub manifest: BackupManifest,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct RestoreReport {
+    pub restored_from: String,
+    pub pre_restore_backup: BackupInfo,
+    pub backup_schema_version: i64,
+    pub schema_version: i64, // after pending migrations ran on the restored file
+    pub row_counts: BTreeMap<String, i64>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct BackupSettings {
+    pub enabled: bool,
+    pub interval_hours: i64,
+    pub keep_daily: i64,
+    pub keep_weekly: i64,
+    pub keep_monthly: i64,
+}
+
+impl<'r> FromRow<'r, SqliteRow> for BackupSettings {
+    fn from_row(row: &'r SqliteRow) -> Result<Self, sqlx::Error> {
+        Ok(BackupSettings {
+            enabled: row.try_get("enabled")?,
+            interval_hours: row.try_get("interval_hours")?,
+            keep_daily: row.try_get("keep_daily")?,
+            keep_weekly: row.try_get("keep_weekly")?,
+            keep_monthly: row.try_get("keep_monthly")?,
+        })
+    }
+}
+
+// What a database file holds, read without modifying it
+#[derive(Debug, Clone)]
+pub struct DatabaseSummary {
+    pub schema_version: i64,
+    pub row_counts: BTreeMap<String, i64>,
+}
+
+impl Database {
+    pub async fn create_backup(&self, backup_dir: &Path, app_version: &str) -> Result<BackupInfo, AppError> {
+        self.write_backup(backup_dir, app_version, false).await
+    }
+
+    async fn write_backup(&self, backup_dir: &Path, app_version: &str, automatic: bool) -> Result<BackupInfo, AppError> {
+        std::fs::create_dir_all(backup_dir)
+            .map_err(|e| AppError::io(format!("Failed to create backup directory: {}", e)))?;
+
+        let created_at = timestamp::now();
+        let prefix = if automatic { "auto" } else { "backup" };
+        let path = unused_backup_path(backup_dir, prefix, &Local::now().format("%Y-%m-%d_%H-%M-%S").to_string());
+
+        sqlx::query("VACUUM INTO ?")
+            .bind(path.to_string_lossy().to_string())
+            .execute(self.get_pool())
+            .await?;
+
+        let summary = match inspect_database(&path).await {
+            Ok(summary) => summary,
+            Err(e) => {
+                // Never leave a backup behind that failed its check
+                let _ = std::fs::remove_file(&path);
+                return Err(e);
+            }
+        };
+
+        let manifest = BackupManifest {
+            app_version: app_version.to_string(),
+            schema_version: summary.schema_version,
+            created_at,
+            file_name: path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default(),
+            size_bytes: std::fs::metadata(&path)?.len(),
+            sha256: sha256_file(&path)?,
+            row_counts: summary.row_counts,
+            automatic,
+        };
+        write_manifest(&path, &manifest)?;
+
+        Ok(BackupInfo {
+            path: path.to_string_lossy().to_string(),
+            manifest,
+        })
+    }
+}
+
+impl Database {
+    // Replaces the live database with a backup. On success `self` points at the restored
+    // file; if the swap or the migrations fail, the pre-restore backup is put back instead.
+    pub async fn restore_backup(
+        &mut self,
+        backup_path: &Path,
+        backup_dir: &Path,
+        app_version: &str,
+    ) -> Result<RestoreReport, AppError> {
+        let candidate = validate_backup(backup_path).await?;
+        let pre_restore_backup = self.create_backup(backup_dir, app_version).await?;
+
+        // Stage the copy beside the live file so the final swap is a rename
+        let live = self.path().to_path_buf();
+        let staged = live.with_extension("restore");
+        std::fs::copy(backup_path, &staged)
+            .map_err(|e| AppError::io(format!("Failed to stage backup: {}", e)))?;
+
+        self.close().await;
+        let restored = match replace_file(&staged, &live) {
+            Ok(()) => open_migrated(&live).await,
+            Err(e) => Err(e),
+        };
+
+        match restored {
+            Ok(database) => {
+                *self = database;
+                let summary = inspect_database(&live).await?;
+                Ok(RestoreReport {
+                    restored_from: backup_path.to_string_lossy().to_string(),
+                    pre_restore_backup,
+                    backup_schema_version: candidate.schema_version,
+                    schema_version: summary.schema_version,
+                    row_counts: summary.row_counts,
+                })
+            }
+            Err(e) => {
+                eprintln!("❌ Restore failed, putting back {}: {}", pre_restore_backup.path, e);
+                let _ = std::fs::remove_file(&staged);
+                std::fs::copy(&pre_restore_backup.path, &staged)?;
+                replace_file(&staged, &live)?;
+                *self = Database::new(live).await?;
+                Err(e)
+            }
+        }
+    }
+}
+
+impl Database {
+    pub async fn get_backup_settings(&self) -> Result<BackupSettings, AppError> {
+        let settings = sqlx::query_as::<_, BackupSettings>("SELECT * FROM backup_settings WHERE id = 1")
+            .fetch_one(self.get_pool())
+            .await?;
+
+        Ok(settings)
+    }
+
+    pub async fn save_backup_settings(&self, settings: BackupSettings) -> Result<BackupSettings, AppError> {
+        if settings.interval_hours < 1 {
+            return Err(AppError::validation("interval_hours", "must be at least 1"));
+        }
+        if settings.keep_daily < 0 || settings.keep_weekly < 0 || settings.keep_monthly < 0 {
+            return Err(AppError::validation("keep", "must not be negative"));
+        }
+
+        sqlx::query(
+            r#"
+            UPDATE backup_settings
+            SET enabled = ?, interval_hours = ?, keep_daily = ?, keep_weekly = ?, keep_monthly = ?, updated_at = ?
+            WHERE id = 1
+            "#
+        )
+        .bind(settings.enabled)
+        .bind(settings.interval_hours)
+        .bind(settings.keep_daily)
+        .bind(settings.keep_weekly)
+        .bind(settings.keep_monthly)
+        .bind(timestamp::format(&timestamp::now()))
+        .execute(self.get_pool())
+        .await?;
+
+        Ok(settings)
+    }
+
+    // Takes an automatic backup and prunes older ones; None while automatic backups are off
+    pub async fn automatic_backup(&self, backup_dir: &Path, app_version: &str) -> Result<Option<BackupInfo>, AppError> {
+        let settings = self.get_backup_settings().await?;
+        if !settings.enabled {
+            return Ok(None);
+        }
+
+        let backup = self.write_backup(backup_dir, app_version, true).await?;
+        apply_retention(backup_dir, &settings)?;
+        Ok(Some(backup))
+    }
+
+    // Backs up when forced or when the newest automatic backup is older than the interval.
+    // Returns how long to wait before checking again.
+    async fn scheduled_backup(
+        &self,
+        backup_dir: &Path,
+        app_version: &str,
+        force: bool,
+    ) -> Result<std::time::Duration, AppError> {
+        let settings = self.get_backup_settings().await?;
+        if !settings.enabled {
+            return Ok(SCHEDULE_POLL);
+        }
+
+        let interval = Duration::hours(settings.interval_hours);
+        let newest = list_backups(backup_dir)?
+            .into_iter()
+            .find(|backup| backup.manifest.automatic)
+            .map(|backup| backup.manifest.created_at);
+        let wait = match time_until_due(newest, interval, timestamp::now(), force) {
+            Some(remaining) => remaining,
+            None => {
+                self.automatic_backup(backup_dir, app_version).await?;
+                interval.to_std().unwrap_or_default()
+            }
+        };
+
+        Ok(wait.min(SCHEDULE_POLL))
+    }
+}
+
+// None when a backup is due now, otherwise how long until it is
+fn time_until_due(
+    newest: Option<DateTime<Utc>>,
+    interval: Duration,
+    now: DateTime<Utc>,
+    force: bool,
+) -> Option<std::time::Duration> {
+    let due = newest.filter(|_| !force)? + interval;
+    if due <= now {
+        return None;
+    }
+    (due - now).to_std().ok()
+}
+
+// Runs for the lifetime of the app: a backup on startup, then one whenever the interval has
+// passed. Settings are re-read every round, so changes apply without a restart.
+pub async fn run_backup_schedule(db: Arc<Mutex<Database>>, backup_dir: PathBuf, app_version: String) {
+    let mut startup = true;
+    loop {
+        let wait = {
+            let db = db.lock().await;
+            match db.scheduled_backup(&backup_dir, &app_version, startup).await {
+                Ok(wait) => wait,
+                Err(e) => {
+                    eprintln!("❌ Automatic backup failed: {}", e);
+                    SCHEDULE_POLL
+                }
+            }
+        };
+        startup = false;
+        tokio::time::sleep(wait).await;
+    }
+}
+
+// Backups in the directory that have a manifest, newest first. Files without one (copies
+// made before manifests existed, or by hand) are left out and never pruned.
+pub fn list_backups(backup_dir: &Path) -> Result<Vec<BackupInfo>, AppError> {
+    if !backup_dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut backups = Vec::new();
+    for entry in std::fs::read_dir(backup_dir)? {
+        let manifest_file = entry?.path();
+        if manifest_file.extension().is_none_or(|ext| ext != "json") {
+            continue;
+        }
+        let manifest: BackupManifest = match std::fs::read_to_string(&manifest_file)
+            .ok()
+            .and_then(|json| serde_json::from_str(&json).ok())
+        {
+            Some(manifest) => manifest,
+            None => continue,
+        };
+
+        let path = backup_dir.join(&manifest.file_name);
+        if path.exists() {
+            backups.push(BackupInfo {
+                path: path.to_string_lossy().to_string(),
+                manifest,
+            });
+        }
+    }
+
+    backups.sort_by_key(|backup| std::cmp::Reverse(backup.manifest.created_at));
+    Ok(backups)
+}
+
+// Day, ISO week or month a backup falls in
+type PeriodOf = fn(DateTime<Local>) -> (i32, u32, u32);
+
+// Grandfather-father-son: keeps the newest automatic backup of each of the last keep_daily
+// days, keep_weekly ISO weeks and keep_monthly months (local time), plus the newest overall.
+// Deletes the other automatic backups and returns their paths.
+pub fn apply_retention(backup_dir: &Path, settings: &BackupSettings) -> Result<Vec<String>, AppError> {
+    let backups: Vec<BackupInfo> = list_backups(backup_dir)?
+        .into_iter()
+        .filter(|backup| backup.manifest.automatic)
+        .collect();
+
+    let mut keep: HashSet<&str> = HashSet::new();
+    if let Some(newest) = backups.first() {
+        keep.insert(&newest.path);
+    }
+
+    let periods: [(i64, PeriodOf); 3] = [
+        (settings.keep_daily, |t| (t.year(), t.month(), t.day())),
+        (settings.keep_weekly, |t| (t.iso_week().year(), t.iso_week().week(), 0)),
+        (settings.keep_monthly, |t| (t.year(), t.month(), 0)),
+    ];
+    for (count, period_of) in periods {
+        let mut periods_seen = HashSet::new();
+        for backup in &backups {
+            let period = period_of(backup.manifest.created_at.with_timezone(&Local));
+            if periods_seen.contains(&period) {
+                continue;
+            }
+            if periods_seen.len() as i64 >= count {
+                break;
+            }
+            periods_seen.insert(period);
+            keep.insert(&backup.path);
+        }
+    }
+
+    let mut removed = Vec::new();
+    for backup in &backups {
+        if keep.contains(backup.path.as_str()) {
+            continue;
+        }
+        let path = Path::new(&backup.path);
+        std::fs::remove_file(path)?;
+        let _ = std::fs::remove_file(manifest_path(path));
+        removed.push(backup.path.clone());
+    }
+
+    Ok(removed)
+}
+
+// Checks that the file is a SQLite database that passes integrity_check, has the MoneyZen
+// tables and is not from a newer version of the app
+pub async fn validate_backup(path: &Path) -> Result<DatabaseSummary, AppError> {
+    let mut header = [0u8; 16];
+    let mut file = std::fs::File::open(path).map_err(|_| AppError::not_found("backup file"))?;
+    if file.read_exact(&mut header).is_err() || &header != SQLITE_HEADER {
+        return Err(AppError::validation("backup_path", "not a SQLite database"));
+    }
+
+    let summary = inspect_database(path).await?;
+    if let Some(missing) = EXPECTED_TABLES.iter().find(|table| !summary.row_counts.contains_key(**table)) {
+        return Err(AppError::validation(
+            "backup_path",
+            format!("not a MoneyZen database (no {} table)", missing),
+        ));
+    }
+    if summary.schema_version > latest_version() {
+        return Err(AppError::validation(
+            "backup_path",
+            format!(
+                "backup has schema version {}, this app supports up to {}",
+                summary.schema_version,
+                latest_version()
+            ),
+        ));
+    }
+
+    Ok(summary)
+}
+
+async fn open_migrated(path: &Path) -> Result<Database, AppError> {
+    let database = Database::new(path.to_path_buf()).await?;
+    if let Err(e) = run_migrations(database.get_pool()).await {
+        database.close().await;
+        return Err(AppError::from(e));
+    }
+    Ok(database)
+}
+
+// Renames `from` over `to`, dropping journal files that belong to the old database
+fn replace_file(from: &Path, to: &Path) -> Result<(), AppError> {
+    for suffix in ["-wal", "-shm", "-journal"] {
+        let mut journal = to.as_os_str().to_os_string();
+        journal.push(suffix);
+        let _ = std::fs::remove_file(PathBuf::from(journal));
+    }
+    std::fs::rename(from, to).map_err(|e| AppError::io(format!("Failed to replace database: {}", e)))
+}
+
+// Opens the file read-only, runs PRAGMA integrity_check and counts the rows of every table
+pub async fn inspect_database(path: &Path) -> Result<DatabaseSummary, AppError> {
+    let mut conn = SqliteConnectOptions::new()
+        .filename(path)
+        .read_only(true)
+        .connect()
+        .await?;
+
+    let result = summarize(&mut conn).await;
+    conn.close().await?;
+    result
+}
+
+async fn summarize(conn: &mut SqliteConnection) -> Result<DatabaseSummary, AppError> {
+    let problems: Vec<String> = sqlx::query_scalar("PRAGMA integrity_check")
+        .fetch_all(&mut *conn)
+        .await?;
+    if problems.len() != 1 || problems[0] != "ok" {
+        return Err(AppError::validation("integrity_check", problems.join("; ")));
+    }
+
+    let has_migrations: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name = 'schema_migrations'"
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+    let schema_version: i64 = if has_migrations > 0 {
+        sqlx::query_scalar("SELECT COALESCE(MAX(version), 0) FROM schema_migrations")
+            .fetch_one(&mut *conn)
+            .await?
+    } else {
+        0
+    };
+
+    // FTS tables are derived from transactions and left out
+    let tables: Vec<String> = sqlx::query_scalar(
+        r#"
+        SELECT name FROM sqlite_master
+        WHERE type = 'table' AND name NOT LIKE 'sqlite_%' AND instr(name, '_fts') = 0
+        ORDER BY name
+        "#
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+
+    let mut row_counts = BTreeMap::new();
+    for table in tables {
+        let count: i64 = sqlx::query_scalar(&format!("SELECT COUNT(*) FROM \"{}\"", table.replace('"', "\"\"")))
+            .fetch_one(&mut *conn)
+            .await?;
+        row_counts.insert(table, count);
+    }
+
+    Ok(DatabaseSummary {
+        schema_version,
+        row_counts,
+    })
+}
+
+// backup-2024-05-01_10-00-00.db (auto- for automatic ones), with a counter when two backups
+// land in the same second
+fn unused_backup_path(backup_dir: &Path, prefix: &str, stamp: &str) -> PathBuf {
+    let mut path = backup_dir.join(format!("{}-{}.db", prefix, stamp));
+    let mut counter = 1;
+    while path.exists() {
+        counter += 1;
+        path = backup_dir.join(format!("{}-{}-{}.db", prefix, stamp, counter));
+    }
+    path
+}
+
+pub fn manifest_path(backup_path: &Path) -> PathBuf {
+    backup_path.with_extension("json")
+}
+
+fn write_manifest(backup_path: &Path, manifest: &BackupManifest) -> Result<(), AppError> {
+    let json = serde_json::to_string_pretty(manifest)
+        .map_err(|e| AppError::io(format!("Failed to write backup manifest: {}", e)))?;
+    std::fs::write(manifest_path(backup_path), json)?;
+    Ok(())
+}
+
+pub fn sha256_file(path: &Path) -> Result<String, AppError> {
+    let mut file = std::fs::File::open(path)?;
+    let mut hasher = Sha256::new();
+    let mut buffer = [0u8; 64 * 1024];
+    loop {
+        let read = file.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn local(day: u32, hour: u32) -> DateTime<Utc> {
+        Local.with_ymd_and_hms(2026, 3, day, hour, 0, 0).unwrap().with_timezone(&Utc)
+    }
+
+    fn write_fake_backup(backup_dir: &Path, file_name: &str, created_at: DateTime<Utc>, automatic: bool) {
+        let path = backup_dir.join(file_name);
+        std::fs::write(&path, b"backup").unwrap();
+        let manifest = BackupManifest {
+            app_version: "0.0.0".to_string(),
+            schema_version: latest_version(),
+            created_at,
+            file_name: file_name.to_string(),
+            size_bytes: 6,
+            sha256: String::new(),
+            row_counts: BTreeMap::new(),
+            automatic,
+        };
+        write_manifest(&path, &manifest).unwrap();
+    }
+
+    #[test]
+    fn backup_is_due_once_the_interval_has_passed() {
+        let now = local(10, 12);
+        let interval = Duration::hours(24);
+
+        assert_eq!(time_until_due(None, interval, now, false), None);
+        assert_eq!(time_until_due(Some(now - Duration::hours(1)), interval, now, true), None);
+        assert_eq!(time_until_due(Some(now - Duration::hours(24)), interval, now, false), None);
+        assert_eq!(time_until_due(Some(now - Duration::hours(30)), interval, now, false), None);
+        assert_eq!(
+            time_until_due(Some(now - Duration::hours(20)), interval, now, false),
+            Some(std::time::Duration::from_secs(4 * 3600))
+        );
+    }
+
+    #[test]
+    fn retention_keeps_newest_per_day_and_manual_backups() {
+        let backup_dir = std::env::temp_dir().join(format!("money-zen-retention-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&backup_dir).unwrap();
+        write_fake_backup(&backup_dir, "auto-10-noon.db", local(10, 12), true);
+        write_fake_backup(&backup_dir, "auto-10-morning.db", local(10, 9), true);
+        write_fake_backup(&backup_dir, "auto-09.db", local(9, 12), true);
+        write_fake_backup(&backup_dir, "auto-08.db", local(8, 12), true);
+        write_fake_backup(&backup_dir, "manual-01.db", local(1, 12), false);
+
+        let settings = BackupSettings { enabled: true, interval_hours: 24, keep_daily: 2, keep_weekly: 0, keep_monthly: 0 };
+        let mut removed: Vec<String> = apply_retention(&backup_dir, &settings)
+            .unwrap()
+            .iter()
+            .map(|path| Path::new(path).file_name().unwrap().to_string_lossy().to_string())
+            .collect();
+        removed.sort();
+        assert_eq!(removed, ["auto-08.db", "auto-10-morning.db"]);
+
+        let mut left: Vec<String> = list_backups(&backup_dir).unwrap().into_iter().map(|b| b.manifest.file_name).collect();
+        left.sort();
+        assert_eq!(left, ["auto-09.db", "auto-10-noon.db", "manual-01.db"]);
+        assert!(!manifest_path(&backup_dir.join("auto-08.db")).exists());
+
+        // nothing to keep per period still keeps the newest automatic backup
+        let settings = BackupSettings { keep_daily: 0, ..settings };
+        assert_eq!(apply_retention(&backup_dir, &settings).unwrap().len(), 1);
+        assert_eq!(list_backups(&backup_dir).unwrap().len(), 2);
+
+        std::fs::remove_dir_all(&backup_dir).unwrap();
+    }
+}
@@ -0,0 +1,370 @@
+// budgets.rs
+// Category budgets and the monthly budget vs actual report
+
+use serde::{Deserialize, Serialize};
+use sqlx::{sqlite::SqliteRow, FromRow, QueryBuilder, Row, Sqlite};
+use chrono::{DateTime, Datelike, TimeZone, Utc};
+use uuid::Uuid;
+use crate::database::Database;
+use crate::error::AppError;
+use crate::money::Money;
+use crate::timestamp;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum BudgetPeriod {
+    Monthly,
+    Yearly,
+}
+
+impl BudgetPeriod {
+    fn as_str(&self) -> &'static str {
+        match self {
+            BudgetPeriod::Monthly => "monthly",
+            BudgetPeriod::Yearly => "yearly",
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Budget {
+    pub id: String,
+    pub category_id: String,
+    pub owner: Option<String>,  // only accounts of this owner count
+    pub tag_id: Option<String>, // only transactions with this tag count
+    pub period: BudgetPeriod,
+    pub amount: Money, // per period; only transactions in this currency count
+    pub rollover: bool, // unspent amount carries into the next period
+    pub warning_percent: i64,
+    pub start_date: DateTime<Utc>, // first day of the first period
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct NewBudget {
+    pub category_id: String,
+    pub owner: Option<String>,
+    pub tag_id: Option<String>,
+    pub period: BudgetPeriod,
+    pub amount: Money,
+    pub rollover: bool,
+    pub warning_percent: Option<i64>, // defaults to 80
+    pub start_year: i32,
+    pub start_month: u32,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum BudgetHealth {
+    OnTrack,
+    Warning,  // spent at least warning_percent of what is available
+    Exceeded, // spent more than is available
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct BudgetStatus {
+    pub budget: Budget,
+    pub period_start: DateTime<Utc>,
+    pub period_end: DateTime<Utc>, // exclusive
+    pub rolled_over: Money,        // carried in from earlier periods
+    pub available: Money,          // amount + rolled_over
+    pub spent: Money,              // expenses minus refunds, transfers excluded
+    pub remaining: Money,
+    pub health: BudgetHealth,
+}
+
+const DEFAULT_WARNING_PERCENT: i64 = 80;
+
+impl<'r> FromRow<'r, SqliteRow> for Budget {
+    fn from_row(row: &'r SqliteRow) -> Result<Self, sqlx::Error> {
+        let period: String = row.try_get("period")?;
+        let period = match period.as_str() {
+            "monthly" => BudgetPeriod::Monthly,
+            "yearly" => BudgetPeriod::Yearly,
+            other => {
+                return Err(sqlx::Error::ColumnDecode {
+                    index: "period".to_string(),
+                    source: format!("unknown budget period {:?}", other).into(),
+                })
+            }
+        };
+
+        Ok(Budget {
+            id: row.try_get("id")?,
+            category_id: row.try_get("category_id")?,
+            owner: row.try_get("owner")?,
+            tag_id: row.try_get("tag_id")?,
+            period,
+            amount: Money::new(row.try_get("amount")?, row.try_get::<String, _>("currency")?),
+            rollover: row.try_get("rollover")?,
+            warning_percent: row.try_get("warning_percent")?,
+            start_date: timestamp::decode(row, "start_date")?,
+            created_at: timestamp::decode(row, "created_at")?,
+            updated_at: timestamp::decode(row, "updated_at")?,
+        })
+    }
+}
+
+impl Database {
+    pub async fn create_budget(&self, new_budget: NewBudget) -> Result<Budget, AppError> {
+        let start_date = validate_budget(&new_budget)?;
+        self.ensure_budgetable(&new_budget.category_id).await?;
+        let id = Uuid::new_v4().to_string();
+        let now = timestamp::now();
+
+        sqlx::query(
+            r#"
+            INSERT INTO budgets (id, category_id, owner, tag_id, period, amount, currency, rollover,
+                                 warning_percent, start_date, created_at, updated_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            "#
+        )
+        .bind(&id)
+        .bind(&new_budget.category_id)
+        .bind(&new_budget.owner)
+        .bind(&new_budget.tag_id)
+        .bind(new_budget.period.as_str())
+        .bind(new_budget.amount.minor_units)
+        .bind(&new_budget.amount.currency)
+        .bind(new_budget.rollover)
+        .bind(new_budget.warning_percent.unwrap_or(DEFAULT_WARNING_PERCENT))
+        .bind(timestamp::format(&start_date))
+        .bind(timestamp::format(&now))
+        .bind(timestamp::format(&now))
+        .execute(self.get_pool())
+        .await?;
+
+        self.get_budget(&id).await
+    }
+
+    pub async fn update_budget(&self, id: String, changes: NewBudget) -> Result<Budget, AppError> {
+        let start_date = validate_budget(&changes)?;
+        self.ensure_budgetable(&changes.category_id).await?;
+
+        let result = sqlx::query(
+            r#"
+            UPDATE budgets
+            SET category_id = ?, owner = ?, tag_id = ?, period = ?, amount = ?, currency = ?,
+                rollover = ?, warning_percent = ?, start_date = ?, updated_at = ?
+            WHERE id = ?
+            "#
+        )
+        .bind(&changes.category_id)
+        .bind(&changes.owner)
+        .bind(&changes.tag_id)
+        .bind(changes.period.as_str())
+        .bind(changes.amount.minor_units)
+        .bind(&changes.amount.currency)
+        .bind(changes.rollover)
+        .bind(changes.warning_percent.unwrap_or(DEFAULT_WARNING_PERCENT))
+        .bind(timestamp::format(&start_date))
+        .bind(timestamp::format(&timestamp::now()))
+        .bind(&id)
+        .execute(self.get_pool())
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(AppError::not_found("budget"));
+        }
+
+        self.get_budget(&id).await
+    }
+
+    pub async fn delete_budget(&self, id: String) -> Result<(), AppError> {
+        let result = sqlx::query("DELETE FROM budgets WHERE id = ?")
+            .bind(&id)
+            .execute(self.get_pool())
+            .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(AppError::not_found("budget"));
+        }
+
+        Ok(())
+    }
+
+    pub async fn get_budgets(&self) -> Result<Vec<Budget>, AppError> {
+        let budgets = sqlx::query_as::<_, Budget>("SELECT * FROM budgets ORDER BY category_id, period, created_at")
+            .fetch_all(self.get_pool())
+            .await?;
+
+        Ok(budgets)
+    }
+
+    // Transfers only move money between own accounts, so their category has nothing to budget
+    async fn ensure_budgetable(&self, category_id: &str) -> Result<(), AppError> {
+        let category_type: String = sqlx::query_scalar("SELECT category_type FROM categories WHERE id = ?")
+            .bind(category_id)
+            .fetch_optional(self.get_pool())
+            .await?
+            .ok_or_else(|| AppError::not_found("category"))?;
+        if category_type == "transfer" {
+            return Err(AppError::validation("category_id", "Transfer categories cannot have a budget"));
+        }
+
+        Ok(())
+    }
+
+    async fn get_budget(&self, id: &str) -> Result<Budget, AppError> {
+        sqlx::query_as::<_, Budget>("SELECT * FROM budgets WHERE id = ?")
+            .bind(id)
+            .fetch_optional(self.get_pool())
+            .await?
+            .ok_or_else(|| AppError::not_found("budget"))
+    }
+
+    // Budget vs actual for every budget active in the given month. Yearly budgets
+    // report the year to date, up to the end of that month.
+    pub async fn get_budget_report(&self, year: i32, month: u32) -> Result<Vec<BudgetStatus>, AppError> {
+        let report_month = month_start(year, month)
+            .ok_or_else(|| AppError::validation("month", format!("{} is not a month", month)))?;
+        let mut report = Vec::new();
+
+        for budget in self.get_budgets().await? {
+            if budget.start_date > report_month {
+                continue; // not started yet
+            }
+            let period_start = match budget.period {
+                BudgetPeriod::Monthly => report_month,
+                BudgetPeriod::Yearly => month_start(year, 1).unwrap_or(report_month),
+            };
+            let period_end = add_months(report_month, 1);
+
+            let rolled_over = if budget.rollover {
+                self.budget_carry_over(&budget, period_start).await?
+            } else {
+                0
+            };
+            let spent = self.budget_spent(&budget, period_start, period_end).await?;
+            let available = budget.amount.minor_units + rolled_over;
+            let currency = budget.amount.currency.clone();
+
+            let health = if spent > available {
+                BudgetHealth::Exceeded
+            } else if (spent as i128) * 100 >= (available as i128) * (budget.warning_percent as i128) {
+                BudgetHealth::Warning
+            } else {
+                BudgetHealth::OnTrack
+            };
+
+            report.push(BudgetStatus {
+                period_start,
+                period_end,
+                rolled_over: Money::new(rolled_over, currency.clone()),
+                available: Money::new(available, currency.clone()),
+                spent: Money::new(spent, currency.clone()),
+                remaining: Money::new(available - spent, currency),
+                health,
+                budget,
+            });
+        }
+
+        Ok(report)
+    }
+
+    // Unspent amount carried from the periods between the budget start and `before`.
+    // Overspending in a period resets the carry to zero rather than going negative.
+    async fn budget_carry_over(&self, budget: &Budget, before: DateTime<Utc>) -> Result<i64, AppError> {
+        let period_format = match budget.period {
+            BudgetPeriod::Monthly => "%Y-%m",
+            BudgetPeriod::Yearly => "%Y",
+        };
+
+        let mut query = QueryBuilder::<Sqlite>::new("SELECT strftime(");
+        query.push_bind(period_format);
+        query.push(", date) AS period, ");
+        query.push(SPENT_EXPRESSION);
+        query.push(" AS spent FROM transactions WHERE ");
+        push_budget_scope(&mut query, budget, budget.start_date, before);
+        query.push(" GROUP BY period");
+
+        let spent_by_period: Vec<(String, i64)> = query
+            .build_query_as()
+            .fetch_all(self.get_pool())
+            .await?;
+
+        let mut carry: i64 = 0;
+        let mut period_start = budget.start_date;
+        while period_start < before {
+            let key = period_start.format(period_format).to_string();
+            let spent = spent_by_period
+                .iter()
+                .find(|(period, _)| *period == key)
+                .map(|(_, spent)| *spent)
+                .unwrap_or(0);
+
+            carry = (budget.amount.minor_units + carry - spent).max(0);
+            period_start = match budget.period {
+                BudgetPeriod::Monthly => add_months(period_start, 1),
+                BudgetPeriod::Yearly => add_months(period_start, 12),
+            };
+        }
+
+        Ok(carry)
+    }
+
+    async fn budget_spent(&self, budget: &Budget, from: DateTime<Utc>, to: DateTime<Utc>) -> Result<i64, AppError> {
+        let mut query = QueryBuilder::<Sqlite>::new("SELECT ");
+        query.push(SPENT_EXPRESSION);
+        query.push(" FROM transactions WHERE ");
+        push_budget_scope(&mut query, budget, from, to);
+
+        let spent: i64 = query.build_query_scalar().fetch_one(self.get_pool()).await?;
+        Ok(spent)
+    }
+}
+
+// Refunds booked as income in the same category reduce what was spent
+const SPENT_EXPRESSION: &str =
+    "COALESCE(SUM(CASE WHEN transaction_type = 'expense' THEN amount ELSE -amount END), 0)";
+
+fn push_budget_scope(query: &mut QueryBuilder<'_, Sqlite>, budget: &Budget, from: DateTime<Utc>, to: DateTime<Utc>) {
+    query.push("transfer_id IS NULL AND category_id = ").push_bind(budget.category_id.clone());
+    query.push(" AND currency = ").push_bind(budget.amount.currency.clone());
+    query.push(" AND date >= ").push_bind(timestamp::format(&from));
+    query.push(" AND date < ").push_bind(timestamp::format(&to));
+
+    if let Some(owner) = &budget.owner {
+        query
+            .push(
+                " AND COALESCE(transactions.owner, \
+                 (SELECT owner FROM accounts WHERE accounts.id = transactions.account_id)) = ",
+            )
+            .push_bind(owner.clone());
+    }
+    if let Some(tag_id) = &budget.tag_id {
+        query
+            .push(" AND id IN (SELECT transaction_id FROM transaction_tags WHERE tag_id = ")
+            .push_bind(tag_id.clone())
+            .push(")");
+    }
+}
+
+// Returns the normalized start date
+fn validate_budget(budget: &NewBudget) -> Result<DateTime<Utc>, AppError> {
+    if budget.amount.minor_units <= 0 {
+        return Err(AppError::validation("amount", "Budget amount must be positive"));
+    }
+    if let Some(percent) = budget.warning_percent {
+        if !(1..=100).contains(&percent) {
+            return Err(AppError::validation("warning_percent", "Must be between 1 and 100"));
+        }
+    }
+
+    let start_month = match budget.period {
+        BudgetPeriod::Monthly => budget.start_month,
+        BudgetPeriod::Yearly => 1,
+    };
+    month_start(budget.start_year, start_month)
+        .ok_or_else(|| AppError::validation("start_month", format!("{} is not a month", budget.start_month)))
+}
+
+fn month_start(year: i32, month: u32) -> Option<DateTime<Utc>> {
+    Utc.with_ymd_and_hms(year, month, 1, 0, 0, 0).single()
+}
+
+fn add_months(date: DateTime<Utc>, months: i32) -> DateTime<Utc> {
+    let index = date.year() * 12 + date.month0() as i32 + months;
+    month_start(index.div_euclid(12), index.rem_euclid(12) as u32 + 1).unwrap_or(date)
+}
@@ -1,15 +1,19 @@
 use serde::{Deserialize, Serialize};
-use sqlx::{sqlite::SqlitePool, Row};
+use sqlx::{sqlite::{Sqlite, SqliteConnection, SqlitePool, SqliteRow}, FromRow, Row};
 use chrono::{DateTime, Utc};
 use uuid::Uuid;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use crate::error::AppError;
+use crate::migrations::run_migrations;
+use crate::money::Money;
+use crate::timestamp;
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Account {
     pub id: String,
     pub name: String,
     pub account_type: String,
-    pub balance: f64,
+    pub balance: Money,
     pub currency: String,
     pub owner: Option<String>,
     pub created_at: DateTime<Utc>,
@@ -22,7 +26,7 @@ pub struct Category {
     pub name: String,
     pub color: String,
     pub icon: String,
-    pub category_type: String, // "income" or "expense"
+    pub category_type: String, // "income", "expense" or "transfer"
     pub created_at: DateTime<Utc>,
 }
 
@@ -40,130 +44,178 @@ pub struct Transaction {
     pub id: String,
     pub account_id: String,
     pub category_id: String,
-    pub amount: f64,
+    pub amount: Money,
+    pub description: String,
+    pub transaction_type: String, // "income" or "expense"
+    pub transfer_id: Option<String>, // set on both legs of a transfer
+    pub external_id: Option<String>, // the bank's id when imported from a statement
+    pub counterparty_name: Option<String>,
+    pub counterparty_iban: Option<String>,
+    pub date: DateTime<Utc>, // booking date
+    pub value_date: Option<DateTime<Utc>>,
+    pub cleared_status: String, // "uncleared", "cleared" or "reconciled"
+    pub owner: Option<String>,  // set when it differs from the account owner
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+// Fields of a transaction as supplied by the caller, before id and timestamps are assigned
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct NewTransaction {
+    pub account_id: String,
+    pub category_id: String,
+    pub amount: Money,
     pub description: String,
     pub transaction_type: String, // "income" or "expense"
     pub date: DateTime<Utc>,
+}
+
+// A movement between two own accounts: an expense leg on the source and an income leg
+// on the destination, possibly in different currencies
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Transfer {
+    pub id: String,
+    pub from_transaction: Transaction,
+    pub to_transaction: Transaction,
+    pub exchange_rate: Option<String>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct NewTransfer {
+    pub from_account_id: String,
+    pub to_account_id: String,
+    pub from_amount: Money,
+    pub to_amount: Option<Money>,      // required unless the currencies match or a rate is given
+    pub exchange_rate: Option<String>, // destination units per source unit, e.g. "4.9750"
+    pub category_id: Option<String>,   // defaults to "Transfer intern"
+    pub description: String,
+    pub date: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct BalanceDiscrepancy {
+    pub account_id: String,
+    pub account_name: String,
+    pub stored: Money,
+    pub computed: Money,
+    pub difference: Money,
+}
+
+// Row decoding shared by every query returning these models. Written by hand rather than
+// derived because amounts are split over two columns and timestamps go through timestamp::decode.
+impl<'r> FromRow<'r, SqliteRow> for Account {
+    fn from_row(row: &'r SqliteRow) -> Result<Self, sqlx::Error> {
+        let currency: String = row.try_get("currency")?;
+
+        Ok(Account {
+            id: row.try_get("id")?,
+            name: row.try_get("name")?,
+            account_type: row.try_get("account_type")?,
+            balance: Money::new(row.try_get("balance")?, currency.clone()),
+            currency,
+            owner: row.try_get("owner")?,
+            created_at: timestamp::decode(row, "created_at")?,
+            updated_at: timestamp::decode(row, "updated_at")?,
+        })
+    }
+}
+
+impl<'r> FromRow<'r, SqliteRow> for Category {
+    fn from_row(row: &'r SqliteRow) -> Result<Self, sqlx::Error> {
+        Ok(Category {
+            id: row.try_get("id")?,
+            name: row.try_get("name")?,
+            color: row.try_get("color")?,
+            icon: row.try_get("icon")?,
+            category_type: row.try_get("category_type")?,
+            created_at: timestamp::decode(row, "created_at")?,
+        })
+    }
+}
+
+impl<'r> FromRow<'r, SqliteRow> for Tag {
+    fn from_row(row: &'r SqliteRow) -> Result<Self, sqlx::Error> {
+        Ok(Tag {
+            id: row.try_get("id")?,
+            name: row.try_get("name")?,
+            color: row.try_get("color")?,
+            icon: row.try_get("icon")?,
+            created_at: timestamp::decode(row, "created_at")?,
+        })
+    }
+}
+
+impl<'r> FromRow<'r, SqliteRow> for Transaction {
+    fn from_row(row: &'r SqliteRow) -> Result<Self, sqlx::Error> {
+        let value_date = match row.try_get::<Option<String>, _>("value_date")? {
+            Some(_) => Some(timestamp::decode(row, "value_date")?),
+            None => None,
+        };
+
+        Ok(Transaction {
+            id: row.try_get("id")?,
+            account_id: row.try_get("account_id")?,
+            category_id: row.try_get("category_id")?,
+            amount: Money::new(row.try_get("amount")?, row.try_get::<String, _>("currency")?),
+            description: row.try_get("description")?,
+            transaction_type: row.try_get("transaction_type")?,
+            transfer_id: row.try_get("transfer_id")?,
+            external_id: row.try_get("external_id")?,
+            counterparty_name: row.try_get("counterparty_name")?,
+            counterparty_iban: row.try_get("counterparty_iban")?,
+            date: timestamp::decode(row, "date")?,
+            value_date,
+            cleared_status: row.try_get("cleared_status")?,
+            owner: row.try_get("owner")?,
+            created_at: timestamp::decode(row, "created_at")?,
+            updated_at: timestamp::decode(row, "updated_at")?,
+        })
+    }
+}
+
 pub struct Database {
     pool: SqlitePool,
+    path: PathBuf,
 }
 
 impl Database {
-    pub async fn new(database_path: PathBuf) -> Result<Self, sqlx::Error> {
+    pub async fn new(database_path: PathBuf) -> Result<Self, AppError> {
         if let Some(parent) = database_path.parent() {
             std::fs::create_dir_all(parent)
-                .map_err(|e| sqlx::Error::Io(e))?;
+                .map_err(AppError::from)?;
         }
         let database_url = format!("sqlite://{}?mode=rwc", database_path.display());
         let pool = SqlitePool::connect(&database_url).await?;
-        Ok(Database { pool })
+        Ok(Database { pool, path: database_path })
     }
 
     pub fn get_pool(&self) -> &SqlitePool {
         &self.pool
     }
 
-    pub async fn init_schema(&self) -> Result<(), sqlx::Error> {
-        // Create accounts table
-        sqlx::query(
-            r#"
-            CREATE TABLE IF NOT EXISTS accounts (
-                id TEXT PRIMARY KEY,
-                name TEXT NOT NULL,
-                account_type TEXT NOT NULL,
-                balance REAL NOT NULL DEFAULT 0.0,
-                currency TEXT NOT NULL DEFAULT 'RON',
-                created_at TEXT NOT NULL,
-                updated_at TEXT NOT NULL
-            )
-            "#
-        )
-        .execute(&self.pool)
-        .await?;
-
-        // Create categories table
-        sqlx::query(
-            r#"
-            CREATE TABLE IF NOT EXISTS categories (
-                id TEXT PRIMARY KEY,
-                name TEXT NOT NULL,
-                color TEXT NOT NULL DEFAULT '#3B82F6',
-                icon TEXT NOT NULL DEFAULT '💰',
-                category_type TEXT NOT NULL CHECK (category_type IN ('income', 'expense')),
-                created_at TEXT NOT NULL
-            )
-            "#
-        )
-        .execute(&self.pool)
-        .await?;
-
-        // Create transactions table
-        sqlx::query(
-            r#"
-            CREATE TABLE IF NOT EXISTS transactions (
-                id TEXT PRIMARY KEY,
-                account_id TEXT NOT NULL,
-                category_id TEXT NOT NULL,
-                amount REAL NOT NULL,
-                description TEXT NOT NULL,
-                transaction_type TEXT NOT NULL CHECK (transaction_type IN ('income', 'expense')),
-                date TEXT NOT NULL,
-                created_at TEXT NOT NULL,
-                updated_at TEXT NOT NULL,
-                FOREIGN KEY (account_id) REFERENCES accounts (id) ON DELETE CASCADE,
-                FOREIGN KEY (category_id) REFERENCES categories (id) ON DELETE RESTRICT
-            )
-            "#
-        )
-        .execute(&self.pool)
-        .await?;
-
-        // Create tags table
-        sqlx::query(
-            r#"
-            CREATE TABLE IF NOT EXISTS tags (
-                id TEXT PRIMARY KEY,
-                name TEXT NOT NULL UNIQUE,
-                color TEXT NOT NULL DEFAULT '#8B5CF6',
-                icon TEXT NOT NULL DEFAULT '🏷️',
-                created_at TEXT NOT NULL
-            )
-            "#
-        )
-        .execute(&self.pool)
-        .await?;
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
 
-        // Create transaction-tags junction table
-        sqlx::query(
-            r#"
-            CREATE TABLE IF NOT EXISTS transaction_tags (
-                transaction_id TEXT NOT NULL,
-                tag_id TEXT NOT NULL,
-                created_at TEXT NOT NULL,
-                PRIMARY KEY (transaction_id, tag_id),
-                FOREIGN KEY (transaction_id) REFERENCES transactions (id) ON DELETE CASCADE,
-                FOREIGN KEY (tag_id) REFERENCES tags (id) ON DELETE CASCADE
-            )
-            "#
-        )
-        .execute(&self.pool)
-        .await?;
+    // Closes every pooled connection; the Database must be replaced before it is used again
+    pub async fn close(&self) {
+        self.pool.close().await;
+    }
 
+    pub async fn init_schema(&self) -> Result<(), AppError> {
+        // Bring the schema up to date
+        run_migrations(&self.pool).await?;
         // Insert default categories if none exist
         self.insert_default_categories().await?;
-        // Run migrations
-        self.run_migrations().await?;
         // Insert N-OMAD accounts if none exist
         self.insert_nomad_accounts().await?;
 
         Ok(())
     }
 
-    async fn insert_default_categories(&self) -> Result<(), sqlx::Error> {
+    async fn insert_default_categories(&self) -> Result<(), AppError> {
         let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM categories")
             .fetch_one(&self.pool)
             .await?;
@@ -184,7 +236,7 @@ impl Database {
 
             for (icon, name, color, category_type) in default_categories {
                 let id = Uuid::new_v4().to_string();
-                let now = Utc::now();
+                let now = timestamp::now();
 
                 sqlx::query(
                     r#"
@@ -197,7 +249,7 @@ impl Database {
                 .bind(color)
                 .bind(icon)
                 .bind(category_type)
-                .bind(now.to_rfc3339())
+                .bind(timestamp::format(&now))
                 .execute(&self.pool)
                 .await?;
             }
@@ -207,22 +259,22 @@ impl Database {
     }
 
     // Account operations
-    pub async fn create_account(&self, name: String, account_type: String, currency: String) -> Result<Account, sqlx::Error> {
+    pub async fn create_account(&self, name: String, account_type: String, currency: String) -> Result<Account, AppError> {
         let id = Uuid::new_v4().to_string();
-        let now = Utc::now();
+        let now = timestamp::now();
 
         sqlx::query(
             r#"
             INSERT INTO accounts (id, name, account_type, balance, currency, created_at, updated_at)
-            VALUES (?, ?, ?, 0.0, ?, ?, ?)
+            VALUES (?, ?, ?, 0, ?, ?, ?)
             "#
         )
         .bind(&id)
         .bind(&name)
         .bind(&account_type)
         .bind(&currency)
-        .bind(now.to_rfc3339())
-        .bind(now.to_rfc3339())
+        .bind(timestamp::format(&now))
+        .bind(timestamp::format(&now))
         .execute(&self.pool)
         .await?;
 
@@ -230,7 +282,7 @@ impl Database {
             id,
             name,
             account_type,
-            balance: 0.0,
+            balance: Money::zero(&currency),
             currency,
             owner: Some("Ioan".to_string()),
             created_at: now,
@@ -238,37 +290,33 @@ impl Database {
         })
     }
 
-    pub async fn get_accounts(&self) -> Result<Vec<Account>, sqlx::Error> {
-        let rows = sqlx::query("SELECT * FROM accounts ORDER BY created_at DESC")
+    pub async fn get_accounts(&self) -> Result<Vec<Account>, AppError> {
+        let accounts = sqlx::query_as::<_, Account>("SELECT * FROM accounts ORDER BY created_at DESC")
             .fetch_all(&self.pool)
             .await?;
 
-        let accounts = rows.into_iter().map(|row| {
-            Account {
-                id: row.get("id"),
-                name: row.get("name"),
-                account_type: row.get("account_type"),
-                balance: row.get("balance"),
-                currency: row.get("currency"),
-                owner: row.get("owner"),
-                created_at: {
-                    let datetime_str: String = row.get("created_at");
-                    chrono::NaiveDateTime::parse_from_str(&datetime_str, "%Y-%m-%d %H:%M:%S").unwrap_or_else(|_| chrono::DateTime::from_timestamp(0, 0).unwrap().naive_utc()).and_utc()
-                },
-                updated_at: {
-                    let datetime_str: String = row.get("updated_at");
-                    chrono::NaiveDateTime::parse_from_str(&datetime_str, "%Y-%m-%d %H:%M:%S").unwrap_or_else(|_| chrono::DateTime::from_timestamp(0, 0).unwrap().naive_utc()).and_utc()
-                },
-            }
-        }).collect();
-
         Ok(accounts)
     }
 
-    pub async fn update_account(&self, id: String, name: String, account_type: String, currency: String) -> Result<Account, sqlx::Error> {
-        let now = Utc::now().to_rfc3339();
+    pub async fn update_account(&self, id: String, name: String, account_type: String, currency: String) -> Result<Account, AppError> {
+        let now = timestamp::format(&timestamp::now());
 
-        sqlx::query(
+        // Balance and amounts are minor units of the old currency, so they would be misread
+        let current: Option<String> = sqlx::query_scalar("SELECT currency FROM accounts WHERE id = ?")
+            .bind(&id)
+            .fetch_optional(&self.pool)
+            .await?;
+        if current.as_deref().is_some_and(|current| current != currency) {
+            let transaction_count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM transactions WHERE account_id = ?")
+                .bind(&id)
+                .fetch_one(&self.pool)
+                .await?;
+            if transaction_count > 0 {
+                return Err(AppError::validation("currency", "cannot change while the account has transactions"));
+            }
+        }
+
+        let result = sqlx::query(
             "UPDATE accounts SET name = ?, account_type = ?, currency = ?, updated_at = ? WHERE id = ?"
         )
         .bind(&name)
@@ -279,90 +327,125 @@ impl Database {
         .execute(&self.pool)
         .await?;
 
+        if result.rows_affected() == 0 {
+            return Err(AppError::not_found("account"));
+        }
+
         // Return the updated account
-        let row = sqlx::query("SELECT * FROM accounts WHERE id = ?")
+        let account = sqlx::query_as::<_, Account>("SELECT * FROM accounts WHERE id = ?")
             .bind(&id)
             .fetch_one(&self.pool)
             .await?;
 
-        Ok(Account {
-            id: row.get("id"),
-            name: row.get("name"),
-            account_type: row.get("account_type"),
-            balance: row.get("balance"),
-            currency: row.get("currency"),
-            owner: row.get("owner"),
-            created_at: {
-                    let datetime_str: String = row.get("created_at");
-                    chrono::NaiveDateTime::parse_from_str(&datetime_str, "%Y-%m-%d %H:%M:%S").unwrap_or_else(|_| chrono::DateTime::from_timestamp(0, 0).unwrap().naive_utc()).and_utc()
-                },
-            updated_at: {
-                    let datetime_str: String = row.get("updated_at");
-                    chrono::NaiveDateTime::parse_from_str(&datetime_str, "%Y-%m-%d %H:%M:%S").unwrap_or_else(|_| chrono::DateTime::from_timestamp(0, 0).unwrap().naive_utc()).and_utc()
-                },
-        })
+        Ok(account)
     }
 
-    pub async fn delete_account(&self, id: String) -> Result<(), sqlx::Error> {
+    pub async fn delete_account(&self, id: String) -> Result<(), AppError> {
+        let mut tx = self.pool.begin().await?;
+
+        // The other side of a transfer really happened, so keep it as a plain transaction
+        let transfer_ids: Vec<String> = sqlx::query_scalar(
+            "SELECT DISTINCT transfer_id FROM transactions WHERE account_id = ? AND transfer_id IS NOT NULL"
+        )
+        .bind(&id)
+        .fetch_all(&mut *tx)
+        .await?;
+
+        for transfer_id in transfer_ids {
+            sqlx::query("DELETE FROM transfers WHERE id = ?")
+                .bind(&transfer_id)
+                .execute(&mut *tx)
+                .await?;
+            sqlx::query("UPDATE transactions SET transfer_id = NULL WHERE transfer_id = ?")
+                .bind(&transfer_id)
+                .execute(&mut *tx)
+                .await?;
+        }
+
         // Note: Due to CASCADE constraint, deleting account will also delete all associated transactions
-        sqlx::query("DELETE FROM accounts WHERE id = ?")
+        let result = sqlx::query("DELETE FROM accounts WHERE id = ?")
             .bind(&id)
-            .execute(&self.pool)
+            .execute(&mut *tx)
             .await?;
 
+        if result.rows_affected() == 0 {
+            return Err(AppError::not_found("account"));
+        }
+
+        tx.commit().await?;
+
         Ok(())
     }
 
+    // Unit of work: begin a SQL transaction, pass `&mut tx` to the `*_in` operations
+    // below and commit when done. Dropping it without commit rolls everything back.
+    pub async fn begin(&self) -> Result<sqlx::Transaction<'static, Sqlite>, AppError> {
+        Ok(self.pool.begin().await?)
+    }
+
     // Transaction operations
     pub async fn create_transaction(
         &self,
         account_id: String,
         category_id: String,
-        amount: f64,
+        amount: Money,
         description: String,
         transaction_type: String,
         date: DateTime<Utc>,
         tag_ids: Option<Vec<String>>,
-    ) -> Result<Transaction, sqlx::Error> {
+    ) -> Result<Transaction, AppError> {
+        let new_transaction = NewTransaction {
+            account_id,
+            category_id,
+            amount,
+            description,
+            transaction_type,
+            date,
+        };
+
+        let mut tx = self.pool.begin().await?;
+        let transaction = Self::create_transaction_in(&mut tx, new_transaction, &tag_ids.unwrap_or_default()).await?;
+        tx.commit().await?;
+
+        Ok(transaction)
+    }
+
+    pub async fn create_transaction_in(
+        conn: &mut SqliteConnection,
+        new_transaction: NewTransaction,
+        tag_ids: &[String],
+    ) -> Result<Transaction, AppError> {
+        let NewTransaction { account_id, category_id, amount, description, transaction_type, date } = new_transaction;
         let id = Uuid::new_v4().to_string();
-        let now = Utc::now();
+        let now = timestamp::now();
+
+        Self::ensure_account_currency(conn, &account_id, &amount).await?;
 
         // Insert transaction
         sqlx::query(
             r#"
-            INSERT INTO transactions (id, account_id, category_id, amount, description, transaction_type, date, created_at, updated_at)
-            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+            INSERT INTO transactions (id, account_id, category_id, amount, currency, description, transaction_type, date, created_at, updated_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
             "#
         )
         .bind(&id)
         .bind(&account_id)
         .bind(&category_id)
-        .bind(amount)
+        .bind(amount.minor_units)
+        .bind(&amount.currency)
         .bind(&description)
         .bind(&transaction_type)
-        .bind(date.to_rfc3339())
-        .bind(now.to_rfc3339())
-        .bind(now.to_rfc3339())
-        .execute(&self.pool)
+        .bind(timestamp::format(&date))
+        .bind(timestamp::format(&now))
+        .bind(timestamp::format(&now))
+        .execute(&mut *conn)
         .await?;
 
         // Update account balance
-        let balance_change = if transaction_type == "income" { amount } else { -amount };
-        sqlx::query(
-            "UPDATE accounts SET balance = balance + ?, updated_at = ? WHERE id = ?"
-        )
-        .bind(balance_change)
-        .bind(now.to_rfc3339())
-        .bind(&account_id)
-        .execute(&self.pool)
-        .await?;
+        Self::adjust_balance(conn, &account_id, balance_effect(&transaction_type, &amount)).await?;
 
         // Add tags if provided
-        if let Some(tag_ids) = tag_ids {
-            if !tag_ids.is_empty() {
-                self.add_tags_to_transaction(id.clone(), tag_ids).await?;
-            }
-        }
+        Self::add_tags_to_transaction_in(conn, &id, tag_ids).await?;
 
         Ok(Transaction {
             id,
@@ -371,7 +454,14 @@ impl Database {
             amount,
             description,
             transaction_type,
+            transfer_id: None,
+            external_id: None,
+            counterparty_name: None,
+            counterparty_iban: None,
             date,
+            value_date: None,
+            cleared_status: "uncleared".to_string(),
+            owner: None,
             created_at: now,
             updated_at: now,
         })
@@ -382,91 +472,230 @@ impl Database {
         id: String,
         account_id: String,
         category_id: String,
-        amount: f64,
+        amount: Money,
         description: String,
         transaction_type: String,
         date: DateTime<Utc>,
-    ) -> Result<Transaction, sqlx::Error> {
-        let now = Utc::now();
+    ) -> Result<Transaction, AppError> {
+        let changes = NewTransaction {
+            account_id,
+            category_id,
+            amount,
+            description,
+            transaction_type,
+            date,
+        };
+
+        let mut tx = self.pool.begin().await?;
+        Self::update_transaction_in(&mut tx, &id, changes).await?;
+        tx.commit().await?;
+
+        // Return the updated transaction
+        let transaction = sqlx::query_as::<_, Transaction>("SELECT * FROM transactions WHERE id = ?")
+            .bind(&id)
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(transaction)
+    }
+
+    pub async fn update_transaction_in(
+        conn: &mut SqliteConnection,
+        id: &str,
+        changes: NewTransaction,
+    ) -> Result<(), AppError> {
+        if Self::transfer_id_of(conn, id).await?.is_some() {
+            return Err(AppError::conflict("Transaction is part of a transfer, edit the transfer instead"));
+        }
+
+        Self::update_ledger_row(conn, id, changes).await
+    }
+
+    // Rewrites a stored transaction and moves its balance effect, without transfer checks
+    async fn update_ledger_row(
+        conn: &mut SqliteConnection,
+        id: &str,
+        changes: NewTransaction,
+    ) -> Result<(), AppError> {
+        let now = timestamp::now();
+
+        Self::ensure_account_currency(conn, &changes.account_id, &changes.amount).await?;
+
+        // Reverse the old balance effect (the account may change) before applying the new one
+        if !Self::reverse_balance_effect(conn, id).await? {
+            return Err(AppError::not_found("transaction"));
+        }
 
         sqlx::query(
             r#"
             UPDATE transactions
-            SET account_id = ?, category_id = ?, amount = ?, description = ?,
+            SET account_id = ?, category_id = ?, amount = ?, currency = ?, description = ?,
                 transaction_type = ?, date = ?, updated_at = ?
             WHERE id = ?
             "#
         )
-        .bind(&account_id)
-        .bind(&category_id)
-        .bind(amount)
-        .bind(&description)
-        .bind(&transaction_type)
-        .bind(date.to_rfc3339())
-        .bind(now.to_rfc3339())
-        .bind(&id)
-        .execute(&self.pool)
+        .bind(&changes.account_id)
+        .bind(&changes.category_id)
+        .bind(changes.amount.minor_units)
+        .bind(&changes.amount.currency)
+        .bind(&changes.description)
+        .bind(&changes.transaction_type)
+        .bind(timestamp::format(&changes.date))
+        .bind(timestamp::format(&now))
+        .bind(id)
+        .execute(&mut *conn)
         .await?;
 
-        // Return the updated transaction
-        let row = sqlx::query("SELECT * FROM transactions WHERE id = ?")
-            .bind(&id)
-            .fetch_one(&self.pool)
+        Self::adjust_balance(conn, &changes.account_id, balance_effect(&changes.transaction_type, &changes.amount)).await?;
+
+        Ok(())
+    }
+
+    pub(crate) async fn ensure_account_currency(conn: &mut SqliteConnection, account_id: &str, amount: &Money) -> Result<(), AppError> {
+        let currency: String = sqlx::query_scalar("SELECT currency FROM accounts WHERE id = ?")
+            .bind(account_id)
+            .fetch_optional(&mut *conn)
+            .await?
+            .ok_or_else(|| AppError::not_found("account"))?;
+
+        Ok(amount.ensure_currency(&currency)?)
+    }
+
+    async fn adjust_balance(conn: &mut SqliteConnection, account_id: &str, delta: i64) -> Result<(), AppError> {
+        sqlx::query("UPDATE accounts SET balance = balance + ?, updated_at = ? WHERE id = ?")
+            .bind(delta)
+            .bind(timestamp::format(&timestamp::now()))
+            .bind(account_id)
+            .execute(&mut *conn)
             .await?;
 
-        Ok(Transaction {
-            id: row.get("id"),
-            account_id: row.get("account_id"),
-            category_id: row.get("category_id"),
-            amount: row.get("amount"),
-            description: row.get("description"),
-            transaction_type: row.get("transaction_type"),
-            date: DateTime::parse_from_rfc3339(&row.get::<String, _>("date")).unwrap().with_timezone(&Utc),
-            created_at: {
-                let datetime_str: String = row.get("created_at");
-                chrono::NaiveDateTime::parse_from_str(&datetime_str, "%Y-%m-%d %H:%M:%S")
-                    .unwrap_or_else(|_| chrono::DateTime::from_timestamp(0, 0).unwrap().naive_utc())
-                    .and_utc()
-            },
-            updated_at: {
-                let datetime_str: String = row.get("updated_at");
-                chrono::NaiveDateTime::parse_from_str(&datetime_str, "%Y-%m-%d %H:%M:%S")
-                    .unwrap_or_else(|_| chrono::DateTime::from_timestamp(0, 0).unwrap().naive_utc())
-                    .and_utc()
-            },
-        })
+        Ok(())
+    }
+
+    // Undo a stored transaction's effect on its account balance. Returns false if it does not exist.
+    async fn reverse_balance_effect(conn: &mut SqliteConnection, transaction_id: &str) -> Result<bool, AppError> {
+        let row = sqlx::query("SELECT account_id, amount, currency, transaction_type FROM transactions WHERE id = ?")
+            .bind(transaction_id)
+            .fetch_optional(&mut *conn)
+            .await?;
+
+        let Some(row) = row else {
+            return Ok(false);
+        };
+
+        let account_id: String = row.get("account_id");
+        let transaction_type: String = row.get("transaction_type");
+        let amount = Money::new(row.get("amount"), row.get::<String, _>("currency"));
+
+        Self::adjust_balance(conn, &account_id, -balance_effect(&transaction_type, &amount)).await?;
+
+        Ok(true)
+    }
+
+    /// Rebuilds every account balance from the transaction ledger and reports the accounts that were off.
+    pub async fn recalculate_balances(&self) -> Result<Vec<BalanceDiscrepancy>, AppError> {
+        let mut tx = self.pool.begin().await?;
+
+        let rows = sqlx::query(
+            r#"
+            SELECT a.id, a.name, a.balance, a.currency,
+                   COALESCE(SUM(CASE WHEN t.transaction_type = 'income' THEN t.amount ELSE -t.amount END), 0) AS computed
+            FROM accounts a
+            LEFT JOIN transactions t ON t.account_id = a.id
+            GROUP BY a.id
+            ORDER BY a.name
+            "#
+        )
+        .fetch_all(&mut *tx)
+        .await?;
+
+        let now = timestamp::format(&timestamp::now());
+        let mut discrepancies = Vec::new();
+
+        for row in rows {
+            let stored: i64 = row.get("balance");
+            let computed: i64 = row.get("computed");
+
+            if stored == computed {
+                continue;
+            }
+
+            let account_id: String = row.get("id");
+            let currency: String = row.get("currency");
+
+            sqlx::query("UPDATE accounts SET balance = ?, updated_at = ? WHERE id = ?")
+                .bind(computed)
+                .bind(&now)
+                .bind(&account_id)
+                .execute(&mut *tx)
+                .await?;
+
+            discrepancies.push(BalanceDiscrepancy {
+                account_id,
+                account_name: row.get("name"),
+                stored: Money::new(stored, currency.clone()),
+                computed: Money::new(computed, currency.clone()),
+                difference: Money::new(computed - stored, currency),
+            });
+        }
+
+        tx.commit().await?;
+
+        Ok(discrepancies)
     }
 
     pub async fn delete_transaction(
         &self,
         id: String,
-    ) -> Result<(), sqlx::Error> {
+    ) -> Result<(), AppError> {
+        let mut tx = self.pool.begin().await?;
+        if !Self::delete_transaction_in(&mut tx, &id).await? {
+            return Err(AppError::not_found("transaction"));
+        }
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    // Deletes a transaction and reverses its balance effect. Deleting either leg of a transfer
+    // deletes the whole transfer. Returns false if it did not exist.
+    pub async fn delete_transaction_in(conn: &mut SqliteConnection, id: &str) -> Result<bool, AppError> {
+        if let Some(transfer_id) = Self::transfer_id_of(conn, id).await? {
+            return Self::delete_transfer_in(conn, &transfer_id).await;
+        }
+
+        Self::delete_ledger_row(conn, id).await
+    }
+
+    async fn delete_ledger_row(conn: &mut SqliteConnection, id: &str) -> Result<bool, AppError> {
+        if !Self::reverse_balance_effect(conn, id).await? {
+            return Ok(false);
+        }
+
+        // Cascade deletes transaction_tags automatically
         sqlx::query("DELETE FROM transactions WHERE id = ?")
-            .bind(&id)
-            .execute(&self.pool)
+            .bind(id)
+            .execute(&mut *conn)
             .await?;
 
-        Ok(())
+        Ok(true)
     }
 
     pub async fn delete_multiple_transactions(
         &self,
         transaction_ids: Vec<String>,
-    ) -> Result<usize, sqlx::Error> {
+    ) -> Result<usize, AppError> {
         let mut deleted_count: usize = 0;
+        let mut tx = self.pool.begin().await?;
 
         for transaction_id in transaction_ids {
-            // Delete transaction (cascade deletes transaction_tags automatically)
-            let result = sqlx::query("DELETE FROM transactions WHERE id = ?")
-                .bind(&transaction_id)
-                .execute(&self.pool)
-                .await?;
-
-            if result.rows_affected() > 0 {
+            if Self::delete_transaction_in(&mut tx, &transaction_id).await? {
                 deleted_count += 1;
             }
         }
 
+        tx.commit().await?;
+
         Ok(deleted_count)
     }
 
@@ -475,89 +704,321 @@ impl Database {
         transaction_ids: Vec<String>,
         tags_to_add: Option<Vec<String>>,
         tags_to_remove: Option<Vec<String>>,
-    ) -> Result<usize, sqlx::Error> {
+    ) -> Result<usize, AppError> {
+        let tags_to_add = tags_to_add.unwrap_or_default();
+        let tags_to_remove = tags_to_remove.unwrap_or_default();
         let mut updated_count: usize = 0;
+        let mut tx = self.pool.begin().await?;
 
         for transaction_id in transaction_ids {
-            // Remove tags if specified
-            if let Some(ref tags_to_remove) = tags_to_remove {
-                for tag_id in tags_to_remove {
-                    sqlx::query("DELETE FROM transaction_tags WHERE transaction_id = ? AND tag_id = ?")
-                        .bind(&transaction_id)
-                        .bind(tag_id)
-                        .execute(&self.pool)
-                        .await?;
-                }
-            }
-
-            // Add tags if specified
-            if let Some(ref tags_to_add) = tags_to_add {
-                for tag_id in tags_to_add {
-                    // Insert only if not already exists (ignore duplicates)
-                    sqlx::query("INSERT OR IGNORE INTO transaction_tags (transaction_id, tag_id) VALUES (?, ?)")
-                        .bind(&transaction_id)
-                        .bind(tag_id)
-                        .execute(&self.pool)
-                        .await?;
-                }
-            }
+            Self::remove_tags_from_transaction_in(&mut tx, &transaction_id, &tags_to_remove).await?;
+            Self::add_tags_to_transaction_in(&mut tx, &transaction_id, &tags_to_add).await?;
 
             updated_count += 1;
         }
 
+        tx.commit().await?;
+
         Ok(updated_count)
     }
 
-    pub async fn get_transactions(&self) -> Result<Vec<Transaction>, sqlx::Error> {
-        let rows = sqlx::query("SELECT * FROM transactions ORDER BY date DESC, created_at DESC")
-            .fetch_all(&self.pool)
+    // Transfer operations
+    pub async fn create_transfer(&self, new_transfer: NewTransfer) -> Result<Transfer, AppError> {
+        let mut tx = self.pool.begin().await?;
+        let transfer = Self::create_transfer_in(&mut tx, new_transfer).await?;
+        tx.commit().await?;
+
+        Ok(transfer)
+    }
+
+    pub async fn create_transfer_in(conn: &mut SqliteConnection, new_transfer: NewTransfer) -> Result<Transfer, AppError> {
+        let (debit, credit, exchange_rate) = Self::transfer_legs(conn, new_transfer).await?;
+
+        let id = Uuid::new_v4().to_string();
+        let now = timestamp::now();
+
+        let from_transaction = Self::create_transaction_in(conn, debit, &[]).await?;
+        let to_transaction = Self::create_transaction_in(conn, credit, &[]).await?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO transfers (id, from_transaction_id, to_transaction_id, exchange_rate, created_at, updated_at)
+            VALUES (?, ?, ?, ?, ?, ?)
+            "#
+        )
+        .bind(&id)
+        .bind(&from_transaction.id)
+        .bind(&to_transaction.id)
+        .bind(&exchange_rate)
+        .bind(timestamp::format(&now))
+        .bind(timestamp::format(&now))
+        .execute(&mut *conn)
+        .await?;
+
+        sqlx::query("UPDATE transactions SET transfer_id = ? WHERE id IN (?, ?)")
+            .bind(&id)
+            .bind(&from_transaction.id)
+            .bind(&to_transaction.id)
+            .execute(&mut *conn)
             .await?;
 
-        let transactions = rows.into_iter().map(|row| {
-            Transaction {
-                id: row.get("id"),
-                account_id: row.get("account_id"),
-                category_id: row.get("category_id"),
-                amount: row.get("amount"),
-                description: row.get("description"),
-                transaction_type: row.get("transaction_type"),
-                date: DateTime::parse_from_rfc3339(&row.get::<String, _>("date")).unwrap().with_timezone(&Utc),
-                created_at: {
-                    let datetime_str: String = row.get("created_at");
-                    chrono::NaiveDateTime::parse_from_str(&datetime_str, "%Y-%m-%d %H:%M:%S").unwrap_or_else(|_| chrono::DateTime::from_timestamp(0, 0).unwrap().naive_utc()).and_utc()
-                },
-                updated_at: {
-                    let datetime_str: String = row.get("updated_at");
-                    chrono::NaiveDateTime::parse_from_str(&datetime_str, "%Y-%m-%d %H:%M:%S").unwrap_or_else(|_| chrono::DateTime::from_timestamp(0, 0).unwrap().naive_utc()).and_utc()
-                },
+        Ok(Transfer {
+            id: id.clone(),
+            from_transaction: Transaction { transfer_id: Some(id.clone()), ..from_transaction },
+            to_transaction: Transaction { transfer_id: Some(id), ..to_transaction },
+            exchange_rate,
+            created_at: now,
+            updated_at: now,
+        })
+    }
+
+    pub async fn update_transfer(&self, id: String, changes: NewTransfer) -> Result<Transfer, AppError> {
+        let mut tx = self.pool.begin().await?;
+
+        let (from_transaction_id, to_transaction_id) = Self::transfer_leg_ids(&mut tx, &id)
+            .await?
+            .ok_or_else(|| AppError::not_found("transfer"))?;
+        let (debit, credit, exchange_rate) = Self::transfer_legs(&mut tx, changes).await?;
+
+        Self::update_ledger_row(&mut tx, &from_transaction_id, debit).await?;
+        Self::update_ledger_row(&mut tx, &to_transaction_id, credit).await?;
+
+        sqlx::query("UPDATE transfers SET exchange_rate = ?, updated_at = ? WHERE id = ?")
+            .bind(&exchange_rate)
+            .bind(timestamp::format(&timestamp::now()))
+            .bind(&id)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+
+        self.get_transfer(id).await
+    }
+
+    pub async fn delete_transfer(&self, id: String) -> Result<(), AppError> {
+        let mut tx = self.pool.begin().await?;
+        if !Self::delete_transfer_in(&mut tx, &id).await? {
+            return Err(AppError::not_found("transfer"));
+        }
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    // Deletes a transfer and both legs, reversing their balance effects
+    pub async fn delete_transfer_in(conn: &mut SqliteConnection, id: &str) -> Result<bool, AppError> {
+        let Some((from_transaction_id, to_transaction_id)) = Self::transfer_leg_ids(conn, id).await? else {
+            return Ok(false);
+        };
+
+        sqlx::query("DELETE FROM transfers WHERE id = ?")
+            .bind(id)
+            .execute(&mut *conn)
+            .await?;
+
+        Self::delete_ledger_row(conn, &from_transaction_id).await?;
+        Self::delete_ledger_row(conn, &to_transaction_id).await?;
+
+        Ok(true)
+    }
+
+    pub async fn get_transfer(&self, id: String) -> Result<Transfer, AppError> {
+        let row = sqlx::query("SELECT * FROM transfers WHERE id = ?")
+            .bind(&id)
+            .fetch_one(&self.pool)
+            .await?;
+
+        self.transfer_from_row(row).await
+    }
+
+    pub async fn get_transfers(&self) -> Result<Vec<Transfer>, AppError> {
+        let rows = sqlx::query(
+            r#"
+            SELECT tf.* FROM transfers tf
+            INNER JOIN transactions tr ON tr.id = tf.from_transaction_id
+            ORDER BY tr.date DESC, tf.created_at DESC
+            "#
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut transfers = Vec::with_capacity(rows.len());
+        for row in rows {
+            transfers.push(self.transfer_from_row(row).await?);
+        }
+
+        Ok(transfers)
+    }
+
+    async fn transfer_from_row(&self, row: SqliteRow) -> Result<Transfer, AppError> {
+        let from_transaction_id: String = row.get("from_transaction_id");
+        let to_transaction_id: String = row.get("to_transaction_id");
+
+        Ok(Transfer {
+            id: row.get("id"),
+            from_transaction: self.get_transaction(&from_transaction_id).await?,
+            to_transaction: self.get_transaction(&to_transaction_id).await?,
+            exchange_rate: row.get("exchange_rate"),
+            created_at: timestamp::decode(&row, "created_at")?,
+            updated_at: timestamp::decode(&row, "updated_at")?,
+        })
+    }
+
+    // Validates a transfer request and builds its debit and credit legs plus the stated rate
+    async fn transfer_legs(
+        conn: &mut SqliteConnection,
+        new_transfer: NewTransfer,
+    ) -> Result<(NewTransaction, NewTransaction, Option<String>), AppError> {
+        if new_transfer.from_account_id == new_transfer.to_account_id {
+            return Err(AppError::validation("to_account_id", "Cannot transfer to the same account"));
+        }
+        if new_transfer.from_amount.minor_units <= 0 {
+            return Err(AppError::validation("from_amount", "Transfer amount must be positive"));
+        }
+
+        let to_currency: String = sqlx::query_scalar("SELECT currency FROM accounts WHERE id = ?")
+            .bind(&new_transfer.to_account_id)
+            .fetch_optional(&mut *conn)
+            .await?
+            .ok_or_else(|| AppError::not_found("account"))?;
+
+        let from_amount = new_transfer.from_amount;
+        let (to_amount, exchange_rate) = match (new_transfer.to_amount, new_transfer.exchange_rate) {
+            (Some(to_amount), Some(rate)) => {
+                let converted = from_amount
+                    .convert(&rate, to_amount.currency.clone())
+                    .map_err(|e| AppError::validation("exchange_rate", e.to_string()))?;
+                // One minor unit of slack for rounding on the bank's side
+                if (converted.minor_units - to_amount.minor_units).abs() > 1 {
+                    return Err(AppError::validation(
+                        "exchange_rate",
+                        format!("{} at {} is {}, not {}", from_amount, rate, converted, to_amount),
+                    ));
+                }
+                (to_amount, Some(rate))
+            }
+            (Some(to_amount), None) => {
+                let rate = if to_amount.currency == from_amount.currency {
+                    None
+                } else {
+                    from_amount.rate_to(&to_amount)
+                };
+                (to_amount, rate)
             }
-        }).collect();
+            (None, Some(rate)) => {
+                let to_amount = from_amount
+                    .convert(&rate, to_currency.clone())
+                    .map_err(|e| AppError::validation("exchange_rate", e.to_string()))?;
+                (to_amount, Some(rate))
+            }
+            (None, None) if from_amount.currency == to_currency => (from_amount.clone(), None),
+            (None, None) => {
+                return Err(AppError::validation(
+                    "to_amount",
+                    "Transfers between currencies need a destination amount or an exchange rate",
+                ));
+            }
+        };
 
-        Ok(transactions)
+        if to_amount.minor_units <= 0 {
+            return Err(AppError::validation("to_amount", "Transfer amount must be positive"));
+        }
+
+        let category_id = match new_transfer.category_id {
+            Some(category_id) => category_id,
+            None => Self::transfer_category_id(conn).await?,
+        };
+
+        let debit = NewTransaction {
+            account_id: new_transfer.from_account_id,
+            category_id: category_id.clone(),
+            amount: from_amount,
+            description: new_transfer.description.clone(),
+            transaction_type: "expense".to_string(),
+            date: new_transfer.date,
+        };
+        let credit = NewTransaction {
+            account_id: new_transfer.to_account_id,
+            category_id,
+            amount: to_amount,
+            description: new_transfer.description,
+            transaction_type: "income".to_string(),
+            date: new_transfer.date,
+        };
+
+        Ok((debit, credit, exchange_rate))
     }
 
-    pub async fn transaction_exists(&self, date: &str, amount: f64, description: &str) -> Result<bool, sqlx::Error> {
-        let row = sqlx::query(
-            "SELECT id FROM transactions WHERE date = ? AND amount = ? AND description = ?"
+    // Id of the "Transfer intern" category, created on first use. Its type is neither income nor
+    // expense, so budgets and per-type reports leave it out.
+    async fn transfer_category_id(conn: &mut SqliteConnection) -> Result<String, AppError> {
+        let existing: Option<String> = sqlx::query_scalar("SELECT id FROM categories WHERE category_type = 'transfer' LIMIT 1")
+            .fetch_optional(&mut *conn)
+            .await?;
+
+        if let Some(id) = existing {
+            return Ok(id);
+        }
+
+        let id = Uuid::new_v4().to_string();
+        sqlx::query(
+            r#"
+            INSERT INTO categories (id, name, color, icon, category_type, created_at)
+            VALUES (?, 'Transfer intern', '#94a3b8', '🔄', 'transfer', ?)
+            "#
         )
-        .bind(date)
-        .bind(amount)
-        .bind(description)
-        .fetch_optional(&self.pool)
+        .bind(&id)
+        .bind(timestamp::format(&timestamp::now()))
+        .execute(&mut *conn)
         .await?;
 
-        Ok(row.is_some())
+        Ok(id)
+    }
+
+    async fn transfer_id_of(conn: &mut SqliteConnection, transaction_id: &str) -> Result<Option<String>, AppError> {
+        let transfer_id: Option<Option<String>> = sqlx::query_scalar("SELECT transfer_id FROM transactions WHERE id = ?")
+            .bind(transaction_id)
+            .fetch_optional(&mut *conn)
+            .await?;
+
+        Ok(transfer_id.flatten())
+    }
+
+    async fn transfer_leg_ids(conn: &mut SqliteConnection, transfer_id: &str) -> Result<Option<(String, String)>, AppError> {
+        let row = sqlx::query("SELECT from_transaction_id, to_transaction_id FROM transfers WHERE id = ?")
+            .bind(transfer_id)
+            .fetch_optional(&mut *conn)
+            .await?;
+
+        Ok(row.map(|row| (row.get("from_transaction_id"), row.get("to_transaction_id"))))
     }
 
-    pub async fn get_transactions_by_month(&self, year: i32, month: i32) -> Result<Vec<Transaction>, sqlx::Error> {
-        let start_date = format!("{:04}-{:02}-01T00:00:00Z", year, month);
+    async fn get_transaction(&self, id: &str) -> Result<Transaction, AppError> {
+        let transaction = sqlx::query_as::<_, Transaction>("SELECT * FROM transactions WHERE id = ?")
+            .bind(id)
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(transaction)
+    }
+
+    pub async fn get_transactions(&self) -> Result<Vec<Transaction>, AppError> {
+        let transactions = sqlx::query_as::<_, Transaction>("SELECT * FROM transactions ORDER BY date DESC, created_at DESC")
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(transactions)
+    }
+
+    pub async fn get_transactions_by_month(&self, year: i32, month: i32) -> Result<Vec<Transaction>, AppError> {
+        let start_date = format!("{:04}-{:02}-01T00:00:00.000Z", year, month);
         let end_date = if month == 12 {
-            format!("{:04}-01-01T00:00:00Z", year + 1)
+            format!("{:04}-01-01T00:00:00.000Z", year + 1)
         } else {
-            format!("{:04}-{:02}-01T00:00:00Z", year, month + 1)
+            format!("{:04}-{:02}-01T00:00:00.000Z", year, month + 1)
         };
 
-        let rows = sqlx::query(
+        let transactions = sqlx::query_as::<_, Transaction>(
             "SELECT * FROM transactions WHERE date >= ? AND date < ? ORDER BY date DESC, created_at DESC"
         )
         .bind(&start_date)
@@ -565,120 +1026,46 @@ impl Database {
         .fetch_all(&self.pool)
         .await?;
 
-        let transactions = rows.into_iter().map(|row| {
-            Transaction {
-                id: row.get("id"),
-                account_id: row.get("account_id"),
-                category_id: row.get("category_id"),
-                amount: row.get("amount"),
-                description: row.get("description"),
-                transaction_type: row.get("transaction_type"),
-                date: DateTime::parse_from_rfc3339(&row.get::<String, _>("date")).unwrap().with_timezone(&Utc),
-                created_at: {
-                    let datetime_str: String = row.get("created_at");
-                    chrono::NaiveDateTime::parse_from_str(&datetime_str, "%Y-%m-%d %H:%M:%S").unwrap_or_else(|_| chrono::DateTime::from_timestamp(0, 0).unwrap().naive_utc()).and_utc()
-                },
-                updated_at: {
-                    let datetime_str: String = row.get("updated_at");
-                    chrono::NaiveDateTime::parse_from_str(&datetime_str, "%Y-%m-%d %H:%M:%S").unwrap_or_else(|_| chrono::DateTime::from_timestamp(0, 0).unwrap().naive_utc()).and_utc()
-                },
-            }
-        }).collect();
-
         Ok(transactions)
     }
 
-    pub async fn get_transactions_by_account(&self, account_id: String) -> Result<Vec<Transaction>, sqlx::Error> {
-        let rows = sqlx::query(
+    pub async fn get_transactions_by_account(&self, account_id: String) -> Result<Vec<Transaction>, AppError> {
+        let transactions = sqlx::query_as::<_, Transaction>(
             "SELECT * FROM transactions WHERE account_id = ? ORDER BY date DESC, created_at DESC"
         )
         .bind(&account_id)
         .fetch_all(&self.pool)
         .await?;
 
-        let transactions = rows.into_iter().map(|row| {
-            Transaction {
-                id: row.get("id"),
-                account_id: row.get("account_id"),
-                category_id: row.get("category_id"),
-                amount: row.get("amount"),
-                description: row.get("description"),
-                transaction_type: row.get("transaction_type"),
-                date: DateTime::parse_from_rfc3339(&row.get::<String, _>("date")).unwrap().with_timezone(&Utc),
-                created_at: {
-                    let datetime_str: String = row.get("created_at");
-                    chrono::NaiveDateTime::parse_from_str(&datetime_str, "%Y-%m-%d %H:%M:%S").unwrap_or_else(|_| chrono::DateTime::from_timestamp(0, 0).unwrap().naive_utc()).and_utc()
-                },
-                updated_at: {
-                    let datetime_str: String = row.get("updated_at");
-                    chrono::NaiveDateTime::parse_from_str(&datetime_str, "%Y-%m-%d %H:%M:%S").unwrap_or_else(|_| chrono::DateTime::from_timestamp(0, 0).unwrap().naive_utc()).and_utc()
-                },
-            }
-        }).collect();
-
         Ok(transactions)
     }
 
-    pub async fn get_transactions_by_category(&self, category_id: String) -> Result<Vec<Transaction>, sqlx::Error> {
-        let rows = sqlx::query(
+    pub async fn get_transactions_by_category(&self, category_id: String) -> Result<Vec<Transaction>, AppError> {
+        let transactions = sqlx::query_as::<_, Transaction>(
             "SELECT * FROM transactions WHERE category_id = ? ORDER BY date DESC, created_at DESC"
         )
         .bind(&category_id)
         .fetch_all(&self.pool)
         .await?;
 
-        let transactions = rows.into_iter().map(|row| {
-            Transaction {
-                id: row.get("id"),
-                account_id: row.get("account_id"),
-                category_id: row.get("category_id"),
-                amount: row.get("amount"),
-                description: row.get("description"),
-                transaction_type: row.get("transaction_type"),
-                date: DateTime::parse_from_rfc3339(&row.get::<String, _>("date")).unwrap().with_timezone(&Utc),
-                created_at: {
-                    let datetime_str: String = row.get("created_at");
-                    chrono::NaiveDateTime::parse_from_str(&datetime_str, "%Y-%m-%d %H:%M:%S").unwrap_or_else(|_| chrono::DateTime::from_timestamp(0, 0).unwrap().naive_utc()).and_utc()
-                },
-                updated_at: {
-                    let datetime_str: String = row.get("updated_at");
-                    chrono::NaiveDateTime::parse_from_str(&datetime_str, "%Y-%m-%d %H:%M:%S").unwrap_or_else(|_| chrono::DateTime::from_timestamp(0, 0).unwrap().naive_utc()).and_utc()
-                },
-            }
-        }).collect();
-
         Ok(transactions)
     }
 
-    pub async fn get_transactions_by_date_range(&self, start_date: String, end_date: String) -> Result<Vec<Transaction>, sqlx::Error> {
-        let rows = sqlx::query(
+    pub async fn get_transactions_by_date_range(&self, start_date: String, end_date: String) -> Result<Vec<Transaction>, AppError> {
+        // Bounds are compared as text, so bring them into the stored format first
+        let start_date = timestamp::parse(&start_date)
+            .ok_or_else(|| AppError::validation("start_date", format!("unrecognized date {}", start_date)))?;
+        let end_date = timestamp::parse(&end_date)
+            .ok_or_else(|| AppError::validation("end_date", format!("unrecognized date {}", end_date)))?;
+
+        let transactions = sqlx::query_as::<_, Transaction>(
             "SELECT * FROM transactions WHERE date >= ? AND date <= ? ORDER BY date DESC, created_at DESC"
         )
-        .bind(&start_date)
-        .bind(&end_date)
+        .bind(timestamp::format(&start_date))
+        .bind(timestamp::format(&end_date))
         .fetch_all(&self.pool)
         .await?;
 
-        let transactions = rows.into_iter().map(|row| {
-            Transaction {
-                id: row.get("id"),
-                account_id: row.get("account_id"),
-                category_id: row.get("category_id"),
-                amount: row.get("amount"),
-                description: row.get("description"),
-                transaction_type: row.get("transaction_type"),
-                date: DateTime::parse_from_rfc3339(&row.get::<String, _>("date")).unwrap().with_timezone(&Utc),
-                created_at: {
-                    let datetime_str: String = row.get("created_at");
-                    chrono::NaiveDateTime::parse_from_str(&datetime_str, "%Y-%m-%d %H:%M:%S").unwrap_or_else(|_| chrono::DateTime::from_timestamp(0, 0).unwrap().naive_utc()).and_utc()
-                },
-                updated_at: {
-                    let datetime_str: String = row.get("updated_at");
-                    chrono::NaiveDateTime::parse_from_str(&datetime_str, "%Y-%m-%d %H:%M:%S").unwrap_or_else(|_| chrono::DateTime::from_timestamp(0, 0).unwrap().naive_utc()).and_utc()
-                },
-            }
-        }).collect();
-
         Ok(transactions)
     }
 
@@ -689,7 +1076,7 @@ impl Database {
         icon: String,
         category_type: String,
         color: String,
-    ) -> Result<Category, sqlx::Error> {
+    ) -> Result<Category, AppError> {
         // Check if category name already exists for this type
         let existing = sqlx::query(
             "SELECT id FROM categories WHERE name = ? AND category_type = ?"
@@ -700,11 +1087,11 @@ impl Database {
         .await?;
 
         if existing.is_some() {
-            return Err(sqlx::Error::RowNotFound);
+            return Err(AppError::conflict(format!("Category \"{}\" already exists", name)));
         }
 
         let id = Uuid::new_v4().to_string();
-        let now = Utc::now();
+        let now = timestamp::now();
 
         sqlx::query(
             r#"
@@ -717,7 +1104,7 @@ impl Database {
         .bind(&color)
         .bind(&icon)
         .bind(&category_type)
-        .bind(now.to_rfc3339())
+        .bind(timestamp::format(&now))
         .execute(&self.pool)
         .await?;
 
@@ -738,7 +1125,7 @@ impl Database {
         icon: String,
         category_type: String,
         color: String,
-    ) -> Result<Category, sqlx::Error> {
+    ) -> Result<Category, AppError> {
         // Check if category name already exists for this type (excluding current category)
         let existing = sqlx::query(
             "SELECT id FROM categories WHERE name = ? AND category_type = ? AND id != ?"
@@ -750,10 +1137,10 @@ impl Database {
         .await?;
 
         if existing.is_some() {
-            return Err(sqlx::Error::RowNotFound);
+            return Err(AppError::conflict(format!("Category \"{}\" already exists", name)));
         }
 
-        sqlx::query(
+        let result = sqlx::query(
             "UPDATE categories SET name = ?, color = ?, icon = ?, category_type = ? WHERE id = ?"
         )
         .bind(&name)
@@ -764,67 +1151,108 @@ impl Database {
         .execute(&self.pool)
         .await?;
 
+        if result.rows_affected() == 0 {
+            return Err(AppError::not_found("category"));
+        }
+
         // Return the updated category
-        let row = sqlx::query("SELECT * FROM categories WHERE id = ?")
+        let category = sqlx::query_as::<_, Category>("SELECT * FROM categories WHERE id = ?")
             .bind(&id)
             .fetch_one(&self.pool)
             .await?;
 
-        Ok(Category {
-            id: row.get("id"),
-            name: row.get("name"),
-            color: row.get("color"),
-            icon: row.get("icon"),
-            category_type: row.get("category_type"),
-            created_at: {
-                let datetime_str: String = row.get("created_at");
-                chrono::NaiveDateTime::parse_from_str(&datetime_str, "%Y-%m-%d %H:%M:%S")
-                    .unwrap_or_else(|_| chrono::DateTime::from_timestamp(0, 0).unwrap().naive_utc())
-                    .and_utc()
-            },
-        })
+        Ok(category)
     }
 
-    pub async fn delete_category(&self, id: String) -> Result<(), sqlx::Error> {
+    // With `reassign_to`, transactions using the category are moved there first
+    pub async fn delete_category(&self, id: String, reassign_to: Option<String>) -> Result<(), AppError> {
+        let mut tx = self.pool.begin().await?;
+
+        if let Some(target_id) = reassign_to {
+            if target_id == id {
+                return Err(AppError::validation("reassign_to", "Cannot reassign to the category being deleted"));
+            }
+
+            let target_exists: Option<String> = sqlx::query_scalar("SELECT id FROM categories WHERE id = ?")
+                .bind(&target_id)
+                .fetch_optional(&mut *tx)
+                .await?;
+            if target_exists.is_none() {
+                return Err(AppError::not_found("category"));
+            }
+
+            sqlx::query("UPDATE transactions SET category_id = ?, updated_at = ? WHERE category_id = ?")
+                .bind(&target_id)
+                .bind(timestamp::format(&timestamp::now()))
+                .bind(&id)
+                .execute(&mut *tx)
+                .await?;
+
+            // Budgets would otherwise go with the category through ON DELETE CASCADE
+            sqlx::query("UPDATE budgets SET category_id = ?, updated_at = ? WHERE category_id = ?")
+                .bind(&target_id)
+                .bind(timestamp::format(&timestamp::now()))
+                .bind(&id)
+                .execute(&mut *tx)
+                .await?;
+
+            // So would recurring rules; edited occurrences would lose their category
+            sqlx::query("UPDATE recurring_rules SET category_id = ?, updated_at = ? WHERE category_id = ?")
+                .bind(&target_id)
+                .bind(timestamp::format(&timestamp::now()))
+                .bind(&id)
+                .execute(&mut *tx)
+                .await?;
+            sqlx::query("UPDATE recurring_occurrences SET category_id = ? WHERE category_id = ?")
+                .bind(&target_id)
+                .bind(&id)
+                .execute(&mut *tx)
+                .await?;
+        }
+
+        // Recurring schedules are never deleted along with their category
+        let rule_count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM recurring_rules WHERE category_id = ?")
+            .bind(&id)
+            .fetch_one(&mut *tx)
+            .await?;
+        if rule_count > 0 {
+            return Err(AppError::conflict(format!(
+                "Category is used by {} recurring rules; reassign it to another category first",
+                rule_count
+            )));
+        }
+
         // Check if category is being used in any transactions
         let transaction_count: i64 = sqlx::query_scalar(
             "SELECT COUNT(*) FROM transactions WHERE category_id = ?"
         )
         .bind(&id)
-        .fetch_one(&self.pool)
+        .fetch_one(&mut *tx)
         .await?;
 
         if transaction_count > 0 {
-            return Err(sqlx::Error::RowNotFound);
+            return Err(AppError::InUse { entity: "category".to_string(), count: transaction_count });
         }
 
-        sqlx::query("DELETE FROM categories WHERE id = ?")
+        let result = sqlx::query("DELETE FROM categories WHERE id = ?")
             .bind(&id)
-            .execute(&self.pool)
+            .execute(&mut *tx)
             .await?;
 
+        if result.rows_affected() == 0 {
+            return Err(AppError::not_found("category"));
+        }
+
+        tx.commit().await?;
+
         Ok(())
     }
 
-    pub async fn get_categories(&self) -> Result<Vec<Category>, sqlx::Error> {
-        let rows = sqlx::query("SELECT * FROM categories ORDER BY category_type, name")
+    pub async fn get_categories(&self) -> Result<Vec<Category>, AppError> {
+        let categories = sqlx::query_as::<_, Category>("SELECT * FROM categories ORDER BY category_type, name")
             .fetch_all(&self.pool)
             .await?;
 
-        let categories = rows.into_iter().map(|row| {
-            Category {
-                id: row.get("id"),
-                name: row.get("name"),
-                color: row.get("color"),
-                icon: row.get("icon"),
-                category_type: row.get("category_type"),
-                created_at: {
-                    let datetime_str: String = row.get("created_at");
-                    chrono::NaiveDateTime::parse_from_str(&datetime_str, "%Y-%m-%d %H:%M:%S").unwrap_or_else(|_| chrono::DateTime::from_timestamp(0, 0).unwrap().naive_utc()).and_utc()
-                },
-            }
-        }).collect();
-
         Ok(categories)
     }
 
@@ -834,7 +1262,7 @@ impl Database {
         name: String,
         icon: String,
         color: String,
-    ) -> Result<Tag, sqlx::Error> {
+    ) -> Result<Tag, AppError> {
         // Check if tag name already exists
         let existing = sqlx::query(
             "SELECT id FROM tags WHERE name = ?"
@@ -844,11 +1272,11 @@ impl Database {
         .await?;
 
         if existing.is_some() {
-            return Err(sqlx::Error::RowNotFound);
+            return Err(AppError::conflict(format!("Tag \"{}\" already exists", name)));
         }
 
         let id = Uuid::new_v4().to_string();
-        let now = Utc::now();
+        let now = timestamp::now();
 
         sqlx::query(
             r#"
@@ -860,7 +1288,7 @@ impl Database {
         .bind(&name)
         .bind(&color)
         .bind(&icon)
-        .bind(now.to_rfc3339())
+        .bind(timestamp::format(&now))
         .execute(&self.pool)
         .await?;
 
@@ -879,7 +1307,7 @@ impl Database {
         name: String,
         icon: String,
         color: String,
-    ) -> Result<Tag, sqlx::Error> {
+    ) -> Result<Tag, AppError> {
         // Check if tag name already exists (excluding current tag)
         let existing = sqlx::query(
             "SELECT id FROM tags WHERE name = ? AND id != ?"
@@ -890,10 +1318,10 @@ impl Database {
         .await?;
 
         if existing.is_some() {
-            return Err(sqlx::Error::RowNotFound);
+            return Err(AppError::conflict(format!("Tag \"{}\" already exists", name)));
         }
 
-        sqlx::query(
+        let result = sqlx::query(
             r#"
             UPDATE tags
             SET name = ?, color = ?, icon = ?
@@ -907,18 +1335,20 @@ impl Database {
         .execute(&self.pool)
         .await?;
 
-        let updated_at = Utc::now();
+        if result.rows_affected() == 0 {
+            return Err(AppError::not_found("tag"));
+        }
 
-        Ok(Tag {
-            id,
-            name,
-            color,
-            icon,
-            created_at: updated_at, // Note: we don't track updated_at for tags, so using current time
-        })
+        // Return the updated tag
+        let tag = sqlx::query_as::<_, Tag>("SELECT * FROM tags WHERE id = ?")
+            .bind(&id)
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(tag)
     }
 
-    pub async fn delete_tag(&self, id: String) -> Result<(), sqlx::Error> {
+    pub async fn delete_tag(&self, id: String) -> Result<(), AppError> {
         // Check if tag is being used in any transactions
         let transaction_count: i64 = sqlx::query_scalar(
             "SELECT COUNT(*) FROM transaction_tags WHERE tag_id = ?"
@@ -928,75 +1358,98 @@ impl Database {
         .await?;
 
         if transaction_count > 0 {
-            return Err(sqlx::Error::RowNotFound);
+            return Err(AppError::InUse { entity: "tag".to_string(), count: transaction_count });
         }
 
-        sqlx::query("DELETE FROM tags WHERE id = ?")
+        let result = sqlx::query("DELETE FROM tags WHERE id = ?")
             .bind(&id)
             .execute(&self.pool)
             .await?;
 
+        if result.rows_affected() == 0 {
+            return Err(AppError::not_found("tag"));
+        }
+
         Ok(())
     }
 
-    pub async fn get_tags(&self) -> Result<Vec<Tag>, sqlx::Error> {
-        let rows = sqlx::query("SELECT * FROM tags ORDER BY name")
+    pub async fn get_tags(&self) -> Result<Vec<Tag>, AppError> {
+        let tags = sqlx::query_as::<_, Tag>("SELECT * FROM tags ORDER BY name")
             .fetch_all(&self.pool)
             .await?;
 
-        let tags = rows.into_iter().map(|row| {
-            Tag {
-                id: row.get("id"),
-                name: row.get("name"),
-                color: row.get("color"),
-                icon: row.get("icon"),
-                created_at: {
-                    let datetime_str: String = row.get("created_at");
-                    chrono::NaiveDateTime::parse_from_str(&datetime_str, "%Y-%m-%d %H:%M:%S").unwrap_or_else(|_| chrono::DateTime::from_timestamp(0, 0).unwrap().naive_utc()).and_utc()
-                },
-            }
-        }).collect();
-
         Ok(tags)
     }
 
     // Transaction-Tag relationship functions
-    pub async fn add_tags_to_transaction(&self, transaction_id: String, tag_ids: Vec<String>) -> Result<(), sqlx::Error> {
-        let now = Utc::now();
+    pub async fn add_tags_to_transaction(&self, transaction_id: String, tag_ids: Vec<String>) -> Result<(), AppError> {
+        let mut tx = self.pool.begin().await?;
+        Self::add_tags_to_transaction_in(&mut tx, &transaction_id, &tag_ids).await?;
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    pub async fn add_tags_to_transaction_in(conn: &mut SqliteConnection, transaction_id: &str, tag_ids: &[String]) -> Result<(), AppError> {
+        let now = timestamp::now();
 
         for tag_id in tag_ids {
+            // Insert only if not already exists (ignore duplicates)
             sqlx::query(
                 r#"
                 INSERT OR IGNORE INTO transaction_tags (transaction_id, tag_id, created_at)
                 VALUES (?, ?, ?)
                 "#
             )
-            .bind(&transaction_id)
-            .bind(&tag_id)
-            .bind(now.to_rfc3339())
-            .execute(&self.pool)
+            .bind(transaction_id)
+            .bind(tag_id)
+            .bind(timestamp::format(&now))
+            .execute(&mut *conn)
             .await?;
         }
 
         Ok(())
     }
 
-    pub async fn remove_tags_from_transaction(&self, transaction_id: String, tag_ids: Vec<String>) -> Result<(), sqlx::Error> {
+    // The given tag ids that still exist, in their original order. Rules keep tag ids in JSON
+    // without a foreign key, so they can outlive their tags.
+    pub(crate) async fn existing_tag_ids_in(conn: &mut SqliteConnection, tag_ids: &[String]) -> Result<Vec<String>, AppError> {
+        let mut existing = Vec::with_capacity(tag_ids.len());
+        for tag_id in tag_ids {
+            let found: Option<String> = sqlx::query_scalar("SELECT id FROM tags WHERE id = ?")
+                .bind(tag_id)
+                .fetch_optional(&mut *conn)
+                .await?;
+            existing.extend(found);
+        }
+
+        Ok(existing)
+    }
+
+    pub async fn remove_tags_from_transaction(&self, transaction_id: String, tag_ids: Vec<String>) -> Result<(), AppError> {
+        let mut tx = self.pool.begin().await?;
+        Self::remove_tags_from_transaction_in(&mut tx, &transaction_id, &tag_ids).await?;
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    pub async fn remove_tags_from_transaction_in(conn: &mut SqliteConnection, transaction_id: &str, tag_ids: &[String]) -> Result<(), AppError> {
         for tag_id in tag_ids {
             sqlx::query(
                 "DELETE FROM transaction_tags WHERE transaction_id = ? AND tag_id = ?"
             )
-            .bind(&transaction_id)
-            .bind(&tag_id)
-            .execute(&self.pool)
+            .bind(transaction_id)
+            .bind(tag_id)
+            .execute(&mut *conn)
             .await?;
         }
 
         Ok(())
     }
 
-    pub async fn get_transaction_tags(&self, transaction_id: String) -> Result<Vec<Tag>, sqlx::Error> {
-        let rows = sqlx::query(
+    pub async fn get_transaction_tags(&self, transaction_id: String) -> Result<Vec<Tag>, AppError> {
+        let tags = sqlx::query_as::<_, Tag>(
             r#"
             SELECT t.* FROM tags t
             INNER JOIN transaction_tags tt ON t.id = tt.tag_id
@@ -1008,24 +1461,11 @@ impl Database {
         .fetch_all(&self.pool)
         .await?;
 
-        let tags = rows.into_iter().map(|row| {
-            Tag {
-                id: row.get("id"),
-                name: row.get("name"),
-                color: row.get("color"),
-                icon: row.get("icon"),
-                created_at: {
-                    let datetime_str: String = row.get("created_at");
-                    chrono::NaiveDateTime::parse_from_str(&datetime_str, "%Y-%m-%d %H:%M:%S").unwrap_or_else(|_| chrono::DateTime::from_timestamp(0, 0).unwrap().naive_utc()).and_utc()
-                },
-            }
-        }).collect();
-
         Ok(tags)
     }
 
-    pub async fn get_transactions_by_tag(&self, tag_id: String) -> Result<Vec<Transaction>, sqlx::Error> {
-        let rows = sqlx::query(
+    pub async fn get_transactions_by_tag(&self, tag_id: String) -> Result<Vec<Transaction>, AppError> {
+        let transactions = sqlx::query_as::<_, Transaction>(
             r#"
             SELECT tr.* FROM transactions tr
             INNER JOIN transaction_tags tt ON tr.id = tt.transaction_id
@@ -1037,66 +1477,10 @@ impl Database {
         .fetch_all(&self.pool)
         .await?;
 
-        let transactions = rows.into_iter().map(|row| {
-            Transaction {
-                id: row.get("id"),
-                account_id: row.get("account_id"),
-                category_id: row.get("category_id"),
-                amount: row.get("amount"),
-                description: row.get("description"),
-                transaction_type: row.get("transaction_type"),
-                date: {
-                    let date_str: String = row.get("date");
-                    chrono::NaiveDateTime::parse_from_str(&date_str, "%Y-%m-%d %H:%M:%S").unwrap_or_else(|_| chrono::DateTime::from_timestamp(0, 0).unwrap().naive_utc()).and_utc()
-                },
-                created_at: {
-                    let datetime_str: String = row.get("created_at");
-                    chrono::NaiveDateTime::parse_from_str(&datetime_str, "%Y-%m-%d %H:%M:%S").unwrap_or_else(|_| chrono::DateTime::from_timestamp(0, 0).unwrap().naive_utc()).and_utc()
-                },
-                updated_at: {
-                    let datetime_str: String = row.get("updated_at");
-                    chrono::NaiveDateTime::parse_from_str(&datetime_str, "%Y-%m-%d %H:%M:%S").unwrap_or_else(|_| chrono::DateTime::from_timestamp(0, 0).unwrap().naive_utc()).and_utc()
-                },
-            }
-        }).collect();
-
         Ok(transactions)
     }
 
-    // Migration functions
-    async fn run_migrations(&self) -> Result<(), sqlx::Error> {
-        self.add_owner_column_to_accounts().await?;
-        Ok(())
-    }
-
-    async fn add_owner_column_to_accounts(&self) -> Result<(), sqlx::Error> {
-        // Check if owner column already exists
-        let table_info = sqlx::query("PRAGMA table_info(accounts)")
-            .fetch_all(&self.pool)
-            .await?;
-
-        let has_owner_column = table_info.iter().any(|row| {
-            let column_name: String = row.get("name");
-            column_name == "owner"
-        });
-
-        if !has_owner_column {
-            // Add owner column
-            sqlx::query("ALTER TABLE accounts ADD COLUMN owner TEXT")
-                .execute(&self.pool)
-                .await?;
-
-            // Set default value for existing accounts
-            sqlx::query("UPDATE accounts SET owner = 'Ioan' WHERE owner IS NULL")
-                .execute(&self.pool)
-                .await?;
-
-            println!("✅ Added owner column to accounts table");
-        }
-
-        Ok(())
-    }
-    pub async fn insert_nomad_accounts(&self) -> Result<(), sqlx::Error> {
+    pub async fn insert_nomad_accounts(&self) -> Result<(), AppError> {
         // Check if accounts already exist
         let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM accounts")
             .fetch_one(&self.pool)
@@ -1132,12 +1516,12 @@ impl Database {
         ];
 
         for (id, name, account_type, currency, owner) in accounts {
-            let now = Utc::now().format("%Y-%m-%d %H:%M:%S").to_string();
+            let now = timestamp::format(&timestamp::now());
 
             sqlx::query(
                 r#"
                 INSERT INTO accounts (id, name, account_type, balance, currency, owner, created_at, updated_at)
-                VALUES (?, ?, ?, 0.0, ?, ?, ?, ?)
+                VALUES (?, ?, ?, 0, ?, ?, ?, ?)
                 "#
             )
             .bind(id)
@@ -1156,3 +1540,11 @@ impl Database {
     }
 }
 
+// Signed change a transaction applies to its account balance, in minor units
+fn balance_effect(transaction_type: &str, amount: &Money) -> i64 {
+    if transaction_type == "income" {
+        amount.minor_units
+    } else {
+        -amount.minor_units
+    }
+}
@@ -0,0 +1,95 @@
+// error.rs
+// Typed errors returned from Database and every Tauri command
+
+use serde::Serialize;
+use std::fmt;
+use crate::migrations::MigrationError;
+use crate::money::MoneyError;
+
+// Serialized as { "kind": "in_use", "details": { "entity": "category", "count": 3 } }
+// so the frontend can pick a message per kind.
+#[derive(Debug, Serialize)]
+#[serde(tag = "kind", content = "details", rename_all = "snake_case")]
+pub enum AppError {
+    NotFound { entity: String },
+    InUse { entity: String, count: i64 },
+    Validation { field: String, reason: String },
+    Conflict { message: String },
+    Io { message: String },
+    Database { message: String },
+}
+
+impl AppError {
+    pub fn not_found(entity: &str) -> Self {
+        AppError::NotFound { entity: entity.to_string() }
+    }
+
+    pub fn validation(field: &str, reason: impl Into<String>) -> Self {
+        AppError::Validation {
+            field: field.to_string(),
+            reason: reason.into(),
+        }
+    }
+
+    pub fn conflict(message: impl Into<String>) -> Self {
+        AppError::Conflict { message: message.into() }
+    }
+
+    pub fn io(message: impl Into<String>) -> Self {
+        AppError::Io { message: message.into() }
+    }
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AppError::NotFound { entity } => write!(f, "{} not found", entity),
+            AppError::InUse { entity, count } => {
+                write!(f, "{} is still used by {} transactions", entity, count)
+            }
+            AppError::Validation { field, reason } => write!(f, "Invalid {}: {}", field, reason),
+            AppError::Conflict { message } => write!(f, "{}", message),
+            AppError::Io { message } => write!(f, "I/O error: {}", message),
+            AppError::Database { message } => write!(f, "Database error: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for AppError {}
+
+impl From<sqlx::Error> for AppError {
+    fn from(e: sqlx::Error) -> Self {
+        match e {
+            sqlx::Error::RowNotFound => AppError::not_found("record"),
+            sqlx::Error::Io(e) => AppError::io(e.to_string()),
+            sqlx::Error::Database(ref db_error) if db_error.is_foreign_key_violation() => {
+                AppError::validation("reference", db_error.message().to_string())
+            }
+            sqlx::Error::Database(ref db_error) if db_error.is_unique_violation() => {
+                AppError::conflict(db_error.message().to_string())
+            }
+            e => AppError::Database { message: e.to_string() },
+        }
+    }
+}
+
+impl From<MigrationError> for AppError {
+    fn from(e: MigrationError) -> Self {
+        match e {
+            MigrationError::Database(e) => e.into(),
+            e => AppError::conflict(e.to_string()),
+        }
+    }
+}
+
+impl From<MoneyError> for AppError {
+    fn from(e: MoneyError) -> Self {
+        AppError::validation("amount", e.to_string())
+    }
+}
+
+impl From<std::io::Error> for AppError {
+    fn from(e: std::io::Error) -> Self {
+        AppError::io(e.to_string())
+    }
+}
@@ -0,0 +1,534 @@
+// import.rs
+// Shared pieces of the statement importers: the row format, preview and the insert path
+
+use serde::{Deserialize, Serialize};
+use sqlx::{sqlite::SqliteRow, FromRow, Row};
+use chrono::{DateTime, NaiveDate, Utc};
+use encoding_rs::Encoding;
+use uuid::Uuid;
+use crate::database::{Database, NewTransaction, NewTransfer, Transaction};
+use crate::error::AppError;
+use crate::import_dedup::{DuplicateMatch, DuplicateMatcher, SuspectedDuplicate};
+use crate::money::Money;
+use crate::rules::RuleOutcome;
+use crate::timestamp;
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct ImportTransaction {
+    pub account_id: String,
+    #[serde(default)]
+    pub category_id: String, // left empty, it is filled from rules or learned suggestions
+    pub amount: Money,
+    pub description: String,
+    pub transaction_type: String,
+    pub date: String, // ISO format from frontend
+    #[serde(default)]
+    pub external_id: Option<String>, // bank id such as the OFX FITID; replaces the heuristic duplicate check
+    #[serde(default)]
+    pub value_date: Option<String>,
+    #[serde(default)]
+    pub counterparty_name: Option<String>,
+    #[serde(default)]
+    pub counterparty_iban: Option<String>,
+    #[serde(default)]
+    pub cleared_status: Option<String>, // defaults to "uncleared"
+    #[serde(default)]
+    pub allow_duplicate: bool, // set once the user has reviewed a suspected duplicate
+}
+
+#[derive(Debug, Serialize)]
+pub struct ImportResult {
+    pub inserted: usize,
+    pub skipped: usize,
+    pub errors: Vec<String>,
+    pub suspected: Vec<SuspectedDuplicate>, // held back, not inserted
+    pub batch_id: Option<String>,
+}
+
+// Currency exchange between two own accounts found in a statement
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct ImportTransfer {
+    pub transfer: NewTransfer,
+    #[serde(default)]
+    pub external_id: Option<String>, // stored on both legs
+}
+
+// One line of the source file; exactly one of transaction, transfer, error and skipped is set
+#[derive(Debug, Serialize, Clone)]
+pub struct PreviewRow {
+    pub line: usize,
+    pub transaction: Option<ImportTransaction>,
+    pub transfer: Option<ImportTransfer>,
+    pub error: Option<String>,
+    pub skipped: Option<String>, // why a valid line is left out, e.g. a declined card payment
+    pub fingerprint: Option<String>,
+    pub duplicate: bool, // batch_insert_transactions would skip it
+    pub suspected: bool, // batch_insert_transactions would hold it back
+    pub matched: Option<Transaction>, // the existing transaction it duplicates or resembles
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct ImportPreview {
+    pub rows: Vec<PreviewRow>,
+    pub ready: usize,
+    pub duplicates: usize,
+    pub suspected: usize,
+    pub errors: usize,
+    pub skipped: usize,
+    pub statement_balances: Vec<StatementBalance>, // balances reported in the file, if any
+    pub balance_checks: Vec<BalanceCheck>,         // formats with opening and closing balances
+}
+
+// Opening balance plus the statement's own lines, compared with its closing balance
+#[derive(Debug, Serialize, Clone)]
+pub struct BalanceCheck {
+    pub statement: String, // the statement's reference or number
+    pub opening: Money,
+    pub closing: Money,
+    pub computed_closing: Money,
+    pub mismatch: bool,
+}
+
+// Balance of an account at the end of a day, as reported by the bank
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct StatementBalance {
+    pub account_id: String,
+    pub date: DateTime<Utc>, // midnight of the statement day
+    pub amount: Money,
+}
+
+// A stored statement balance next to the ledger balance at the end of the same day
+#[derive(Debug, Serialize, Clone)]
+pub struct BalanceReconciliation {
+    pub id: String,
+    pub account_id: String,
+    pub date: DateTime<Utc>,
+    pub statement: Money,
+    pub computed: Money,
+    pub difference: Money, // computed - statement
+    pub created_at: DateTime<Utc>,
+}
+
+impl<'r> FromRow<'r, SqliteRow> for BalanceReconciliation {
+    fn from_row(row: &'r SqliteRow) -> Result<Self, sqlx::Error> {
+        let currency: String = row.try_get("currency")?;
+        let statement: i64 = row.try_get("amount")?;
+        let computed: i64 = row.try_get("computed")?;
+
+        Ok(BalanceReconciliation {
+            id: row.try_get("id")?,
+            account_id: row.try_get("account_id")?,
+            date: timestamp::decode(row, "balance_date")?,
+            statement: Money::new(statement, currency.clone()),
+            computed: Money::new(computed, currency.clone()),
+            difference: Money::new(computed - statement, currency),
+            created_at: timestamp::decode(row, "created_at")?,
+        })
+    }
+}
+
+impl PreviewRow {
+    pub fn parsed(line: usize, transaction: ImportTransaction) -> Self {
+        PreviewRow {
+            line,
+            transaction: Some(transaction),
+            transfer: None,
+            error: None,
+            skipped: None,
+            fingerprint: None,
+            duplicate: false,
+            suspected: false,
+            matched: None,
+        }
+    }
+
+    pub fn transfer(line: usize, transfer: ImportTransfer) -> Self {
+        PreviewRow {
+            line,
+            transaction: None,
+            transfer: Some(transfer),
+            error: None,
+            skipped: None,
+            fingerprint: None,
+            duplicate: false,
+            suspected: false,
+            matched: None,
+        }
+    }
+
+    pub fn failed(line: usize, error: impl Into<String>) -> Self {
+        PreviewRow {
+            line,
+            transaction: None,
+            transfer: None,
+            error: Some(error.into()),
+            skipped: None,
+            fingerprint: None,
+            duplicate: false,
+            suspected: false,
+            matched: None,
+        }
+    }
+
+    pub fn skipped(line: usize, reason: impl Into<String>) -> Self {
+        PreviewRow {
+            line,
+            transaction: None,
+            transfer: None,
+            error: None,
+            skipped: Some(reason.into()),
+            fingerprint: None,
+            duplicate: false,
+            suspected: false,
+            matched: None,
+        }
+    }
+}
+
+impl ImportResult {
+    pub fn absorb(&mut self, other: ImportResult) {
+        self.inserted += other.inserted;
+        self.skipped += other.skipped;
+        self.errors.extend(other.errors);
+        self.suspected.extend(other.suspected);
+    }
+}
+
+impl Database {
+    pub async fn import_transactions(
+        &self,
+        transactions: Vec<ImportTransaction>,
+        statement_balances: &[StatementBalance],
+        batch_id: Option<&str>,
+    ) -> Result<ImportResult, AppError> {
+        let mut inserted = 0;
+        let mut skipped = 0;
+        let mut errors = Vec::new();
+        let mut suspected = Vec::new();
+        let mut matcher = self.duplicate_matcher().await?;
+        let rules = self.rule_set().await?;
+        let mut model = None;
+
+        for mut tx in transactions {
+            // Parse date - RFC3339 or plain YYYY-MM-DD
+            let date = match timestamp::parse(&tx.date) {
+                Some(d) => d,
+                None => {
+                    errors.push(format!("Invalid date: {}", tx.date));
+                    continue;
+                }
+            };
+
+            // Rules run first so a rewritten description is what gets compared and stored
+            let outcome = rules.apply_to_import(&mut tx);
+            if tx.category_id.is_empty() {
+                if model.is_none() {
+                    model = Some(self.category_model().await?);
+                }
+                let suggestion = model
+                    .as_ref()
+                    .and_then(|model| model.suggest(&tx.description, Some(&tx.transaction_type)).into_iter().next());
+                match suggestion {
+                    Some((category_id, _)) => tx.category_id = category_id,
+                    None => {
+                        errors.push(format!("No category for {}", tx.description));
+                        continue;
+                    }
+                }
+            }
+
+            // Check duplicate (bank id, or same day, amount and normalized description)
+            match self.match_duplicate(&mut matcher, &tx, &date).await {
+                Ok((_, DuplicateMatch::Exact(_))) => {
+                    skipped += 1;
+                    continue;
+                }
+                Ok((_, DuplicateMatch::Suspected(matched, similarity))) => {
+                    suspected.push(SuspectedDuplicate {
+                        transaction: tx,
+                        matched,
+                        similarity,
+                    });
+                    continue;
+                }
+                Ok((_, DuplicateMatch::New)) => {
+                    // Insert new transaction
+                    match self.insert_imported(tx, date, batch_id, &outcome).await {
+                        Ok(id) => {
+                            matcher.claim(&id);
+                            inserted += 1;
+                        },
+                        Err(e) => {
+                            errors.push(format!("Failed to insert: {}", e));
+                        },
+                    }
+                }
+                Err(e) => {
+                    errors.push(format!("Database error checking duplicate: {}", e));
+                    continue;
+                }
+            }
+        }
+
+        for balance in statement_balances {
+            if let Err(e) = self.save_statement_balance(balance, batch_id).await {
+                errors.push(format!("Failed to save statement balance: {}", e));
+            }
+        }
+
+        Ok(ImportResult {
+            inserted,
+            skipped,
+            errors,
+            suspected,
+            batch_id: batch_id.map(str::to_string),
+        })
+    }
+
+    pub async fn import_transfers(
+        &self,
+        transfers: Vec<ImportTransfer>,
+        batch_id: Option<&str>,
+    ) -> Result<ImportResult, AppError> {
+        let mut inserted = 0;
+        let mut skipped = 0;
+        let mut errors = Vec::new();
+        let mut matcher = self.duplicate_matcher().await?;
+
+        for import in transfers {
+            match self.is_duplicate_transfer(&mut matcher, &import).await {
+                Ok(true) => {
+                    skipped += 1;
+                }
+                Ok(false) => match self.insert_imported_transfer(import, batch_id).await {
+                    Ok(id) => {
+                        matcher.claim(&id);
+                        inserted += 1;
+                    }
+                    Err(e) => {
+                        errors.push(format!("Failed to insert transfer: {}", e));
+                    }
+                },
+                Err(e) => errors.push(format!("Database error checking duplicate: {}", e)),
+            }
+        }
+
+        Ok(ImportResult {
+            inserted,
+            skipped,
+            errors,
+            suspected: Vec::new(),
+            batch_id: batch_id.map(str::to_string),
+        })
+    }
+
+    // Judged by the source leg, the same way a single transaction would be, but on an exact
+    // match only: a transfer between own accounts has no statement row to review it against
+    async fn is_duplicate_transfer(&self, matcher: &mut DuplicateMatcher, import: &ImportTransfer) -> Result<bool, AppError> {
+        let transfer = &import.transfer;
+        let source_leg = ImportTransaction {
+            account_id: transfer.from_account_id.clone(),
+            category_id: String::new(),
+            amount: transfer.from_amount.clone(),
+            description: transfer.description.clone(),
+            transaction_type: "expense".to_string(),
+            date: timestamp::format(&transfer.date),
+            external_id: import.external_id.clone(),
+            value_date: None,
+            counterparty_name: None,
+            counterparty_iban: None,
+            cleared_status: None,
+            allow_duplicate: true,
+        };
+
+        let (_, found) = self.match_duplicate(matcher, &source_leg, &transfer.date).await?;
+        Ok(matches!(found, DuplicateMatch::Exact(_)))
+    }
+
+    // Returns the id of the source leg
+    async fn insert_imported_transfer(&self, import: ImportTransfer, batch_id: Option<&str>) -> Result<String, AppError> {
+        let mut conn = self.begin().await?;
+        let transfer = Self::create_transfer_in(&mut conn, import.transfer).await?;
+
+        if import.external_id.is_some() {
+            sqlx::query("UPDATE transactions SET external_id = ? WHERE transfer_id = ?")
+                .bind(&import.external_id)
+                .bind(&transfer.id)
+                .execute(&mut *conn)
+                .await?;
+        }
+        if let Some(batch_id) = batch_id {
+            Self::link_import_batch_in(&mut conn, batch_id, &transfer.from_transaction.id).await?;
+            Self::link_import_batch_in(&mut conn, batch_id, &transfer.to_transaction.id).await?;
+        }
+
+        conn.commit().await?;
+        Ok(transfer.from_transaction.id)
+    }
+
+    async fn insert_imported(
+        &self,
+        tx: ImportTransaction,
+        date: DateTime<Utc>,
+        batch_id: Option<&str>,
+        outcome: &RuleOutcome,
+    ) -> Result<String, AppError> {
+        let mut conn = self.begin().await?;
+        let transaction = Self::create_transaction_in(&mut conn, NewTransaction {
+            account_id: tx.account_id,
+            category_id: tx.category_id,
+            amount: tx.amount,
+            description: tx.description,
+            transaction_type: tx.transaction_type,
+            date,
+        }, &outcome.tag_ids).await?;
+
+        // Statement details create_transaction_in does not know about
+        sqlx::query(
+            r#"
+            UPDATE transactions
+            SET external_id = ?, value_date = ?, counterparty_name = ?, counterparty_iban = ?,
+                cleared_status = COALESCE(?, cleared_status), owner = ?
+            WHERE id = ?
+            "#
+        )
+        .bind(&tx.external_id)
+        .bind(tx.value_date.as_deref().and_then(timestamp::parse).as_ref().map(timestamp::format))
+        .bind(&tx.counterparty_name)
+        .bind(&tx.counterparty_iban)
+        .bind(&tx.cleared_status)
+        .bind(&outcome.owner)
+        .bind(&transaction.id)
+        .execute(&mut *conn)
+        .await?;
+
+        if let Some(batch_id) = batch_id {
+            Self::link_import_batch_in(&mut conn, batch_id, &transaction.id).await?;
+        }
+
+        conn.commit().await?;
+        Ok(transaction.id)
+    }
+
+    // Flags rows import_transactions would skip and counts the outcome
+    pub async fn build_preview(
+        &self,
+        mut rows: Vec<PreviewRow>,
+        statement_balances: Vec<StatementBalance>,
+    ) -> Result<ImportPreview, AppError> {
+        let mut matcher = self.duplicate_matcher().await?;
+        let mut transfer_matcher = self.duplicate_matcher().await?;
+
+        for row in rows.iter_mut() {
+            if let Some(tx) = &row.transaction {
+                if let Some(date) = timestamp::parse(&tx.date) {
+                    let (fingerprint, found) = self.match_duplicate(&mut matcher, tx, &date).await?;
+                    row.fingerprint = Some(fingerprint);
+                    match found {
+                        DuplicateMatch::Exact(matched) => {
+                            row.duplicate = true;
+                            row.matched = Some(matched);
+                        }
+                        DuplicateMatch::Suspected(matched, _) => {
+                            row.suspected = true;
+                            row.matched = Some(matched);
+                        }
+                        DuplicateMatch::New => {}
+                    }
+                }
+            }
+            if let Some(transfer) = &row.transfer {
+                row.duplicate = self.is_duplicate_transfer(&mut transfer_matcher, transfer).await?;
+            }
+        }
+
+        let duplicates = rows.iter().filter(|row| row.duplicate).count();
+        let suspected = rows.iter().filter(|row| row.suspected).count();
+        let errors = rows.iter().filter(|row| row.error.is_some()).count();
+        let skipped = rows.iter().filter(|row| row.skipped.is_some()).count();
+
+        Ok(ImportPreview {
+            ready: rows.len() - duplicates - suspected - errors - skipped,
+            duplicates,
+            suspected,
+            errors,
+            skipped,
+            rows,
+            statement_balances,
+            balance_checks: Vec::new(),
+        })
+    }
+
+    // A later statement for the same day replaces the earlier one's amount, but the row keeps
+    // the batch that first saved it, so reverting the later import does not remove it
+    pub async fn save_statement_balance(&self, balance: &StatementBalance, batch_id: Option<&str>) -> Result<(), AppError> {
+        let mut conn = self.begin().await?;
+        Self::ensure_account_currency(&mut conn, &balance.account_id, &balance.amount).await?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO statement_balances (id, account_id, balance_date, amount, currency, import_batch_id, created_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?)
+            ON CONFLICT (account_id, balance_date) DO UPDATE SET
+                amount = excluded.amount,
+                currency = excluded.currency,
+                created_at = excluded.created_at
+            "#
+        )
+        .bind(Uuid::new_v4().to_string())
+        .bind(&balance.account_id)
+        .bind(timestamp::format(&balance.date))
+        .bind(balance.amount.minor_units)
+        .bind(&balance.amount.currency)
+        .bind(batch_id)
+        .bind(timestamp::format(&timestamp::now()))
+        .execute(&mut *conn)
+        .await?;
+
+        conn.commit().await?;
+        Ok(())
+    }
+
+    // Newest first; computed sums every transaction dated before the end of the statement day
+    pub async fn get_balance_reconciliation(&self, account_id: String) -> Result<Vec<BalanceReconciliation>, AppError> {
+        let reconciliations = sqlx::query_as::<_, BalanceReconciliation>(
+            r#"
+            SELECT s.*,
+                   (SELECT COALESCE(SUM(CASE WHEN t.transaction_type = 'income' THEN t.amount ELSE -t.amount END), 0)
+                    FROM transactions t
+                    WHERE t.account_id = s.account_id
+                      AND t.date < strftime('%Y-%m-%dT%H:%M:%fZ', s.balance_date, '+1 day')) AS computed
+            FROM statement_balances s
+            WHERE s.account_id = ?
+            ORDER BY s.balance_date DESC
+            "#
+        )
+        .bind(&account_id)
+        .fetch_all(self.get_pool())
+        .await?;
+
+        Ok(reconciliations)
+    }
+
+    pub(crate) async fn account_currency(&self, account_id: &str) -> Result<String, AppError> {
+        sqlx::query_scalar("SELECT currency FROM accounts WHERE id = ?")
+            .bind(account_id)
+            .fetch_optional(self.get_pool())
+            .await?
+            .ok_or_else(|| AppError::not_found("account"))
+    }
+}
+
+// Decodes a statement file; a byte order mark overrides the given encoding
+pub fn decode_text(bytes: &[u8], encoding: &str) -> Result<String, AppError> {
+    let encoding = Encoding::for_label(encoding.as_bytes())
+        .ok_or_else(|| AppError::validation("encoding", format!("unknown encoding {}", encoding)))?;
+
+    let (text, _, _) = encoding.decode(bytes);
+    Ok(text.into_owned())
+}
+
+// Statement dates are calendar days at the bank; every importer keeps them as midnight UTC
+pub fn midnight(date: NaiveDate) -> DateTime<Utc> {
+    date.and_hms_opt(0, 0, 0).unwrap_or_default().and_utc()
+}
@@ -0,0 +1,196 @@
+// import_batches.rs
+// Every import is recorded as a batch of the transactions it inserted, so a wrong file or a
+// wrong target account can be undone as a whole.
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use sqlx::{sqlite::{SqliteConnection, SqliteRow}, FromRow, Row};
+use std::collections::{BTreeSet, HashMap};
+use std::path::Path;
+use uuid::Uuid;
+use crate::database::Database;
+use crate::error::AppError;
+use crate::import::{ImportResult, ImportTransaction, ImportTransfer, StatementBalance};
+use crate::timestamp;
+
+#[derive(Debug, Serialize, Clone)]
+pub struct ImportBatch {
+    pub id: String,
+    pub file_name: Option<String>,
+    pub file_hash: Option<String>, // sha256 of the file as imported
+    pub account_id: Option<String>, // unset when the import spanned several accounts
+    pub inserted: i64,
+    pub skipped: i64,
+    pub created_at: DateTime<Utc>,
+    pub reverted_at: Option<DateTime<Utc>>,
+    pub transaction_ids: Vec<String>, // inserted rows still in the ledger; both legs of transfers
+}
+
+impl<'r> FromRow<'r, SqliteRow> for ImportBatch {
+    fn from_row(row: &'r SqliteRow) -> Result<Self, sqlx::Error> {
+        let reverted_at: Option<String> = row.try_get("reverted_at")?;
+
+        Ok(ImportBatch {
+            id: row.try_get("id")?,
+            file_name: row.try_get("file_name")?,
+            file_hash: row.try_get("file_hash")?,
+            account_id: row.try_get("account_id")?,
+            inserted: row.try_get("inserted")?,
+            skipped: row.try_get("skipped")?,
+            created_at: timestamp::decode(row, "created_at")?,
+            reverted_at: match reverted_at {
+                Some(_) => Some(timestamp::decode(row, "reverted_at")?),
+                None => None,
+            },
+            transaction_ids: Vec::new(),
+        })
+    }
+}
+
+impl Database {
+    // Imports rows and transfers as one batch recorded against the source file
+    pub async fn import_batch(
+        &self,
+        file_path: Option<&str>,
+        transactions: Vec<ImportTransaction>,
+        statement_balances: &[StatementBalance],
+        transfers: Vec<ImportTransfer>,
+    ) -> Result<ImportResult, AppError> {
+        let accounts: BTreeSet<&str> = transactions
+            .iter()
+            .map(|tx| tx.account_id.as_str())
+            .chain(transfers.iter().flat_map(|t| [t.transfer.from_account_id.as_str(), t.transfer.to_account_id.as_str()]))
+            .collect();
+        let account_id = match accounts.len() {
+            1 => accounts.into_iter().next().map(str::to_string),
+            _ => None,
+        };
+
+        let batch_id = self.start_import_batch(file_path, account_id).await?;
+        let mut result = self.import_transactions(transactions, statement_balances, Some(&batch_id)).await?;
+        if !transfers.is_empty() {
+            result.absorb(self.import_transfers(transfers, Some(&batch_id)).await?);
+        }
+
+        sqlx::query("UPDATE import_batches SET inserted = ?, skipped = ? WHERE id = ?")
+            .bind(result.inserted as i64)
+            .bind(result.skipped as i64)
+            .bind(&batch_id)
+            .execute(self.get_pool())
+            .await?;
+
+        result.batch_id = Some(batch_id);
+        Ok(result)
+    }
+
+    async fn start_import_batch(&self, file_path: Option<&str>, account_id: Option<String>) -> Result<String, AppError> {
+        let id = Uuid::new_v4().to_string();
+        let file_name = file_path
+            .and_then(|path| Path::new(path).file_name())
+            .map(|name| name.to_string_lossy().to_string());
+        // The file may be gone by now; the batch is still worth recording
+        let file_hash = file_path
+            .and_then(|path| std::fs::read(path).ok())
+            .map(|bytes| format!("{:x}", Sha256::digest(&bytes)));
+
+        sqlx::query(
+            r#"
+            INSERT INTO import_batches (id, file_name, file_hash, account_id, created_at)
+            VALUES (?, ?, ?, ?, ?)
+            "#
+        )
+        .bind(&id)
+        .bind(&file_name)
+        .bind(&file_hash)
+        .bind(&account_id)
+        .bind(timestamp::format(&timestamp::now()))
+        .execute(self.get_pool())
+        .await?;
+
+        Ok(id)
+    }
+
+    pub(crate) async fn link_import_batch_in(
+        conn: &mut SqliteConnection,
+        batch_id: &str,
+        transaction_id: &str,
+    ) -> Result<(), AppError> {
+        sqlx::query("INSERT INTO import_batch_transactions (batch_id, transaction_id) VALUES (?, ?)")
+            .bind(batch_id)
+            .bind(transaction_id)
+            .execute(&mut *conn)
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn get_import_batches(&self) -> Result<Vec<ImportBatch>, AppError> {
+        let mut batches = sqlx::query_as::<_, ImportBatch>("SELECT * FROM import_batches ORDER BY created_at DESC")
+            .fetch_all(self.get_pool())
+            .await?;
+
+        let links: Vec<(String, String)> = sqlx::query_as(
+            "SELECT batch_id, transaction_id FROM import_batch_transactions ORDER BY rowid"
+        )
+        .fetch_all(self.get_pool())
+        .await?;
+
+        let mut transaction_ids: HashMap<String, Vec<String>> = HashMap::new();
+        for (batch_id, transaction_id) in links {
+            transaction_ids.entry(batch_id).or_default().push(transaction_id);
+        }
+        for batch in batches.iter_mut() {
+            batch.transaction_ids = transaction_ids.remove(&batch.id).unwrap_or_default();
+        }
+
+        Ok(batches)
+    }
+
+    // Deletes every transaction the batch inserted, reversing balances and dropping their
+    // tags, plus the statement balances it saved. The batch itself stays, marked reverted.
+    pub async fn revert_import_batch(&self, id: String) -> Result<ImportBatch, AppError> {
+        let mut conn = self.begin().await?;
+
+        let reverted_at: Option<Option<String>> = sqlx::query_scalar("SELECT reverted_at FROM import_batches WHERE id = ?")
+            .bind(&id)
+            .fetch_optional(&mut *conn)
+            .await?;
+        match reverted_at {
+            None => return Err(AppError::not_found("import batch")),
+            Some(Some(_)) => return Err(AppError::conflict("Import batch was already reverted")),
+            Some(None) => {}
+        }
+
+        let transaction_ids: Vec<String> = sqlx::query_scalar(
+            "SELECT transaction_id FROM import_batch_transactions WHERE batch_id = ? ORDER BY rowid"
+        )
+        .bind(&id)
+        .fetch_all(&mut *conn)
+        .await?;
+
+        // Deleting one leg of a transfer removes both, so the other leg is already gone
+        for transaction_id in &transaction_ids {
+            Self::delete_transaction_in(&mut conn, transaction_id).await?;
+        }
+
+        sqlx::query("DELETE FROM statement_balances WHERE import_batch_id = ?")
+            .bind(&id)
+            .execute(&mut *conn)
+            .await?;
+
+        sqlx::query("UPDATE import_batches SET reverted_at = ? WHERE id = ?")
+            .bind(timestamp::format(&timestamp::now()))
+            .bind(&id)
+            .execute(&mut *conn)
+            .await?;
+
+        conn.commit().await?;
+
+        self.get_import_batches()
+            .await?
+            .into_iter()
+            .find(|batch| batch.id == id)
+            .ok_or_else(|| AppError::not_found("import batch"))
+    }
+}
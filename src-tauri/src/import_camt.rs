@@ -0,0 +1,390 @@
+// import_camt.rs
+// ISO 20022 camt.053 (bank to customer statement) parsing. Elements are matched by local
+// name, so every camt.053.001.xx namespace version is read the same way.
+
+use chrono::NaiveDate;
+use roxmltree::{Document, Node};
+use std::path::Path;
+use crate::database::Database;
+use crate::error::AppError;
+use crate::import::{decode_text, midnight, ImportPreview, ImportTransaction, PreviewRow, StatementBalance};
+use crate::money::Money;
+use crate::timestamp;
+
+// One Stmt element: an account and its entries for the statement period
+#[derive(Debug, Clone)]
+pub struct CamtStatement {
+    pub iban: String,
+    pub entries: Vec<CamtEntry>,
+    pub closing_balance: Option<CamtBalance>,
+}
+
+#[derive(Debug, Clone)]
+pub struct CamtBalance {
+    pub date: NaiveDate,
+    pub amount: String, // signed, e.g. "-12.50"
+    pub currency: String,
+}
+
+// A booked amount; batch entries with several TxDtls are split into one CamtEntry each
+#[derive(Debug, Clone)]
+pub struct CamtEntry {
+    pub entry_number: usize, // position of the Ntry in the statement, 1-based
+    pub reference: Option<String>,
+    pub amount: String,
+    pub currency: String,
+    pub credit: bool,
+    pub status: String, // BOOK, PDNG or INFO
+    pub booking_date: Option<NaiveDate>,
+    pub value_date: Option<NaiveDate>,
+    pub counterparty_name: Option<String>,
+    pub counterparty_iban: Option<String>,
+    pub remittance: String,
+}
+
+impl Database {
+    // `bank_account` picks the statement by IBAN when the file holds several accounts
+    pub async fn preview_camt_import(
+        &self,
+        file_path: &str,
+        account_id: &str,
+        default_category_id: &str,
+        bank_account: Option<String>,
+    ) -> Result<ImportPreview, AppError> {
+        let bytes = std::fs::read(Path::new(file_path))?;
+        let currency = self.account_currency(account_id).await?;
+        let statements = parse_camt(&bytes)?;
+
+        let statement = match bank_account {
+            Some(bank_account) => statements
+                .into_iter()
+                .find(|statement| same_iban(&statement.iban, &bank_account))
+                .ok_or_else(|| AppError::validation("bank_account", format!("no statement for account {}", bank_account)))?,
+            None if statements.len() > 1 => {
+                let accounts: Vec<_> = statements.iter().map(|s| s.iban.as_str()).collect();
+                return Err(AppError::validation(
+                    "bank_account",
+                    format!("file contains statements for {}; choose one", accounts.join(", ")),
+                ));
+            }
+            None => statements
+                .into_iter()
+                .next()
+                .ok_or_else(|| AppError::validation("file", "no statement found"))?,
+        };
+
+        let (rows, balances) = statement_rows(&statement, account_id, &currency, default_category_id);
+        self.build_preview(rows, balances).await
+    }
+}
+
+fn statement_rows(
+    statement: &CamtStatement,
+    account_id: &str,
+    currency: &str,
+    default_category_id: &str,
+) -> (Vec<PreviewRow>, Vec<StatementBalance>) {
+    let rows = statement
+        .entries
+        .iter()
+        .map(|entry| {
+            let line = entry.entry_number;
+            if entry.status != "BOOK" {
+                return PreviewRow::failed(line, format!("Entry is not booked ({})", entry.status));
+            }
+            if entry.currency != currency {
+                return PreviewRow::failed(line, format!("Entry is in {}, account is in {}", entry.currency, currency));
+            }
+            let date = match entry.booking_date.or(entry.value_date) {
+                Some(date) => date,
+                None => return PreviewRow::failed(line, "Missing booking date"),
+            };
+            let amount = match Money::parse(&entry.amount, currency) {
+                Ok(amount) if !amount.is_zero() => amount.abs(),
+                Ok(_) => return PreviewRow::failed(line, "Amount is zero"),
+                Err(e) => return PreviewRow::failed(line, e.to_string()),
+            };
+
+            let description = [entry.counterparty_name.as_deref().unwrap_or(""), entry.remittance.as_str()]
+                .iter()
+                .filter(|part| !part.is_empty())
+                .copied()
+                .collect::<Vec<_>>()
+                .join(" ");
+
+            PreviewRow::parsed(line, ImportTransaction {
+                account_id: account_id.to_string(),
+                category_id: default_category_id.to_string(),
+                amount,
+                description,
+                transaction_type: if entry.credit { "income" } else { "expense" }.to_string(),
+                date: timestamp::format(&midnight(date)),
+                external_id: entry.reference.clone(),
+                value_date: entry.value_date.map(|date| timestamp::format(&midnight(date))),
+                counterparty_name: entry.counterparty_name.clone(),
+                counterparty_iban: entry.counterparty_iban.clone(),
+                cleared_status: None,
+                allow_duplicate: false,
+            })
+        })
+        .collect();
+
+    let balances = statement
+        .closing_balance
+        .as_ref()
+        .filter(|balance| balance.currency == currency)
+        .and_then(|balance| {
+            Money::parse(&balance.amount, currency).ok().map(|amount| StatementBalance {
+                account_id: account_id.to_string(),
+                date: midnight(balance.date),
+                amount,
+            })
+        })
+        .into_iter()
+        .collect();
+
+    (rows, balances)
+}
+
+pub fn parse_camt(bytes: &[u8]) -> Result<Vec<CamtStatement>, AppError> {
+    let text = decode_text(bytes, &declared_encoding(bytes))?;
+    let document = Document::parse(&text).map_err(|e| AppError::validation("file", e.to_string()))?;
+
+    let report = child(document.root_element(), "BkToCstmrStmt")
+        .ok_or_else(|| AppError::validation("file", "not a camt.053 statement"))?;
+
+    Ok(children(report, "Stmt").map(parse_statement).collect())
+}
+
+fn parse_statement(statement: Node) -> CamtStatement {
+    let iban = text(statement, &["Acct", "Id", "IBAN"])
+        .or_else(|| text(statement, &["Acct", "Id", "Othr", "Id"]))
+        .unwrap_or_default();
+
+    let closing_balance = children(statement, "Bal")
+        .find(|balance| text(*balance, &["Tp", "CdOrPrtry", "Cd"]).as_deref() == Some("CLBD"))
+        .and_then(|balance| {
+            let amount = child(balance, "Amt")?;
+            Some(CamtBalance {
+                date: date(balance, "Dt")?,
+                amount: signed(amount.text().unwrap_or("").trim(), &text(balance, &["CdtDbtInd"]).unwrap_or_default()),
+                currency: amount.attribute("Ccy").unwrap_or("").to_string(),
+            })
+        });
+
+    let mut entries = Vec::new();
+    for (index, entry) in children(statement, "Ntry").enumerate() {
+        parse_entry(entry, index + 1, &mut entries);
+    }
+
+    CamtStatement {
+        iban,
+        entries,
+        closing_balance,
+    }
+}
+
+fn parse_entry(entry: Node, entry_number: usize, entries: &mut Vec<CamtEntry>) {
+    let amount = child(entry, "Amt");
+    let credit = text(entry, &["CdtDbtInd"]).as_deref() == Some("CRDT");
+    // Sts is a plain code up to version 06 and wraps a Cd element from 08 on
+    let status = text(entry, &["Sts", "Cd"]).or_else(|| text(entry, &["Sts"])).unwrap_or_else(|| "BOOK".to_string());
+    let reference = text(entry, &["AcctSvcrRef"]);
+    let additional_info = text(entry, &["AddtlNtryInf"]).unwrap_or_default();
+
+    let base = CamtEntry {
+        entry_number,
+        reference: reference.clone(),
+        amount: amount.and_then(|a| a.text()).unwrap_or("").trim().to_string(),
+        currency: amount.and_then(|a| a.attribute("Ccy")).unwrap_or("").to_string(),
+        credit,
+        status,
+        booking_date: date(entry, "BookgDt"),
+        value_date: date(entry, "ValDt"),
+        counterparty_name: None,
+        counterparty_iban: None,
+        remittance: additional_info.clone(),
+    };
+
+    let details: Vec<Node> = child(entry, "NtryDtls")
+        .map(|d| children(d, "TxDtls").collect())
+        .unwrap_or_default();
+    let split = details.len() > 1;
+
+    if details.is_empty() {
+        entries.push(base);
+        return;
+    }
+
+    for (index, tx) in details.into_iter().enumerate() {
+        let mut parsed = base.clone();
+
+        // Counterparty is the creditor of a debit and the debtor of a credit
+        let (party, party_account) = if credit { ("Dbtr", "DbtrAcct") } else { ("Cdtr", "CdtrAcct") };
+        parsed.counterparty_name = text(tx, &["RltdPties", party, "Nm"])
+            .or_else(|| text(tx, &["RltdPties", party, "Pty", "Nm"]));
+        parsed.counterparty_iban = text(tx, &["RltdPties", party_account, "Id", "IBAN"]);
+
+        let unstructured: Vec<String> = child(tx, "RmtInf")
+            .map(|info| children(info, "Ustrd").filter_map(|u| u.text().map(|t| t.trim().to_string())).collect())
+            .unwrap_or_default();
+        parsed.remittance = if !unstructured.is_empty() {
+            unstructured.join(" ")
+        } else {
+            text(tx, &["RmtInf", "Strd", "CdtrRefInf", "Ref"])
+                .or_else(|| text(tx, &["AddtlTxInf"]))
+                .unwrap_or_else(|| additional_info.clone())
+        };
+
+        if split {
+            let tx_amount = child(tx, "Amt").or_else(|| path(tx, &["AmtDtls", "TxAmt", "Amt"]));
+            if let Some(tx_amount) = tx_amount {
+                parsed.amount = tx_amount.text().unwrap_or("").trim().to_string();
+                parsed.currency = tx_amount.attribute("Ccy").unwrap_or("").to_string();
+            }
+            if let Some(indicator) = text(tx, &["CdtDbtInd"]) {
+                parsed.credit = indicator == "CRDT";
+            }
+            parsed.reference = text(tx, &["Refs", "AcctSvcrRef"])
+                .or_else(|| reference.as_ref().map(|r| format!("{}/{}", r, index + 1)));
+        } else if parsed.reference.is_none() {
+            parsed.reference = text(tx, &["Refs", "AcctSvcrRef"]);
+        }
+
+        entries.push(parsed);
+    }
+}
+
+fn child<'a, 'input>(node: Node<'a, 'input>, name: &str) -> Option<Node<'a, 'input>> {
+    node.children().find(|c| c.is_element() && c.tag_name().name() == name)
+}
+
+fn children<'a, 'input: 'a>(node: Node<'a, 'input>, name: &'a str) -> impl Iterator<Item = Node<'a, 'input>> + 'a {
+    node.children().filter(move |c| c.is_element() && c.tag_name().name() == name)
+}
+
+fn path<'a, 'input>(node: Node<'a, 'input>, names: &[&str]) -> Option<Node<'a, 'input>> {
+    names.iter().try_fold(node, |node, name| child(node, name))
+}
+
+fn text(node: Node, names: &[&str]) -> Option<String> {
+    path(node, names)
+        .and_then(|n| n.text())
+        .map(|t| t.trim().to_string())
+        .filter(|t| !t.is_empty())
+}
+
+// <BookgDt><Dt>2024-02-05</Dt></BookgDt> or <DtTm>2024-02-05T10:15:00+02:00</DtTm>
+fn date(node: Node, name: &str) -> Option<NaiveDate> {
+    let value = text(node, &[name, "Dt"]).or_else(|| text(node, &[name, "DtTm"]))?;
+    NaiveDate::parse_from_str(value.get(..10)?, "%Y-%m-%d").ok()
+}
+
+fn signed(amount: &str, indicator: &str) -> String {
+    if indicator == "DBIT" {
+        format!("-{}", amount)
+    } else {
+        amount.to_string()
+    }
+}
+
+fn same_iban(a: &str, b: &str) -> bool {
+    let normalize = |s: &str| s.chars().filter(|c| !c.is_whitespace()).collect::<String>().to_uppercase();
+    normalize(a) == normalize(b)
+}
+
+// Encoding from the XML declaration; camt files are UTF-8 unless they say otherwise
+fn declared_encoding(bytes: &[u8]) -> String {
+    let head = String::from_utf8_lossy(&bytes[..bytes.len().min(200)]).to_string();
+    head.split_once("encoding=")
+        .and_then(|(_, rest)| {
+            let quote = rest.chars().next()?;
+            rest[quote.len_utf8()..].split(quote).next().map(str::to_string)
+        })
+        .unwrap_or_else(|| "utf-8".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CAMT: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<Document xmlns="urn:iso:std:iso:20022:tech:xsd:camt.053.001.08">
+  <BkToCstmrStmt>
+    <GrpHdr><MsgId>MSG1</MsgId><CreDtTm>2024-02-06T08:00:00</CreDtTm></GrpHdr>
+    <Stmt>
+      <Id>STMT-2024-02-05</Id>
+      <Acct><Id><IBAN>RO49AAAA1B31007593840000</IBAN></Id><Ccy>RON</Ccy></Acct>
+      <Bal><Tp><CdOrPrtry><Cd>OPBD</Cd></CdOrPrtry></Tp><Amt Ccy="RON">1000.00</Amt><CdtDbtInd>CRDT</CdtDbtInd><Dt><Dt>2024-02-04</Dt></Dt></Bal>
+      <Bal><Tp><CdOrPrtry><Cd>CLBD</Cd></CdOrPrtry></Tp><Amt Ccy="RON">250.00</Amt><CdtDbtInd>DBIT</CdtDbtInd><Dt><Dt>2024-02-05</Dt></Dt></Bal>
+      <Ntry>
+        <Amt Ccy="RON">1500.00</Amt><CdtDbtInd>DBIT</CdtDbtInd><Sts><Cd>BOOK</Cd></Sts>
+        <BookgDt><Dt>2024-02-05</Dt></BookgDt><ValDt><DtTm>2024-02-06T10:15:00+02:00</DtTm></ValDt>
+        <AcctSvcrRef>REF1</AcctSvcrRef>
+        <NtryDtls><TxDtls>
+          <RltdPties><Cdtr><Pty><Nm>Landlord SRL</Nm></Pty></Cdtr><CdtrAcct><Id><IBAN>RO09BCYP0000001234567890</IBAN></Id></CdtrAcct></RltdPties>
+          <RmtInf><Ustrd>Rent</Ustrd><Ustrd>February</Ustrd></RmtInf>
+        </TxDtls></NtryDtls>
+      </Ntry>
+      <Ntry>
+        <Amt Ccy="RON">300.00</Amt><CdtDbtInd>CRDT</CdtDbtInd><Sts><Cd>BOOK</Cd></Sts>
+        <BookgDt><Dt>2024-02-05</Dt></BookgDt>
+        <AcctSvcrRef>BATCH</AcctSvcrRef>
+        <NtryDtls>
+          <TxDtls><Amt Ccy="RON">100.00</Amt><RltdPties><Dbtr><Nm>Ana</Nm></Dbtr></RltdPties><RmtInf><Ustrd>Gift</Ustrd></RmtInf></TxDtls>
+          <TxDtls><Amt Ccy="RON">200.00</Amt><RltdPties><Dbtr><Nm>Dan</Nm></Dbtr></RltdPties><AddtlTxInf>Refund</AddtlTxInf></TxDtls>
+        </NtryDtls>
+      </Ntry>
+      <Ntry><Amt Ccy="RON">5.00</Amt><CdtDbtInd>DBIT</CdtDbtInd><Sts><Cd>PDNG</Cd></Sts><BookgDt><Dt>2024-02-05</Dt></BookgDt></Ntry>
+      <Ntry><Amt Ccy="EUR">5.00</Amt><CdtDbtInd>DBIT</CdtDbtInd><Sts><Cd>BOOK</Cd></Sts><BookgDt><Dt>2024-02-05</Dt></BookgDt></Ntry>
+    </Stmt>
+  </BkToCstmrStmt>
+</Document>"#;
+
+    #[test]
+    fn statement_entries_and_closing_balance() {
+        let statements = parse_camt(CAMT.as_bytes()).unwrap();
+        assert_eq!(statements.len(), 1);
+        let statement = &statements[0];
+        assert!(same_iban(&statement.iban, "RO49 AAAA 1B31 0075 9384 0000"));
+        assert_eq!(statement.entries.len(), 5);
+
+        let (rows, balances) = statement_rows(statement, "acc", "RON", "cat");
+        let rent = rows[0].transaction.as_ref().unwrap();
+        assert_eq!((rent.description.as_str(), rent.amount.minor_units, rent.transaction_type.as_str()), ("Landlord SRL Rent February", 150000, "expense"));
+        assert_eq!((rent.external_id.as_deref(), rent.counterparty_iban.as_deref()), (Some("REF1"), Some("RO09BCYP0000001234567890")));
+        assert_eq!(rent.value_date.as_deref(), Some("2024-02-06T00:00:00.000Z"));
+
+        // a batch entry is split per TxDtls, with references derived from the entry's
+        let gift = rows[1].transaction.as_ref().unwrap();
+        let refund = rows[2].transaction.as_ref().unwrap();
+        assert_eq!((gift.description.as_str(), gift.amount.minor_units, gift.external_id.as_deref()), ("Ana Gift", 10000, Some("BATCH/1")));
+        assert_eq!((refund.description.as_str(), refund.amount.minor_units, refund.external_id.as_deref()), ("Dan Refund", 20000, Some("BATCH/2")));
+        assert_eq!((rows[1].line, rows[2].line), (2, 2));
+
+        assert_eq!(rows[3].error.as_deref(), Some("Entry is not booked (PDNG)"));
+        assert_eq!(rows[4].error.as_deref(), Some("Entry is in EUR, account is in RON"));
+
+        assert_eq!(balances.len(), 1);
+        assert_eq!(balances[0].amount.minor_units, -25000);
+    }
+
+    #[test]
+    fn reads_the_declared_encoding() {
+        // ISO-8859-2: 0xBA is "ş"
+        let bytes = b"<?xml version='1.0' encoding='ISO-8859-2'?>\
+<Document><BkToCstmrStmt><Stmt><Acct><Id><Othr><Id>123</Id></Othr></Id></Acct>\
+<Ntry><Amt Ccy=\"RON\">1</Amt><CdtDbtInd>DBIT</CdtDbtInd><Sts>BOOK</Sts><BookgDt><Dt>2024-02-05</Dt></BookgDt>\
+<AddtlNtryInf>Pl\xe3\xbai</AddtlNtryInf></Ntry></Stmt></BkToCstmrStmt></Document>";
+        let statements = parse_camt(bytes).unwrap();
+        assert_eq!(statements[0].iban, "123");
+        assert_eq!(statements[0].entries[0].remittance, "Plăşi");
+        assert_eq!(statements[0].entries[0].status, "BOOK");
+    }
+
+    #[test]
+    fn rejects_other_documents() {
+        assert!(parse_camt(b"<Document><BkToCstmrDbtCdtNtfctn/></Document>").is_err());
+        assert!(parse_camt(b"not xml").is_err());
+    }
+}
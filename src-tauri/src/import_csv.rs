@@ -0,0 +1,506 @@
+// import_csv.rs
+// Bank statement CSV parsing driven by per-bank column mappings, plus the saved profiles
+
+use serde::{Deserialize, Serialize};
+use sqlx::{sqlite::SqliteRow, FromRow, Row};
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
+use csv::StringRecord;
+use encoding_rs::Encoding;
+use std::collections::HashMap;
+use std::path::Path;
+use uuid::Uuid;
+use crate::database::Database;
+use crate::error::AppError;
+use crate::import::{decode_text, ImportPreview, ImportTransaction, PreviewRow};
+use crate::money::Money;
+use crate::timestamp;
+
+// Where the amount of a row comes from
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum AmountColumns {
+    // One column, negative values are expenses
+    Signed { column: String },
+    // Separate debit (expense) and credit (income) columns, one of them empty per row
+    DebitCredit { debit: String, credit: String },
+}
+
+// Columns are referenced by header name (case-insensitive) or by 0-based index
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[serde(default)]
+pub struct CsvMapping {
+    pub delimiter: char,
+    pub encoding: String,   // WHATWG label, e.g. "utf-8" or "windows-1250"
+    pub skip_rows: usize,   // lines before the header (bank name, IBAN, period...)
+    pub has_header: bool,
+    pub date_column: String,
+    pub date_formats: Vec<String>, // chrono formats, tried in order
+    pub description_columns: Vec<String>, // joined with a space
+    pub amount: AmountColumns,
+    pub decimal_separator: char, // '.' or ','; the other one is treated as a thousands separator
+    pub category_column: Option<String>, // matched against category names
+}
+
+impl Default for CsvMapping {
+    fn default() -> Self {
+        CsvMapping {
+            delimiter: ',',
+            encoding: "utf-8".to_string(),
+            skip_rows: 0,
+            has_header: true,
+            date_column: "0".to_string(),
+            date_formats: vec!["%Y-%m-%d".to_string()],
+            description_columns: vec!["1".to_string()],
+            amount: AmountColumns::Signed { column: "2".to_string() },
+            decimal_separator: '.',
+            category_column: None,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CsvProfile {
+    pub id: String,
+    pub name: String,
+    pub mapping: CsvMapping,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl<'r> FromRow<'r, SqliteRow> for CsvProfile {
+    fn from_row(row: &'r SqliteRow) -> Result<Self, sqlx::Error> {
+        let mapping: String = row.try_get("mapping")?;
+        let mapping = serde_json::from_str(&mapping).map_err(|e| sqlx::Error::ColumnDecode {
+            index: "mapping".to_string(),
+            source: e.into(),
+        })?;
+
+        Ok(CsvProfile {
+            id: row.try_get("id")?,
+            name: row.try_get("name")?,
+            mapping,
+            created_at: timestamp::decode(row, "created_at")?,
+            updated_at: timestamp::decode(row, "updated_at")?,
+        })
+    }
+}
+
+// Column positions resolved against the header once per file
+struct ResolvedColumns {
+    date: usize,
+    description: Vec<usize>,
+    amount: ResolvedAmount,
+    category: Option<usize>,
+}
+
+enum ResolvedAmount {
+    Signed(usize),
+    DebitCredit { debit: usize, credit: usize },
+}
+
+impl Database {
+    // Parses the file without writing anything; the frontend sends the rows it keeps
+    // to batch_insert_transactions
+    pub async fn preview_csv_import(
+        &self,
+        file_path: &str,
+        account_id: &str,
+        default_category_id: &str,
+        mapping: &CsvMapping,
+    ) -> Result<ImportPreview, AppError> {
+        let bytes = std::fs::read(Path::new(file_path))?;
+        let currency = self.account_currency(account_id).await?;
+        let categories: HashMap<String, String> = self
+            .get_categories()
+            .await?
+            .into_iter()
+            .map(|category| (category.name.trim().to_lowercase(), category.id))
+            .collect();
+
+        let rows = parse_csv(&bytes, mapping, account_id, &currency, default_category_id, &categories)?;
+
+        self.build_preview(rows, Vec::new()).await
+    }
+
+    // Creates the profile when id is None, otherwise replaces it
+    pub async fn save_csv_profile(&self, id: Option<String>, name: String, mapping: CsvMapping) -> Result<CsvProfile, AppError> {
+        let name = name.trim().to_string();
+        if name.is_empty() {
+            return Err(AppError::validation("name", "must not be empty"));
+        }
+        validate_mapping(&mapping)?;
+        let mapping_json = serde_json::to_string(&mapping)
+            .map_err(|e| AppError::validation("mapping", e.to_string()))?;
+        let now = timestamp::now();
+
+        let id = match id {
+            Some(id) => {
+                let result = sqlx::query("UPDATE csv_profiles SET name = ?, mapping = ?, updated_at = ? WHERE id = ?")
+                    .bind(&name)
+                    .bind(&mapping_json)
+                    .bind(timestamp::format(&now))
+                    .bind(&id)
+                    .execute(self.get_pool())
+                    .await?;
+
+                if result.rows_affected() == 0 {
+                    return Err(AppError::not_found("csv profile"));
+                }
+                id
+            }
+            None => {
+                let id = Uuid::new_v4().to_string();
+                sqlx::query(
+                    "INSERT INTO csv_profiles (id, name, mapping, created_at, updated_at) VALUES (?, ?, ?, ?, ?)"
+                )
+                .bind(&id)
+                .bind(&name)
+                .bind(&mapping_json)
+                .bind(timestamp::format(&now))
+                .bind(timestamp::format(&now))
+                .execute(self.get_pool())
+                .await?;
+                id
+            }
+        };
+
+        let profile = sqlx::query_as::<_, CsvProfile>("SELECT * FROM csv_profiles WHERE id = ?")
+            .bind(&id)
+            .fetch_one(self.get_pool())
+            .await?;
+
+        Ok(profile)
+    }
+
+    pub async fn get_csv_profiles(&self) -> Result<Vec<CsvProfile>, AppError> {
+        let profiles = sqlx::query_as::<_, CsvProfile>("SELECT * FROM csv_profiles ORDER BY name")
+            .fetch_all(self.get_pool())
+            .await?;
+
+        Ok(profiles)
+    }
+
+    pub async fn delete_csv_profile(&self, id: String) -> Result<(), AppError> {
+        let result = sqlx::query("DELETE FROM csv_profiles WHERE id = ?")
+            .bind(&id)
+            .execute(self.get_pool())
+            .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(AppError::not_found("csv profile"));
+        }
+
+        Ok(())
+    }
+}
+
+fn validate_mapping(mapping: &CsvMapping) -> Result<(), AppError> {
+    if !mapping.delimiter.is_ascii() {
+        return Err(AppError::validation("delimiter", "must be a single ASCII character"));
+    }
+    if Encoding::for_label(mapping.encoding.as_bytes()).is_none() {
+        return Err(AppError::validation("encoding", format!("unknown encoding {}", mapping.encoding)));
+    }
+    if mapping.decimal_separator != '.' && mapping.decimal_separator != ',' {
+        return Err(AppError::validation("decimal_separator", "must be '.' or ','"));
+    }
+    if mapping.date_formats.is_empty() {
+        return Err(AppError::validation("date_formats", "at least one format is required"));
+    }
+    if mapping.description_columns.is_empty() {
+        return Err(AppError::validation("description_columns", "at least one column is required"));
+    }
+
+    Ok(())
+}
+
+// `categories` maps lowercased category names to ids; rows whose category is
+// missing or unknown get `default_category_id`
+pub fn parse_csv(
+    bytes: &[u8],
+    mapping: &CsvMapping,
+    account_id: &str,
+    currency: &str,
+    default_category_id: &str,
+    categories: &HashMap<String, String>,
+) -> Result<Vec<PreviewRow>, AppError> {
+    validate_mapping(mapping)?;
+    let text = decode_text(bytes, &mapping.encoding)?;
+
+    // Preamble lines are dropped as text: they rarely have the same shape as the table
+    let body_start: usize = text
+        .split_inclusive('\n')
+        .take(mapping.skip_rows)
+        .map(str::len)
+        .sum();
+
+    let body = &text.as_bytes()[body_start..];
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(mapping.delimiter as u8)
+        .has_headers(false)
+        .flexible(true)
+        .from_reader(body);
+
+    let mut records = reader.records();
+    let header = if mapping.has_header {
+        match records.next() {
+            Some(record) => Some(record.map_err(|e| AppError::validation("file", e.to_string()))?),
+            None => return Ok(Vec::new()),
+        }
+    } else {
+        None
+    };
+    let columns = resolve_columns(mapping, header.as_ref())?;
+
+    let mut rows = Vec::new();
+    for record in records {
+        let record = match record {
+            Ok(record) => record,
+            Err(e) => {
+                let line = e.position().map(|p| line_at(body, p)).unwrap_or(0) + mapping.skip_rows;
+                rows.push(PreviewRow::failed(line, e.to_string()));
+                continue;
+            }
+        };
+        if record.iter().all(|field| field.trim().is_empty()) {
+            continue;
+        }
+
+        let line = record.position().map(|p| line_at(body, p)).unwrap_or(0) + mapping.skip_rows;
+        let row = match parse_record(&record, mapping, &columns, currency) {
+            Ok((date, amount, description, transaction_type, category_name)) => {
+                let category_id = category_name
+                    .and_then(|name| categories.get(&name.to_lowercase()).cloned())
+                    .unwrap_or_else(|| default_category_id.to_string());
+
+                PreviewRow::parsed(line, ImportTransaction {
+                    account_id: account_id.to_string(),
+                    category_id,
+                    amount,
+                    description,
+                    transaction_type,
+                    date: timestamp::format(&date),
+                    external_id: None,
+                    value_date: None,
+                    counterparty_name: None,
+                    counterparty_iban: None,
+                    cleared_status: None,
+                    allow_duplicate: false,
+                })
+            }
+            Err(message) => PreviewRow::failed(line, message),
+        };
+        rows.push(row);
+    }
+
+    Ok(rows)
+}
+
+// 1-based line of a record. csv's own line count is off for \r\n line endings, where the
+// reported start is the \n of the previous line, so that byte is counted as well.
+pub(crate) fn line_at(body: &[u8], position: &csv::Position) -> usize {
+    let end = (position.byte() as usize + 1).min(body.len());
+    body[..end].iter().filter(|&&b| b == b'\n').count() + 1
+}
+
+fn resolve_columns(mapping: &CsvMapping, header: Option<&StringRecord>) -> Result<ResolvedColumns, AppError> {
+    let amount = match &mapping.amount {
+        AmountColumns::Signed { column } => ResolvedAmount::Signed(resolve_column(column, header)?),
+        AmountColumns::DebitCredit { debit, credit } => ResolvedAmount::DebitCredit {
+            debit: resolve_column(debit, header)?,
+            credit: resolve_column(credit, header)?,
+        },
+    };
+
+    Ok(ResolvedColumns {
+        date: resolve_column(&mapping.date_column, header)?,
+        description: mapping
+            .description_columns
+            .iter()
+            .map(|column| resolve_column(column, header))
+            .collect::<Result<_, _>>()?,
+        amount,
+        category: mapping
+            .category_column
+            .as_deref()
+            .map(|column| resolve_column(column, header))
+            .transpose()?,
+    })
+}
+
+fn resolve_column(column: &str, header: Option<&StringRecord>) -> Result<usize, AppError> {
+    let wanted = column.trim();
+
+    if let Some(header) = header {
+        if let Some(index) = header.iter().position(|name| name.trim().eq_ignore_ascii_case(wanted)) {
+            return Ok(index);
+        }
+    }
+
+    wanted
+        .parse()
+        .map_err(|_| AppError::validation("column", format!("column {} not found in the header", column)))
+}
+
+type ParsedRecord = (DateTime<Utc>, Money, String, String, Option<String>);
+
+fn parse_record(record: &StringRecord, mapping: &CsvMapping, columns: &ResolvedColumns, currency: &str) -> Result<ParsedRecord, String> {
+    let field = |index: usize| record.get(index).map(str::trim).unwrap_or("");
+
+    let raw_date = field(columns.date);
+    let date = parse_date(raw_date, &mapping.date_formats)
+        .ok_or_else(|| format!("Invalid date: {}", raw_date))?;
+
+    let signed = match columns.amount {
+        ResolvedAmount::Signed(index) => parse_amount(field(index), mapping.decimal_separator, currency)?,
+        ResolvedAmount::DebitCredit { debit, credit } => {
+            let debit = parse_optional_amount(field(debit), mapping.decimal_separator, currency)?;
+            let credit = parse_optional_amount(field(credit), mapping.decimal_separator, currency)?;
+            match (debit, credit) {
+                (Some(debit), None) => debit.abs().negate(),
+                (None, Some(credit)) => credit.abs(),
+                (None, None) => return Err("Both debit and credit are empty".to_string()),
+                (Some(_), Some(_)) => return Err("Both debit and credit are filled".to_string()),
+            }
+        }
+    };
+    if signed.is_zero() {
+        return Err("Amount is zero".to_string());
+    }
+    let transaction_type = if signed.is_negative() { "expense" } else { "income" };
+
+    let description = columns
+        .description
+        .iter()
+        .map(|&index| field(index))
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    let category = columns
+        .category
+        .map(|index| field(index).to_string())
+        .filter(|name| !name.is_empty());
+
+    Ok((date, signed.abs(), description, transaction_type.to_string(), category))
+}
+
+pub(crate) fn parse_date(value: &str, formats: &[String]) -> Option<DateTime<Utc>> {
+    for format in formats {
+        if let Ok(datetime) = NaiveDateTime::parse_from_str(value, format) {
+            return Some(datetime.and_utc());
+        }
+        if let Ok(date) = NaiveDate::parse_from_str(value, format) {
+            return date.and_hms_opt(0, 0, 0).map(|datetime| datetime.and_utc());
+        }
+    }
+
+    None
+}
+
+// "1.234,56" with a decimal comma, "1,234.56" with a decimal point
+pub(crate) fn parse_amount(value: &str, decimal_separator: char, currency: &str) -> Result<Money, String> {
+    let thousands_separator = if decimal_separator == ',' { '.' } else { ',' };
+    let normalized: String = value
+        .chars()
+        .filter(|c| *c != thousands_separator && !c.is_whitespace())
+        .collect();
+
+    Money::parse(&normalized, currency).map_err(|e| e.to_string())
+}
+
+fn parse_optional_amount(value: &str, decimal_separator: char, currency: &str) -> Result<Option<Money>, String> {
+    if value.is_empty() {
+        return Ok(None);
+    }
+
+    let amount = parse_amount(value, decimal_separator, currency)?;
+    Ok(if amount.is_zero() { None } else { Some(amount) })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(bytes: &[u8], mapping: &CsvMapping) -> Vec<PreviewRow> {
+        let categories = HashMap::from([("food".to_string(), "cat-food".to_string())]);
+        parse_csv(bytes, mapping, "acc", "RON", "cat-default", &categories).unwrap()
+    }
+
+    #[test]
+    fn signed_amounts_with_decimal_point() {
+        let csv = "Date,Description,Amount,Category\n\
+                   2024-03-01,Salary,\"4,500.00\",\n\
+                   2024-03-02,Lidl,-123.45,Food\n";
+        let rows = parse(csv.as_bytes(), &CsvMapping { category_column: Some("category".to_string()), ..CsvMapping::default() });
+
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows.iter().map(|r| r.line).collect::<Vec<_>>(), [2, 3]);
+        let salary = rows[0].transaction.as_ref().unwrap();
+        assert_eq!((salary.amount.minor_units, salary.transaction_type.as_str()), (450000, "income"));
+        assert_eq!(salary.category_id, "cat-default");
+        let lidl = rows[1].transaction.as_ref().unwrap();
+        assert_eq!((lidl.amount.minor_units, lidl.transaction_type.as_str()), (12345, "expense"));
+        assert_eq!((lidl.category_id.as_str(), lidl.date.as_str()), ("cat-food", "2024-03-02T00:00:00.000Z"));
+    }
+
+    #[test]
+    fn bank_export_with_preamble_debit_credit_and_decimal_comma() {
+        // windows-1250: 0xE3 is "ă"
+        let bytes: &[u8] = b"Banca Transilvania\r\nCont RO49AAAA1B31007593840000\r\n\
+            Data;Detalii;Debit;Credit\r\n\
+            05.03.2024;Plat\xe3 card;1.234,56;\r\n\
+            ;;;\r\n\
+            06.03.2024;Dob\xe2nd\xe3;;0,07\r\n\
+            07.03.2024;Nimic;;\r\n\
+            2024-03-08;Format gresit;10,00;\r\n";
+        let mapping = CsvMapping {
+            delimiter: ';',
+            encoding: "windows-1250".to_string(),
+            skip_rows: 2,
+            date_column: "data".to_string(),
+            date_formats: vec!["%d.%m.%Y".to_string()],
+            description_columns: vec!["Detalii".to_string()],
+            amount: AmountColumns::DebitCredit { debit: "Debit".to_string(), credit: "Credit".to_string() },
+            decimal_separator: ',',
+            ..CsvMapping::default()
+        };
+        let rows = parse(bytes, &mapping);
+
+        assert_eq!(rows.len(), 4);
+        let card = rows[0].transaction.as_ref().unwrap();
+        assert_eq!(rows[0].line, 4);
+        assert_eq!((card.description.as_str(), card.amount.minor_units, card.transaction_type.as_str()), ("Plată card", 123456, "expense"));
+        let interest = rows[1].transaction.as_ref().unwrap();
+        assert_eq!((interest.description.as_str(), interest.amount.minor_units, interest.transaction_type.as_str()), ("Dobândă", 7, "income"));
+        assert_eq!(rows[2].error.as_deref(), Some("Both debit and credit are empty"));
+        assert_eq!(rows[3].error.as_deref(), Some("Invalid date: 2024-03-08"));
+    }
+
+    #[test]
+    fn columns_by_index_without_header() {
+        let csv = "2024-03-01 14:30:00|Taxi|Cluj|-25.5\n";
+        let mapping = CsvMapping {
+            delimiter: '|',
+            has_header: false,
+            date_formats: vec!["%d/%m/%Y".to_string(), "%Y-%m-%d %H:%M:%S".to_string()],
+            description_columns: vec!["1".to_string(), "2".to_string()],
+            amount: AmountColumns::Signed { column: "3".to_string() },
+            ..CsvMapping::default()
+        };
+        let rows = parse(csv.as_bytes(), &mapping);
+
+        let taxi = rows[0].transaction.as_ref().unwrap();
+        assert_eq!((taxi.description.as_str(), taxi.amount.minor_units), ("Taxi Cluj", 2550));
+        assert_eq!(taxi.date, "2024-03-01T14:30:00.000Z");
+    }
+
+    #[test]
+    fn rejects_unknown_columns_and_encodings() {
+        let csv = b"Date,Description,Amount\n2024-03-01,x,1\n";
+        let missing = CsvMapping { date_column: "Booking date".to_string(), ..CsvMapping::default() };
+        assert!(parse_csv(csv, &missing, "acc", "RON", "cat", &HashMap::new()).is_err());
+        let encoding = CsvMapping { encoding: "klingon".to_string(), ..CsvMapping::default() };
+        assert!(parse_csv(csv, &encoding, "acc", "RON", "cat", &HashMap::new()).is_err());
+    }
+}
@@ -0,0 +1,352 @@
+// import_dedup.rs
+// Duplicate detection for imports. A row whose fingerprint matches an existing transaction is
+// skipped; a row that only resembles one is held back as a suspected duplicate for review.
+
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{sqlite::SqliteRow, FromRow, Row};
+use std::collections::{HashMap, HashSet};
+use crate::database::{Database, Transaction};
+use crate::error::AppError;
+use crate::import::{midnight, ImportTransaction};
+use crate::timestamp;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DuplicateSettings {
+    pub fuzzy_enabled: bool,
+    pub date_tolerance_days: i64,
+    pub amount_tolerance: i64, // minor units
+    pub min_similarity: f64,   // share of description words in common, 0 to 1
+}
+
+// An incoming row held back because it looks like `matched`
+#[derive(Debug, Serialize, Clone)]
+pub struct SuspectedDuplicate {
+    pub transaction: ImportTransaction,
+    pub matched: Transaction,
+    pub similarity: f64,
+}
+
+#[derive(Debug, Clone)]
+pub enum DuplicateMatch {
+    New,
+    Exact(Transaction),
+    Suspected(Transaction, f64),
+}
+
+// State of one import batch. An existing transaction is matched by at most one incoming row
+// and rows inserted by the batch are never matched, so two identical coffees on one day are
+// both kept while re-importing them skips both.
+pub struct DuplicateMatcher {
+    settings: DuplicateSettings,
+    occurrences: HashMap<String, usize>,
+    claimed: HashSet<String>,
+}
+
+impl<'r> FromRow<'r, SqliteRow> for DuplicateSettings {
+    fn from_row(row: &'r SqliteRow) -> Result<Self, sqlx::Error> {
+        Ok(DuplicateSettings {
+            fuzzy_enabled: row.try_get("fuzzy_enabled")?,
+            date_tolerance_days: row.try_get("date_tolerance_days")?,
+            amount_tolerance: row.try_get("amount_tolerance")?,
+            min_similarity: row.try_get("min_similarity")?,
+        })
+    }
+}
+
+impl DuplicateMatcher {
+    pub fn new(settings: DuplicateSettings) -> Self {
+        DuplicateMatcher {
+            settings,
+            occurrences: HashMap::new(),
+            claimed: HashSet::new(),
+        }
+    }
+
+    pub fn claim(&mut self, transaction_id: &str) {
+        self.claimed.insert(transaction_id.to_string());
+    }
+}
+
+impl Database {
+    pub async fn get_duplicate_settings(&self) -> Result<DuplicateSettings, AppError> {
+        let settings = sqlx::query_as::<_, DuplicateSettings>("SELECT * FROM duplicate_settings WHERE id = 1")
+            .fetch_one(self.get_pool())
+            .await?;
+
+        Ok(settings)
+    }
+
+    pub async fn save_duplicate_settings(&self, settings: DuplicateSettings) -> Result<DuplicateSettings, AppError> {
+        if settings.date_tolerance_days < 0 {
+            return Err(AppError::validation("date_tolerance_days", "must not be negative"));
+        }
+        if settings.amount_tolerance < 0 {
+            return Err(AppError::validation("amount_tolerance", "must not be negative"));
+        }
+        if !(0.0..=1.0).contains(&settings.min_similarity) {
+            return Err(AppError::validation("min_similarity", "must be between 0 and 1"));
+        }
+
+        sqlx::query(
+            r#"
+            UPDATE duplicate_settings
+            SET fuzzy_enabled = ?, date_tolerance_days = ?, amount_tolerance = ?, min_similarity = ?, updated_at = ?
+            WHERE id = 1
+            "#
+        )
+        .bind(settings.fuzzy_enabled)
+        .bind(settings.date_tolerance_days)
+        .bind(settings.amount_tolerance)
+        .bind(settings.min_similarity)
+        .bind(timestamp::format(&timestamp::now()))
+        .execute(self.get_pool())
+        .await?;
+
+        Ok(settings)
+    }
+
+    pub async fn duplicate_matcher(&self) -> Result<DuplicateMatcher, AppError> {
+        Ok(DuplicateMatcher::new(self.get_duplicate_settings().await?))
+    }
+
+    // Returns the row's fingerprint and what it matched. Rows with a bank id match on that id
+    // alone; other rows on account, day, amount and normalized description.
+    pub async fn match_duplicate(
+        &self,
+        matcher: &mut DuplicateMatcher,
+        tx: &ImportTransaction,
+        date: &DateTime<Utc>,
+    ) -> Result<(String, DuplicateMatch), AppError> {
+        let description = normalize_description(&tx.description);
+        let day = midnight(date.date_naive());
+
+        let fingerprint = match &tx.external_id {
+            Some(external_id) => format!("{}|id:{}", tx.account_id, external_id),
+            None => {
+                let key = format!(
+                    "{}|{}|{}|{}{}|{}",
+                    tx.account_id,
+                    day.format("%Y-%m-%d"),
+                    tx.transaction_type,
+                    tx.amount.minor_units,
+                    tx.amount.currency,
+                    description
+                );
+                let occurrence = matcher.occurrences.entry(key.clone()).or_insert(0);
+                *occurrence += 1;
+                format!("{}#{}", key, occurrence)
+            }
+        };
+
+        let exact = match &tx.external_id {
+            Some(external_id) => {
+                sqlx::query_as::<_, Transaction>("SELECT * FROM transactions WHERE account_id = ? AND external_id = ?")
+                    .bind(&tx.account_id)
+                    .bind(external_id)
+                    .fetch_optional(self.get_pool())
+                    .await?
+            }
+            None => self
+                .candidates(tx, day, day + Duration::days(1), 0, false)
+                .await?
+                .into_iter()
+                .find(|existing| {
+                    !matcher.claimed.contains(&existing.id) && normalize_description(&existing.description) == description
+                }),
+        };
+        if let Some(existing) = exact {
+            matcher.claim(&existing.id);
+            return Ok((fingerprint, DuplicateMatch::Exact(existing)));
+        }
+
+        if !matcher.settings.fuzzy_enabled || tx.allow_duplicate {
+            return Ok((fingerprint, DuplicateMatch::New));
+        }
+
+        // Transactions carrying another bank id are known to be different ones
+        let tolerance = Duration::days(matcher.settings.date_tolerance_days);
+        let candidates = self
+            .candidates(
+                tx,
+                day - tolerance,
+                day + tolerance + Duration::days(1),
+                matcher.settings.amount_tolerance,
+                tx.external_id.is_some(),
+            )
+            .await?;
+
+        let best = candidates
+            .into_iter()
+            .filter(|existing| !matcher.claimed.contains(&existing.id))
+            .map(|existing| {
+                let score = similarity(&description, &normalize_description(&existing.description));
+                (existing, score)
+            })
+            .filter(|(_, score)| *score >= matcher.settings.min_similarity)
+            .min_by(|(a, a_score), (b, b_score)| {
+                b_score
+                    .total_cmp(a_score)
+                    .then(distance(&a.date, date).cmp(&distance(&b.date, date)))
+            });
+
+        match best {
+            Some((existing, score)) => {
+                matcher.claim(&existing.id);
+                Ok((fingerprint, DuplicateMatch::Suspected(existing, score)))
+            }
+            None => Ok((fingerprint, DuplicateMatch::New)),
+        }
+    }
+
+    // Same account, currency and direction, amount within tolerance, dated in [from, to)
+    async fn candidates(
+        &self,
+        tx: &ImportTransaction,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+        amount_tolerance: i64,
+        without_external_id: bool,
+    ) -> Result<Vec<Transaction>, AppError> {
+        let candidates = sqlx::query_as::<_, Transaction>(
+            r#"
+            SELECT * FROM transactions
+            WHERE account_id = ? AND currency = ? AND transaction_type = ?
+              AND amount BETWEEN ? AND ?
+              AND date >= ? AND date < ?
+              AND (? = 0 OR external_id IS NULL)
+            ORDER BY date, created_at, id
+            "#
+        )
+        .bind(&tx.account_id)
+        .bind(&tx.amount.currency)
+        .bind(&tx.transaction_type)
+        .bind(tx.amount.minor_units - amount_tolerance)
+        .bind(tx.amount.minor_units + amount_tolerance)
+        .bind(timestamp::format(&from))
+        .bind(timestamp::format(&to))
+        .bind(without_external_id)
+        .fetch_all(self.get_pool())
+        .await?;
+
+        Ok(candidates)
+    }
+}
+
+// Lowercase words of letters and digits: "LIDL  Cluj-Napoca." becomes "lidl cluj napoca"
+pub fn normalize_description(description: &str) -> String {
+    description
+        .to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+// Dice coefficient of the two word sets
+fn similarity(a: &str, b: &str) -> f64 {
+    let a: HashSet<&str> = a.split(' ').filter(|w| !w.is_empty()).collect();
+    let b: HashSet<&str> = b.split(' ').filter(|w| !w.is_empty()).collect();
+    if a.is_empty() && b.is_empty() {
+        return 1.0;
+    }
+
+    2.0 * a.intersection(&b).count() as f64 / (a.len() + b.len()) as f64
+}
+
+fn distance(a: &DateTime<Utc>, b: &DateTime<Utc>) -> i64 {
+    (*a - *b).num_seconds().abs()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::import_csv::{parse_csv, CsvMapping};
+
+    // A bank CSV export: two identical coffees on one day, then the same statement again
+    // with a third coffee and the Lidl line described differently
+    const FIRST: &str = "Date;Description;Amount\n\
+                         01.03.2024;STARBUCKS CLUJ;-4,50\n\
+                         01.03.2024;STARBUCKS CLUJ;-4,50\n\
+                         02.03.2024;LIDL CLUJ-NAPOCA;-123,45\n";
+    const SECOND: &str = "Date;Description;Amount\n\
+                          01.03.2024;Starbucks  Cluj;-4,50\n\
+                          01.03.2024;STARBUCKS CLUJ;-4,50\n\
+                          01.03.2024;STARBUCKS CLUJ;-4,50\n\
+                          04.03.2024;Lidl Cluj Napoca SRL;-123,45\n";
+
+    fn rows(csv: &str) -> Vec<ImportTransaction> {
+        let mapping = CsvMapping {
+            delimiter: ';',
+            date_formats: vec!["%d.%m.%Y".to_string()],
+            decimal_separator: ',',
+            ..CsvMapping::default()
+        };
+        parse_csv(csv.as_bytes(), &mapping, "ac1-ioan-bt", "RON", "cat", &HashMap::new())
+            .unwrap()
+            .into_iter()
+            .map(|row| row.transaction.unwrap())
+            .collect()
+    }
+
+    async fn database(dir: &std::path::Path) -> Database {
+        let db = Database::new(dir.join("dedup.db")).await.unwrap();
+        db.init_schema().await.unwrap();
+        db
+    }
+
+    #[test]
+    fn descriptions_compare_by_words() {
+        assert_eq!(normalize_description("LIDL  Cluj-Napoca."), "lidl cluj napoca");
+        assert_eq!(similarity("lidl cluj napoca", "lidl cluj napoca"), 1.0);
+        assert!((similarity("lidl cluj napoca", "lidl cluj napoca srl") - 6.0 / 7.0).abs() < 1e-9);
+        assert_eq!(similarity("lidl", "kaufland"), 0.0);
+        assert_eq!(similarity("", ""), 1.0);
+    }
+
+    #[tokio::test]
+    async fn reimport_skips_exact_and_holds_back_similar_rows() {
+        let dir = std::env::temp_dir().join(format!("money-zen-dedup-{}", uuid::Uuid::new_v4()));
+        let db = database(&dir).await;
+        let category_id = db.get_categories().await.unwrap()[0].id.clone();
+        let first: Vec<_> = rows(FIRST).into_iter().map(|tx| ImportTransaction { category_id: category_id.clone(), ..tx }).collect();
+        let result = db.import_transactions(first, &[], None).await.unwrap();
+        assert_eq!(result.inserted, 3);
+
+        let mut matcher = db.duplicate_matcher().await.unwrap();
+        let mut outcomes = Vec::new();
+        let mut fingerprints = Vec::new();
+        for tx in rows(SECOND) {
+            let date = timestamp::parse(&tx.date).unwrap();
+            let (fingerprint, found) = db.match_duplicate(&mut matcher, &tx, &date).await.unwrap();
+            fingerprints.push(fingerprint);
+            outcomes.push(found);
+        }
+
+        // each existing coffee is matched once, so the third one is new
+        assert!(matches!(outcomes[0], DuplicateMatch::Exact(_)));
+        assert!(matches!(outcomes[1], DuplicateMatch::Exact(_)));
+        assert!(matches!(outcomes[2], DuplicateMatch::New));
+        assert_ne!(fingerprints[1], fingerprints[2]);
+        match &outcomes[3] {
+            DuplicateMatch::Suspected(existing, score) => {
+                assert_eq!(existing.description, "LIDL CLUJ-NAPOCA");
+                assert!(*score > 0.8);
+            }
+            other => panic!("expected a suspected duplicate, got {:?}", other),
+        }
+
+        // confirmed rows and rows outside the date tolerance are new
+        let mut matcher = db.duplicate_matcher().await.unwrap();
+        let mut lidl = rows(SECOND).remove(3);
+        lidl.allow_duplicate = true;
+        let date = timestamp::parse(&lidl.date).unwrap();
+        assert!(matches!(db.match_duplicate(&mut matcher, &lidl, &date).await.unwrap().1, DuplicateMatch::New));
+        lidl.allow_duplicate = false;
+        let later = date + Duration::days(5);
+        assert!(matches!(db.match_duplicate(&mut matcher, &lidl, &later).await.unwrap().1, DuplicateMatch::New));
+
+        db.close().await;
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
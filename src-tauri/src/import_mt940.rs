@@ -0,0 +1,429 @@
+// import_mt940.rs
+// SWIFT MT940 statement parsing: :61: statement lines with their :86: details, several
+// statements per file, and the :60F:/:62F: balances each statement is checked against
+
+use chrono::{Datelike, NaiveDate};
+use std::path::Path;
+use crate::database::Database;
+use crate::error::AppError;
+use crate::import::{decode_text, midnight, BalanceCheck, ImportPreview, ImportTransaction, PreviewRow, StatementBalance};
+use crate::money::Money;
+use crate::timestamp;
+
+// Everything from one :20: up to the next
+#[derive(Debug, Clone, Default)]
+pub struct Mt940Statement {
+    pub reference: String,          // :20:
+    pub account: String,            // :25:
+    pub number: String,             // :28C:
+    pub opening: Option<Mt940Balance>, // :60F: or :60M:
+    pub closing: Option<Mt940Balance>, // :62F: or :62M:
+    pub lines: Vec<Mt940Line>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Mt940Balance {
+    pub date: NaiveDate,
+    pub currency: String,
+    pub amount: String, // signed, decimal comma as in the file
+}
+
+#[derive(Debug, Clone)]
+pub struct Mt940Line {
+    pub line: usize, // line of the :61: tag in the file
+    pub value_date: Option<NaiveDate>,
+    pub entry_date: Option<NaiveDate>,
+    pub credit: bool,
+    pub amount: String,
+    pub reference: Option<String>, // bank reference, else the customer reference
+    pub details: String,           // :86: text
+    pub counterparty_name: Option<String>,
+    pub counterparty_iban: Option<String>,
+    pub error: Option<String>,
+}
+
+impl Database {
+    // Lines of every statement in the file go to the selected account
+    pub async fn preview_mt940_import(
+        &self,
+        file_path: &str,
+        account_id: &str,
+        default_category_id: &str,
+    ) -> Result<ImportPreview, AppError> {
+        let bytes = std::fs::read(Path::new(file_path))?;
+        let currency = self.account_currency(account_id).await?;
+        let statements = parse_mt940(&bytes)?;
+
+        if let Some(other) = statements
+            .iter()
+            .filter_map(|statement| statement.opening.as_ref().or(statement.closing.as_ref()))
+            .find(|balance| balance.currency != currency)
+        {
+            return Err(AppError::validation(
+                "currency",
+                format!("statement is in {}, account is in {}", other.currency, currency),
+            ));
+        }
+
+        let mut rows = Vec::new();
+        let mut balances = Vec::new();
+        let mut checks = Vec::new();
+
+        for statement in &statements {
+            rows.extend(statement.lines.iter().map(|line| line_row(line, account_id, &currency, default_category_id)));
+
+            if let Some(closing) = &statement.closing {
+                if let Ok(amount) = Money::parse(&closing.amount, currency.clone()) {
+                    balances.push(StatementBalance {
+                        account_id: account_id.to_string(),
+                        date: midnight(closing.date),
+                        amount,
+                    });
+                }
+            }
+
+            if let Some(check) = balance_check(statement, &currency) {
+                checks.push(check);
+            }
+        }
+
+        let mut preview = self.build_preview(rows, balances).await?;
+        preview.balance_checks = checks;
+
+        Ok(preview)
+    }
+}
+
+fn line_row(line: &Mt940Line, account_id: &str, currency: &str, default_category_id: &str) -> PreviewRow {
+    if let Some(error) = &line.error {
+        return PreviewRow::failed(line.line, error.clone());
+    }
+    let date = match line.entry_date.or(line.value_date) {
+        Some(date) => date,
+        None => return PreviewRow::failed(line.line, "Missing date"),
+    };
+    let amount = match Money::parse(&line.amount, currency) {
+        Ok(amount) if !amount.is_zero() => amount,
+        Ok(_) => return PreviewRow::failed(line.line, "Amount is zero"),
+        Err(e) => return PreviewRow::failed(line.line, e.to_string()),
+    };
+
+    PreviewRow::parsed(line.line, ImportTransaction {
+        account_id: account_id.to_string(),
+        category_id: default_category_id.to_string(),
+        amount,
+        description: line.details.clone(),
+        transaction_type: if line.credit { "income" } else { "expense" }.to_string(),
+        date: timestamp::format(&midnight(date)),
+        external_id: line.reference.clone(),
+        value_date: line.value_date.map(|date| timestamp::format(&midnight(date))),
+        counterparty_name: line.counterparty_name.clone(),
+        counterparty_iban: line.counterparty_iban.clone(),
+        cleared_status: None,
+        allow_duplicate: false,
+    })
+}
+
+fn balance_check(statement: &Mt940Statement, currency: &str) -> Option<BalanceCheck> {
+    let opening = Money::parse(&statement.opening.as_ref()?.amount, currency).ok()?;
+    let closing = Money::parse(&statement.closing.as_ref()?.amount, currency).ok()?;
+
+    // Unreadable lines are left out, so they surface as a mismatch
+    let mut computed = opening.clone();
+    for line in statement.lines.iter().filter(|line| line.error.is_none()) {
+        if let Ok(amount) = Money::parse(&line.amount, currency) {
+            let signed = if line.credit { amount } else { amount.negate() };
+            computed = computed.checked_add(&signed).ok()?;
+        }
+    }
+
+    let name = if statement.number.is_empty() { &statement.reference } else { &statement.number };
+    Some(BalanceCheck {
+        statement: name.clone(),
+        mismatch: computed != closing,
+        opening,
+        closing,
+        computed_closing: computed,
+    })
+}
+
+pub fn parse_mt940(bytes: &[u8]) -> Result<Vec<Mt940Statement>, AppError> {
+    // MT940 is ASCII in theory; Romanian banks send Windows-1250 in practice
+    let encoding = if std::str::from_utf8(bytes).is_ok() { "utf-8" } else { "windows-1250" };
+    let text = decode_text(bytes, encoding)?;
+
+    let mut statements: Vec<Mt940Statement> = Vec::new();
+    for (tag, value, line) in fields(&text) {
+        if tag == "20" {
+            statements.push(Mt940Statement { reference: value, ..Default::default() });
+            continue;
+        }
+        let statement = match statements.last_mut() {
+            Some(statement) => statement,
+            None => continue, // header fields before the first :20:
+        };
+
+        match tag.as_str() {
+            "25" => statement.account = value,
+            "28C" | "28" => statement.number = value,
+            "60F" | "60M" => statement.opening = parse_balance(&value),
+            "62F" | "62M" => statement.closing = parse_balance(&value),
+            "61" => statement.lines.push(parse_statement_line(&value, line)),
+            "86" => {
+                if let Some(last) = statement.lines.last_mut() {
+                    apply_details(last, &value);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    if statements.is_empty() {
+        return Err(AppError::validation("file", "no MT940 statement found"));
+    }
+
+    Ok(statements)
+}
+
+// (tag, value, line number) for every ":tag:value" field, continuation lines included;
+// SWIFT block wrappers ("{1:...}{4:" and "-}") are skipped
+fn fields(text: &str) -> Vec<(String, String, usize)> {
+    let mut fields: Vec<(String, String, usize)> = Vec::new();
+
+    for (index, raw) in text.lines().enumerate() {
+        let line = match raw.rfind("{4:") {
+            Some(start) => &raw[start + 3..],
+            None => raw,
+        };
+        let trimmed = line.trim_end();
+        if trimmed.is_empty() || trimmed == "-" || trimmed.starts_with("-}") || trimmed.starts_with('{') {
+            continue;
+        }
+
+        let tag = trimmed
+            .strip_prefix(':')
+            .and_then(|rest| rest.split_once(':'))
+            .filter(|(tag, _)| {
+                (2..=3).contains(&tag.len())
+                    && tag.chars().take(2).all(|c| c.is_ascii_digit())
+                    && tag.chars().skip(2).all(|c| c.is_ascii_uppercase())
+            });
+
+        match tag {
+            Some((tag, value)) => fields.push((tag.to_string(), value.to_string(), index + 1)),
+            None => {
+                if let Some((_, value, _)) = fields.last_mut() {
+                    value.push('\n');
+                    value.push_str(trimmed);
+                }
+            }
+        }
+    }
+
+    fields
+}
+
+// "C240229RON1234,56": debit/credit mark, date, currency, amount
+fn parse_balance(value: &str) -> Option<Mt940Balance> {
+    let value = value.trim();
+    let mark = value.get(..1)?;
+    let date = NaiveDate::parse_from_str(value.get(1..7)?, "%y%m%d").ok()?;
+    let currency = value.get(7..10)?.to_string();
+    let amount = value.get(10..)?.trim();
+
+    Some(Mt940Balance {
+        date,
+        currency,
+        amount: if mark == "D" { format!("-{}", amount) } else { amount.to_string() },
+    })
+}
+
+// "2402050205D45,90NTRFNONREF//B123\nsupplementary details"
+// value date, optional entry date (MMDD), mark (C, D, RC, RD), optional funds code,
+// amount, transaction type, customer reference and optional bank reference
+fn parse_statement_line(value: &str, line: usize) -> Mt940Line {
+    let mut parsed = Mt940Line {
+        line,
+        value_date: None,
+        entry_date: None,
+        credit: false,
+        amount: String::new(),
+        reference: None,
+        details: String::new(),
+        counterparty_name: None,
+        counterparty_iban: None,
+        error: None,
+    };
+
+    let first_line = value.lines().next().unwrap_or("").trim();
+    let value_date = match first_line.get(..6).and_then(|d| NaiveDate::parse_from_str(d, "%y%m%d").ok()) {
+        Some(date) => date,
+        None => {
+            parsed.error = Some(format!("Invalid :61: line: {}", first_line));
+            return parsed;
+        }
+    };
+    parsed.value_date = Some(value_date);
+
+    let mut rest = &first_line[6..];
+    if let Some(entry) = rest.get(..4).filter(|d| d.chars().all(|c| c.is_ascii_digit())) {
+        parsed.entry_date = entry_date(value_date, entry);
+        rest = &rest[4..];
+    }
+
+    let (credit, after_mark) = if let Some(after) = rest.strip_prefix("RC") {
+        (false, after) // reversal of a credit takes money out
+    } else if let Some(after) = rest.strip_prefix("RD") {
+        (true, after)
+    } else if let Some(after) = rest.strip_prefix('C') {
+        (true, after)
+    } else if let Some(after) = rest.strip_prefix('D') {
+        (false, after)
+    } else {
+        parsed.error = Some(format!("Missing debit/credit mark: {}", first_line));
+        return parsed;
+    };
+    parsed.credit = credit;
+
+    // Funds code is a single letter before the amount
+    let after_funds = after_mark.strip_prefix(|c: char| c.is_ascii_alphabetic()).unwrap_or(after_mark);
+    let amount_length = after_funds.find(|c: char| !c.is_ascii_digit() && c != ',').unwrap_or(after_funds.len());
+    parsed.amount = after_funds[..amount_length].to_string();
+
+    // Transaction type is 4 characters (e.g. NTRF), the customer reference follows
+    let references = after_funds[amount_length..].get(4..).unwrap_or("");
+    let (customer, bank) = match references.split_once("//") {
+        Some((customer, bank)) => (customer, Some(bank)),
+        None => (references, None),
+    };
+    parsed.reference = bank
+        .map(str::trim)
+        .filter(|r| !r.is_empty())
+        .or_else(|| Some(customer.trim()).filter(|r| !r.is_empty() && *r != "NONREF"))
+        .map(str::to_string);
+
+    parsed
+}
+
+// Entry date has no year: take the value date's, moving across New Year when needed
+fn entry_date(value_date: NaiveDate, mmdd: &str) -> Option<NaiveDate> {
+    let month: u32 = mmdd.get(..2)?.parse().ok()?;
+    let day: u32 = mmdd.get(2..4)?.parse().ok()?;
+
+    let year = match (value_date.month(), month) {
+        (12, 1) => value_date.year() + 1,
+        (1, 12) => value_date.year() - 1,
+        _ => value_date.year(),
+    };
+
+    NaiveDate::from_ymd_opt(year, month, day)
+}
+
+// :86: is free text, or structured "GVC?20...?21...?32..." subfields (German banks)
+fn apply_details(line: &mut Mt940Line, value: &str) {
+    let joined = value.lines().map(str::trim).collect::<Vec<_>>().join("");
+
+    let structured = joined.len() > 3 && joined.as_bytes()[3] == b'?' && joined[..3].chars().all(|c| c.is_ascii_digit());
+    if !structured {
+        line.details = value.lines().map(str::trim).filter(|l| !l.is_empty()).collect::<Vec<_>>().join(" ");
+        return;
+    }
+
+    let mut purpose = Vec::new();
+    let mut name = Vec::new();
+    for subfield in joined[4..].split('?') {
+        let (code, text) = match (subfield.get(..2), subfield.get(2..)) {
+            (Some(code), Some(text)) => (code, text),
+            _ => continue,
+        };
+        match code {
+            "20" | "21" | "22" | "23" | "24" | "25" | "26" | "27" | "28" | "29" => purpose.push(text),
+            "31" => line.counterparty_iban = Some(text.trim().to_string()).filter(|t| !t.is_empty()),
+            "32" | "33" => name.push(text),
+            _ => {}
+        }
+    }
+
+    let name = name.concat().trim().to_string();
+    line.counterparty_name = Some(name.clone()).filter(|n| !n.is_empty());
+    line.details = [name, purpose.concat().trim().to_string()]
+        .into_iter()
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // windows-1250: 0xE3 is "ă"
+    const MT940: &[u8] = b"{1:F01BTRLRO22AXXX0000000000}{2:I940BTRLRO22XXXXN}{4:\r\n\
+:20:STMT1\r\n\
+:25:RO49AAAA1B31007593840000\r\n\
+:28C:00012/001\r\n\
+:60F:C231231RON1000,00\r\n\
+:61:2312310102D45,90NTRFNONREF//B123\r\n\
+Card 1234\r\n\
+:86:Plat\xe3 Kaufland\r\n\
+Cluj\r\n\
+:61:240102C100,NMSCREF2\r\n\
+:86:166?00GUTSCHRIFT?20Invoice ?2142?32ACME?33 GmbH?31DE89370400440532013000\r\n\
+:62F:C240102RON1054,10\r\n\
+-}\r\n\
+{1:F01BTRLRO22AXXX0000000000}{2:I940BTRLRO22XXXXN}{4:\r\n\
+:20:STMT2\r\n\
+:25:RO49AAAA1B31007593840000\r\n\
+:60F:C240102RON1054,10\r\n\
+:61:240103RC4,10NCHGNONREF\r\n\
+:86:Reversal of\r\n\
+ fee\r\n\
+:62F:C240103RON1050,00\r\n\
+-}\r\n";
+
+    #[test]
+    fn statements_with_continuation_lines() {
+        let statements = parse_mt940(MT940).unwrap();
+        assert_eq!(statements.len(), 2);
+        let first = &statements[0];
+        assert_eq!((first.reference.as_str(), first.number.as_str()), ("STMT1", "00012/001"));
+        assert_eq!(first.lines.len(), 2);
+
+        // the :61: continuation does not leak into the :86: details
+        let card = line_row(&first.lines[0], "acc", "RON", "cat");
+        assert_eq!(card.line, 6);
+        let card = card.transaction.unwrap();
+        assert_eq!((card.description.as_str(), card.amount.minor_units, card.transaction_type.as_str()), ("Plată Kaufland Cluj", 4590, "expense"));
+        assert_eq!((card.date.as_str(), card.value_date.as_deref()), ("2024-01-02T00:00:00.000Z", Some("2023-12-31T00:00:00.000Z")));
+        assert_eq!(card.external_id.as_deref(), Some("B123"));
+
+        let invoice = line_row(&first.lines[1], "acc", "RON", "cat").transaction.unwrap();
+        assert_eq!((invoice.description.as_str(), invoice.amount.minor_units, invoice.transaction_type.as_str()), ("ACME GmbH Invoice 42", 10000, "income"));
+        assert_eq!((invoice.counterparty_name.as_deref(), invoice.counterparty_iban.as_deref()), (Some("ACME GmbH"), Some("DE89370400440532013000")));
+        assert_eq!(invoice.external_id.as_deref(), Some("REF2"));
+
+        let check = balance_check(first, "RON").unwrap();
+        assert!(!check.mismatch);
+        assert_eq!(check.closing.minor_units, 105410);
+
+        let reversal = line_row(&statements[1].lines[0], "acc", "RON", "cat").transaction.unwrap();
+        assert_eq!((reversal.description.as_str(), reversal.transaction_type.as_str(), reversal.external_id), ("Reversal of fee", "expense", None));
+        assert!(!balance_check(&statements[1], "RON").unwrap().mismatch);
+    }
+
+    #[test]
+    fn unreadable_lines_fail_and_show_as_mismatch() {
+        let text = ":20:X\n:60F:C240102RON10,00\n:61:2401X2C5,00NTRF\n:61:240102X5,00NTRF\n:62F:C240102RON15,00\n";
+        let statements = parse_mt940(text.as_bytes()).unwrap();
+        let lines = &statements[0].lines;
+        assert_eq!(line_row(&lines[0], "acc", "RON", "cat").error.as_deref(), Some("Invalid :61: line: 2401X2C5,00NTRF"));
+        assert_eq!(line_row(&lines[1], "acc", "RON", "cat").line, 4);
+        assert!(lines[1].error.as_deref().unwrap().starts_with("Missing debit/credit mark"));
+        assert!(balance_check(&statements[0], "RON").unwrap().mismatch);
+    }
+
+    #[test]
+    fn rejects_files_without_statements() {
+        assert!(parse_mt940(b"Date;Amount\n2024-01-02;5\n").is_err());
+    }
+}
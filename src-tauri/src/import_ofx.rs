@@ -0,0 +1,417 @@
+// import_ofx.rs
+// OFX/QFX statement parsing. Handles both OFX 1.x (SGML, leaf elements without closing
+// tags) and OFX 2.x (XML) with one tolerant tag reader.
+
+use chrono::NaiveDate;
+use std::collections::HashMap;
+use std::path::Path;
+use crate::database::Database;
+use crate::error::AppError;
+use crate::import::{decode_text, midnight, ImportPreview, ImportTransaction, PreviewRow, StatementBalance};
+use crate::money::Money;
+use crate::timestamp;
+
+// Element of the OFX document; leaf elements carry a value, aggregates carry children
+#[derive(Debug, Clone, Default)]
+pub struct OfxElement {
+    pub name: String,
+    pub value: Option<String>,
+    pub children: Vec<OfxElement>,
+}
+
+impl OfxElement {
+    fn child(&self, name: &str) -> Option<&OfxElement> {
+        self.children.iter().find(|child| child.name == name)
+    }
+
+    fn value_of(&self, name: &str) -> Option<&str> {
+        self.child(name).and_then(|child| child.value.as_deref()).filter(|v| !v.is_empty())
+    }
+
+    // Every element with this name, depth first
+    fn descendants<'a>(&'a self, name: &str, found: &mut Vec<&'a OfxElement>) {
+        for child in &self.children {
+            if child.name == name {
+                found.push(child);
+            } else {
+                child.descendants(name, found);
+            }
+        }
+    }
+}
+
+// One STMTRS (bank) or CCSTMTRS (credit card) block
+#[derive(Debug, Clone)]
+pub struct OfxStatement {
+    pub bank_account_id: String,
+    pub currency: String,
+    pub transactions: Vec<OfxTransaction>,
+    pub ledger_balance: Option<(NaiveDate, String)>, // raw BALAMT, parsed once the currency is checked
+}
+
+#[derive(Debug, Clone)]
+pub struct OfxTransaction {
+    pub fitid: Option<String>,
+    pub posted: Option<NaiveDate>,
+    pub amount: String,
+    pub name: String,
+    pub memo: String,
+}
+
+enum Token {
+    Open(String),
+    Close(String),
+    Text(String),
+}
+
+impl Database {
+    // `bank_account` picks the statement by its ACCTID when the file holds several accounts
+    pub async fn preview_ofx_import(
+        &self,
+        file_path: &str,
+        account_id: &str,
+        default_category_id: &str,
+        bank_account: Option<String>,
+    ) -> Result<ImportPreview, AppError> {
+        let bytes = std::fs::read(Path::new(file_path))?;
+        let currency = self.account_currency(account_id).await?;
+        let statements = parse_ofx(&bytes)?;
+
+        let statement = match bank_account {
+            Some(bank_account) => statements
+                .into_iter()
+                .find(|statement| statement.bank_account_id == bank_account)
+                .ok_or_else(|| AppError::validation("bank_account", format!("no statement for account {}", bank_account)))?,
+            None if statements.len() > 1 => {
+                let accounts: Vec<_> = statements.iter().map(|s| s.bank_account_id.as_str()).collect();
+                return Err(AppError::validation(
+                    "bank_account",
+                    format!("file contains statements for {}; choose one", accounts.join(", ")),
+                ));
+            }
+            None => statements
+                .into_iter()
+                .next()
+                .ok_or_else(|| AppError::validation("file", "no bank or credit card statement found"))?,
+        };
+
+        if statement.currency != currency {
+            return Err(AppError::validation(
+                "currency",
+                format!("statement is in {}, account is in {}", statement.currency, currency),
+            ));
+        }
+
+        let (rows, balances) = statement_rows(&statement, account_id, default_category_id);
+        self.build_preview(rows, balances).await
+    }
+}
+
+fn statement_rows(statement: &OfxStatement, account_id: &str, default_category_id: &str) -> (Vec<PreviewRow>, Vec<StatementBalance>) {
+    let rows = statement
+        .transactions
+        .iter()
+        .enumerate()
+        .map(|(index, tx)| {
+            let line = index + 1;
+            let date = match tx.posted {
+                Some(date) => date,
+                None => return PreviewRow::failed(line, "Missing DTPOSTED"),
+            };
+            let amount = match Money::parse(&tx.amount, statement.currency.clone()) {
+                Ok(amount) if !amount.is_zero() => amount,
+                Ok(_) => return PreviewRow::failed(line, "Amount is zero"),
+                Err(e) => return PreviewRow::failed(line, e.to_string()),
+            };
+
+            let description = if tx.memo.is_empty() || tx.memo == tx.name {
+                tx.name.clone()
+            } else if tx.name.is_empty() {
+                tx.memo.clone()
+            } else {
+                format!("{} {}", tx.name, tx.memo)
+            };
+
+            PreviewRow::parsed(line, ImportTransaction {
+                account_id: account_id.to_string(),
+                category_id: default_category_id.to_string(),
+                transaction_type: if amount.is_negative() { "expense" } else { "income" }.to_string(),
+                amount: amount.abs(),
+                description,
+                date: timestamp::format(&midnight(date)),
+                external_id: tx.fitid.clone(),
+                value_date: None,
+                counterparty_name: None,
+                counterparty_iban: None,
+                cleared_status: None,
+                allow_duplicate: false,
+            })
+        })
+        .collect();
+
+    let balances = statement
+        .ledger_balance
+        .as_ref()
+        .and_then(|(date, amount)| {
+            Money::parse(amount, statement.currency.clone()).ok().map(|amount| StatementBalance {
+                account_id: account_id.to_string(),
+                date: midnight(*date),
+                amount,
+            })
+        })
+        .into_iter()
+        .collect();
+
+    (rows, balances)
+}
+
+pub fn parse_ofx(bytes: &[u8]) -> Result<Vec<OfxStatement>, AppError> {
+    let text = decode_text(bytes, header_encoding(bytes))?;
+    let start = text
+        .find("<OFX>")
+        .or_else(|| text.find("<ofx>"))
+        .ok_or_else(|| AppError::validation("file", "not an OFX file"))?;
+    let root = build_tree(tokenize(&text[start..]));
+
+    let mut blocks = Vec::new();
+    root.descendants("STMTRS", &mut blocks);
+    root.descendants("CCSTMTRS", &mut blocks);
+
+    let statements = blocks
+        .into_iter()
+        .map(|block| {
+            let bank_account_id = block
+                .child("BANKACCTFROM")
+                .or_else(|| block.child("CCACCTFROM"))
+                .and_then(|account| account.value_of("ACCTID"))
+                .unwrap_or_default()
+                .to_string();
+
+            let mut entries = Vec::new();
+            if let Some(list) = block.child("BANKTRANLIST") {
+                list.descendants("STMTTRN", &mut entries);
+            }
+
+            let transactions = entries
+                .into_iter()
+                .map(|entry| OfxTransaction {
+                    fitid: entry.value_of("FITID").map(str::to_string),
+                    posted: entry.value_of("DTPOSTED").and_then(parse_ofx_date),
+                    amount: entry.value_of("TRNAMT").unwrap_or_default().to_string(),
+                    name: entry.value_of("NAME").or_else(|| entry.value_of("PAYEE")).unwrap_or_default().to_string(),
+                    memo: entry.value_of("MEMO").unwrap_or_default().to_string(),
+                })
+                .collect();
+
+            let ledger_balance = block.child("LEDGERBAL").and_then(|balance| {
+                let date = balance.value_of("DTASOF").and_then(parse_ofx_date)?;
+                let amount = balance.value_of("BALAMT")?;
+                Some((date, amount.to_string()))
+            });
+
+            OfxStatement {
+                bank_account_id,
+                currency: block.value_of("CURDEF").unwrap_or_default().to_uppercase(),
+                transactions,
+                ledger_balance,
+            }
+        })
+        .collect();
+
+    Ok(statements)
+}
+
+// OFX 1.x declares the charset in its plain-text header, 2.x in the XML declaration
+fn header_encoding(bytes: &[u8]) -> &'static str {
+    let head = String::from_utf8_lossy(&bytes[..bytes.len().min(1024)]).to_uppercase();
+    let headers: HashMap<&str, &str> = head
+        .lines()
+        .filter_map(|line| line.split_once(':'))
+        .map(|(key, value)| (key.trim(), value.trim()))
+        .collect();
+
+    if head.contains("ENCODING=\"UTF-8\"") || headers.get("ENCODING") == Some(&"UTF-8") {
+        return "utf-8";
+    }
+    match headers.get("CHARSET").copied() {
+        Some("1250") => "windows-1250",
+        Some("1252") => "windows-1252",
+        Some("ISO-8859-1") | Some("8859-1") => "iso-8859-1",
+        Some("ISO-8859-2") | Some("8859-2") => "iso-8859-2",
+        _ if std::str::from_utf8(bytes).is_ok() => "utf-8",
+        _ => "windows-1252",
+    }
+}
+
+fn tokenize(text: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut rest = text;
+
+    while let Some(open) = rest.find('<') {
+        let before = rest[..open].trim();
+        if !before.is_empty() {
+            tokens.push(Token::Text(unescape(before)));
+        }
+
+        let after = &rest[open + 1..];
+        let close = match after.find('>') {
+            Some(close) => close,
+            None => break,
+        };
+        let tag = after[..close].trim();
+        rest = &after[close + 1..];
+
+        if tag.starts_with('?') || tag.starts_with('!') {
+            continue;
+        }
+        match tag.strip_prefix('/') {
+            Some(name) => tokens.push(Token::Close(name.trim().to_uppercase())),
+            None => {
+                // Attributes never matter in OFX; self-closing tags are empty leaves
+                let name = tag.split_whitespace().next().unwrap_or_default().trim_end_matches('/');
+                tokens.push(Token::Open(name.to_uppercase()));
+                if tag.ends_with('/') {
+                    tokens.push(Token::Close(name.to_uppercase()));
+                }
+            }
+        }
+    }
+
+    tokens
+}
+
+// An element followed directly by text is a leaf whose closing tag is optional (SGML);
+// anything else is an aggregate that stays open until its closing tag
+fn build_tree(tokens: Vec<Token>) -> OfxElement {
+    let mut stack = vec![OfxElement::default()];
+    let mut tokens = tokens.into_iter().peekable();
+
+    while let Some(token) = tokens.next() {
+        match token {
+            Token::Open(name) => {
+                if let Some(Token::Text(_)) = tokens.peek() {
+                    let value = match tokens.next() {
+                        Some(Token::Text(value)) => value,
+                        _ => String::new(),
+                    };
+                    if matches!(tokens.peek(), Some(Token::Close(close)) if *close == name) {
+                        tokens.next();
+                    }
+                    if let Some(parent) = stack.last_mut() {
+                        parent.children.push(OfxElement { name, value: Some(value), children: Vec::new() });
+                    }
+                } else {
+                    stack.push(OfxElement { name, value: None, children: Vec::new() });
+                }
+            }
+            Token::Close(name) => {
+                if !stack.iter().skip(1).any(|element| element.name == name) {
+                    continue;
+                }
+                while stack.len() > 1 {
+                    let element = stack.pop().unwrap_or_default();
+                    let done = element.name == name;
+                    if let Some(parent) = stack.last_mut() {
+                        parent.children.push(element);
+                    }
+                    if done {
+                        break;
+                    }
+                }
+            }
+            Token::Text(_) => {} // stray text between aggregates
+        }
+    }
+
+    while stack.len() > 1 {
+        let element = stack.pop().unwrap_or_default();
+        if let Some(parent) = stack.last_mut() {
+            parent.children.push(element);
+        }
+    }
+    stack.pop().unwrap_or_default()
+}
+
+fn unescape(value: &str) -> String {
+    value
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&nbsp;", " ")
+        .replace("&amp;", "&")
+}
+
+// "YYYYMMDD[HHMMSS[.XXX]][[offset:TZ]]"; only the calendar day is kept
+fn parse_ofx_date(value: &str) -> Option<NaiveDate> {
+    let digits = value.get(..8)?;
+    NaiveDate::parse_from_str(digits, "%Y%m%d").ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // OFX 1.x as exported by most banks: plain-text header, SGML body, no closing tags on leaves
+    const SGML: &[u8] = b"OFXHEADER:100\r\nDATA:OFXSGML\r\nVERSION:102\r\nENCODING:USASCII\r\nCHARSET:1252\r\n\r\n\
+<OFX><SIGNONMSGSRSV1><SONRS><STATUS><CODE>0<SEVERITY>INFO</STATUS><DTSERVER>20240305120000</SONRS></SIGNONMSGSRSV1>\r\n\
+<BANKMSGSRSV1><STMTTRNRS><TRNUID>1<STMTRS><CURDEF>eur\r\n\
+<BANKACCTFROM><BANKID>12345<ACCTID>DE001<ACCTTYPE>CHECKING</BANKACCTFROM>\r\n\
+<BANKTRANLIST><DTSTART>20240301<DTEND>20240305\r\n\
+<STMTTRN><TRNTYPE>DEBIT<DTPOSTED>20240302120000.000[+1:CET]<TRNAMT>-12.50<FITID>A1<NAME>Caf\xe9 &amp; Bar<MEMO>Card 1234</STMTTRN>\r\n\
+<STMTTRN><TRNTYPE>CREDIT<DTPOSTED>20240303<TRNAMT>1500.00<FITID>A2<NAME>Salary<MEMO>Salary</STMTTRN>\r\n\
+<STMTTRN><TRNTYPE>DEBIT<TRNAMT>-1.00<FITID>A3<NAME>No date</STMTTRN>\r\n\
+</BANKTRANLIST><LEDGERBAL><BALAMT>2487.50<DTASOF>20240305</LEDGERBAL></STMTRS></STMTTRNRS></BANKMSGSRSV1></OFX>\r\n";
+
+    const XML: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<?OFX OFXHEADER="200" VERSION="220"?>
+<OFX>
+  <CREDITCARDMSGSRSV1><CCSTMTTRNRS><CCSTMTRS>
+    <CURDEF>RON</CURDEF>
+    <CCACCTFROM><ACCTID>4111</ACCTID></CCACCTFROM>
+    <BANKTRANLIST>
+      <STMTTRN><DTPOSTED>20240410</DTPOSTED><TRNAMT>-99,90</TRNAMT><FITID>C1</FITID><PAYEE>eMAG</PAYEE><MEMO/></STMTTRN>
+    </BANKTRANLIST>
+  </CCSTMTRS></CCSTMTTRNRS></CREDITCARDMSGSRSV1>
+</OFX>"#;
+
+    #[test]
+    fn sgml_statement_with_header_charset() {
+        let statements = parse_ofx(SGML).unwrap();
+        assert_eq!(statements.len(), 1);
+        let statement = &statements[0];
+        assert_eq!((statement.bank_account_id.as_str(), statement.currency.as_str()), ("DE001", "EUR"));
+        assert_eq!(statement.transactions.len(), 3);
+        assert_eq!(statement.transactions[0].name, "Café & Bar");
+        assert_eq!(statement.transactions[0].posted, NaiveDate::from_ymd_opt(2024, 3, 2));
+
+        let (rows, balances) = statement_rows(statement, "acc", "cat");
+        let cafe = rows[0].transaction.as_ref().unwrap();
+        assert_eq!((cafe.description.as_str(), cafe.amount.minor_units, cafe.transaction_type.as_str()), ("Café & Bar Card 1234", 1250, "expense"));
+        assert_eq!((cafe.external_id.as_deref(), cafe.date.as_str()), (Some("A1"), "2024-03-02T00:00:00.000Z"));
+        let salary = rows[1].transaction.as_ref().unwrap();
+        assert_eq!((salary.description.as_str(), salary.transaction_type.as_str()), ("Salary", "income"));
+        assert_eq!(rows[2].error.as_deref(), Some("Missing DTPOSTED"));
+
+        assert_eq!(balances.len(), 1);
+        assert_eq!((balances[0].amount.minor_units, balances[0].date), (248750, midnight(NaiveDate::from_ymd_opt(2024, 3, 5).unwrap())));
+    }
+
+    #[test]
+    fn xml_credit_card_statement() {
+        let statements = parse_ofx(XML.as_bytes()).unwrap();
+        assert_eq!(statements.len(), 1);
+        let statement = &statements[0];
+        assert_eq!((statement.bank_account_id.as_str(), statement.currency.as_str()), ("4111", "RON"));
+        assert!(statement.ledger_balance.is_none());
+
+        let (rows, _) = statement_rows(statement, "acc", "cat");
+        let emag = rows[0].transaction.as_ref().unwrap();
+        assert_eq!((emag.description.as_str(), emag.amount.minor_units), ("eMAG", 9990));
+    }
+
+    #[test]
+    fn rejects_files_without_ofx_root() {
+        assert!(parse_ofx(b"Date,Amount\n2024-03-01,1\n").is_err());
+    }
+}
@@ -0,0 +1,464 @@
+// import_presets.rs
+// Built-in parsers for the Revolut and Wise CSV exports. Both are multi-currency, so each
+// currency is routed to its own account; fees become separate expenses and currency
+// exchanges become transfers between those accounts.
+
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+use csv::StringRecord;
+use std::collections::HashMap;
+use std::path::Path;
+use crate::database::{Database, NewTransfer};
+use crate::error::AppError;
+use crate::import::{decode_text, ImportPreview, ImportTransaction, ImportTransfer, PreviewRow};
+use crate::import_csv::line_at;
+use crate::money::{Money, MoneyError};
+use crate::timestamp;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Provider {
+    // Account statement: Type,Product,Started Date,Completed Date,Description,Amount,Fee,Currency,State,Balance
+    Revolut,
+    // Transaction history: ID,Status,Direction,Created on,Finished on,Source fee amount,...
+    Wise,
+}
+
+// Where parsed rows go
+pub struct PresetTargets<'a> {
+    pub accounts: &'a HashMap<String, String>, // currency -> account id
+    pub default_category_id: &'a str,
+    pub fee_category_id: &'a str,
+}
+
+// Column name -> position
+type Header = HashMap<String, usize>;
+
+// CSV row with columns looked up by header name
+struct Row<'a> {
+    header: &'a Header,
+    record: StringRecord,
+}
+
+impl Row<'_> {
+    fn get(&self, column: &str) -> &str {
+        self.header
+            .get(column)
+            .and_then(|&index| self.record.get(index))
+            .map(str::trim)
+            .unwrap_or("")
+    }
+}
+
+// One side of a Revolut exchange, waiting for its counterpart
+struct ExchangeLeg {
+    line: usize,
+    started: String,
+    date: DateTime<Utc>,
+    amount: Money, // signed
+    description: String,
+}
+
+impl Database {
+    pub async fn preview_provider_import(
+        &self,
+        file_path: &str,
+        provider: Provider,
+        accounts: HashMap<String, String>,
+        default_category_id: &str,
+        fee_category_id: Option<String>,
+    ) -> Result<ImportPreview, AppError> {
+        let bytes = std::fs::read(Path::new(file_path))?;
+
+        // Every mapped account must really hold the currency it is mapped to
+        for (currency, account_id) in &accounts {
+            let account_currency = self.account_currency(account_id).await?;
+            if &account_currency != currency {
+                return Err(AppError::validation(
+                    "accounts",
+                    format!("account {} is in {}, not {}", account_id, account_currency, currency),
+                ));
+            }
+        }
+
+        let targets = PresetTargets {
+            accounts: &accounts,
+            default_category_id,
+            fee_category_id: fee_category_id.as_deref().unwrap_or(default_category_id),
+        };
+        let rows = match provider {
+            Provider::Revolut => parse_revolut(&bytes, &targets)?,
+            Provider::Wise => parse_wise(&bytes, &targets)?,
+        };
+
+        self.build_preview(rows, Vec::new()).await
+    }
+}
+
+pub fn parse_revolut(bytes: &[u8], targets: &PresetTargets) -> Result<Vec<PreviewRow>, AppError> {
+    let (header, records) = read_csv(bytes, &["Type", "Started Date", "Description", "Amount", "Fee", "Currency", "State"])?;
+
+    let mut rows = Vec::new();
+    let mut exchanges: Vec<ExchangeLeg> = Vec::new();
+
+    for (line, record) in records {
+        let row = Row { header: &header, record };
+
+        let state = row.get("State");
+        if state != "COMPLETED" {
+            rows.push(PreviewRow::skipped(line, format!("State is {}", state)));
+            continue;
+        }
+
+        let currency = row.get("Currency");
+        let date_text = Some(row.get("Completed Date")).filter(|d| !d.is_empty()).unwrap_or(row.get("Started Date"));
+        let date = match timestamp::parse(date_text) {
+            Some(date) => date,
+            None => {
+                rows.push(PreviewRow::failed(line, format!("Invalid date: {}", date_text)));
+                continue;
+            }
+        };
+        let (amount, fee) = match (Money::parse(row.get("Amount"), currency), parse_fee(row.get("Fee"), currency)) {
+            (Ok(amount), Ok(fee)) => (amount, fee),
+            (Err(e), _) | (_, Err(e)) => {
+                rows.push(PreviewRow::failed(line, e.to_string()));
+                continue;
+            }
+        };
+        let description = row.get("Description").to_string();
+
+        if let Some(fee) = fee {
+            rows.push(fee_row(line, targets, date, fee, &description, None));
+        }
+
+        if row.get("Type") == "EXCHANGE" {
+            exchanges.push(ExchangeLeg {
+                line,
+                started: row.get("Started Date").to_string(),
+                date,
+                amount,
+                description,
+            });
+            continue;
+        }
+        if amount.is_zero() {
+            continue;
+        }
+
+        let category_id = if row.get("Type") == "FEE" { targets.fee_category_id } else { targets.default_category_id };
+        rows.push(transaction_row(line, targets, date, amount, description, category_id, None));
+    }
+
+    rows.extend(pair_exchanges(exchanges, targets));
+    rows.sort_by_key(|row| row.line);
+
+    Ok(rows)
+}
+
+// Revolut writes an exchange as two rows, one per currency pocket, started at the same moment
+fn pair_exchanges(mut legs: Vec<ExchangeLeg>, targets: &PresetTargets) -> Vec<PreviewRow> {
+    let mut rows = Vec::new();
+
+    while let Some(leg) = legs.pop() {
+        let partner = legs.iter().position(|other| {
+            other.started == leg.started
+                && other.amount.currency != leg.amount.currency
+                && other.amount.is_negative() != leg.amount.is_negative()
+        });
+        let other = match partner {
+            Some(index) => legs.remove(index),
+            None => {
+                rows.push(PreviewRow::failed(leg.line, "Exchange without its other currency leg"));
+                continue;
+            }
+        };
+
+        let (from, to) = if leg.amount.is_negative() { (leg, other) } else { (other, leg) };
+        rows.push(transfer_row(from.line, targets, from.date, from.amount.abs(), to.amount.abs(), from.description, None));
+    }
+
+    rows
+}
+
+pub fn parse_wise(bytes: &[u8], targets: &PresetTargets) -> Result<Vec<PreviewRow>, AppError> {
+    let (header, records) = read_csv(bytes, &[
+        "ID", "Status", "Direction", "Created on", "Source amount (after fees)", "Source currency",
+        "Target amount (after fees)", "Target currency",
+    ])?;
+
+    let mut rows = Vec::new();
+    for (line, record) in records {
+        let row = Row { header: &header, record };
+
+        let status = row.get("Status");
+        if status != "COMPLETED" {
+            rows.push(PreviewRow::skipped(line, format!("Status is {}", status)));
+            continue;
+        }
+
+        let date_text = Some(row.get("Finished on")).filter(|d| !d.is_empty()).unwrap_or(row.get("Created on"));
+        let date = match timestamp::parse(date_text) {
+            Some(date) => date,
+            None => {
+                rows.push(PreviewRow::failed(line, format!("Invalid date: {}", date_text)));
+                continue;
+            }
+        };
+        let id = Some(row.get("ID").to_string()).filter(|id| !id.is_empty());
+        let source = Money::parse(row.get("Source amount (after fees)"), row.get("Source currency"));
+        let target = Money::parse(row.get("Target amount (after fees)"), row.get("Target currency"));
+        let (source, target) = match (source, target) {
+            (Ok(source), Ok(target)) => (source, target),
+            (Err(e), _) | (_, Err(e)) => {
+                rows.push(PreviewRow::failed(line, e.to_string()));
+                continue;
+            }
+        };
+        let reference = row.get("Reference");
+        let direction = row.get("Direction");
+        let description = match direction {
+            "OUT" => describe(row.get("Target name"), reference),
+            "IN" => describe(row.get("Source name"), reference),
+            _ => format!("Exchange {} → {}", source.currency, target.currency),
+        };
+
+        let fee_columns = [
+            ("Source fee amount", "Source fee currency", "fee"),
+            ("Target fee amount", "Target fee currency", "target-fee"),
+        ];
+        for (amount_column, currency_column, suffix) in fee_columns {
+            match parse_fee(row.get(amount_column), row.get(currency_column)) {
+                Ok(Some(fee)) => {
+                    let fee_id = id.as_ref().map(|id| format!("{}:{}", id, suffix));
+                    rows.push(fee_row(line, targets, date, fee, &description, fee_id));
+                }
+                Ok(None) => {}
+                Err(e) => rows.push(PreviewRow::failed(line, e.to_string())),
+            }
+        }
+
+        let imported = match direction {
+            "OUT" => transaction_row(line, targets, date, source.negate(), description, targets.default_category_id, id),
+            "IN" => transaction_row(line, targets, date, target, description, targets.default_category_id, id),
+            // Conversion between two of our own balances
+            "NEUTRAL" => transfer_row(line, targets, date, source, target, description, id),
+            other => PreviewRow::failed(line, format!("Unknown direction {}", other)),
+        };
+        rows.push(imported);
+    }
+
+    Ok(rows)
+}
+
+fn read_csv(bytes: &[u8], required: &[&str]) -> Result<(Header, Vec<(usize, StringRecord)>), AppError> {
+    let text = decode_text(bytes, "utf-8")?;
+    let mut reader = csv::ReaderBuilder::new().flexible(true).from_reader(text.as_bytes());
+
+    let header: Header = reader
+        .headers()
+        .map_err(|e| AppError::validation("file", e.to_string()))?
+        .iter()
+        .enumerate()
+        .map(|(index, name)| (name.trim().to_string(), index))
+        .collect();
+
+    if let Some(missing) = required.iter().find(|column| !header.contains_key(**column)) {
+        return Err(AppError::validation("file", format!("missing column {}; is this the right export?", missing)));
+    }
+
+    let mut records = Vec::new();
+    for record in reader.records() {
+        let record = record.map_err(|e| AppError::validation("file", e.to_string()))?;
+        let line = record.position().map(|p| line_at(text.as_bytes(), p)).unwrap_or(0);
+        if record.iter().any(|field| !field.trim().is_empty()) {
+            records.push((line, record));
+        }
+    }
+
+    Ok((header, records))
+}
+
+fn parse_fee(value: &str, currency: &str) -> Result<Option<Money>, MoneyError> {
+    if value.is_empty() || currency.is_empty() {
+        return Ok(None);
+    }
+
+    let fee = Money::parse(value, currency)?.abs();
+    Ok(if fee.is_zero() { None } else { Some(fee) })
+}
+
+fn describe(name: &str, reference: &str) -> String {
+    [name, reference]
+        .iter()
+        .filter(|part| !part.is_empty())
+        .copied()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn account_for<'a>(targets: &'a PresetTargets, currency: &str) -> Result<&'a String, String> {
+    targets
+        .accounts
+        .get(currency)
+        .ok_or_else(|| format!("No account selected for {}", currency))
+}
+
+// `amount` is signed: negative for money going out
+fn transaction_row(
+    line: usize,
+    targets: &PresetTargets,
+    date: DateTime<Utc>,
+    amount: Money,
+    description: String,
+    category_id: &str,
+    external_id: Option<String>,
+) -> PreviewRow {
+    let account_id = match account_for(targets, &amount.currency) {
+        Ok(account_id) => account_id,
+        Err(message) => return PreviewRow::failed(line, message),
+    };
+
+    PreviewRow::parsed(line, ImportTransaction {
+        account_id: account_id.clone(),
+        category_id: category_id.to_string(),
+        transaction_type: if amount.is_negative() { "expense" } else { "income" }.to_string(),
+        amount: amount.abs(),
+        description,
+        date: timestamp::format(&date),
+        external_id,
+        value_date: None,
+        counterparty_name: None,
+        counterparty_iban: None,
+        cleared_status: None,
+        allow_duplicate: false,
+    })
+}
+
+fn fee_row(
+    line: usize,
+    targets: &PresetTargets,
+    date: DateTime<Utc>,
+    fee: Money,
+    description: &str,
+    external_id: Option<String>,
+) -> PreviewRow {
+    let description = if description.is_empty() { "Fee".to_string() } else { format!("Fee: {}", description) };
+    transaction_row(line, targets, date, fee.negate(), description, targets.fee_category_id, external_id)
+}
+
+fn transfer_row(
+    line: usize,
+    targets: &PresetTargets,
+    date: DateTime<Utc>,
+    from_amount: Money,
+    to_amount: Money,
+    description: String,
+    external_id: Option<String>,
+) -> PreviewRow {
+    let (from_account_id, to_account_id) = match (account_for(targets, &from_amount.currency), account_for(targets, &to_amount.currency)) {
+        (Ok(from), Ok(to)) => (from.clone(), to.clone()),
+        (Err(message), _) | (_, Err(message)) => return PreviewRow::failed(line, message),
+    };
+    if from_account_id == to_account_id {
+        return PreviewRow::skipped(line, "Move within the same account");
+    }
+
+    PreviewRow::transfer(line, ImportTransfer {
+        transfer: NewTransfer {
+            from_account_id,
+            to_account_id,
+            from_amount,
+            to_amount: Some(to_amount),
+            exchange_rate: None,
+            category_id: None,
+            description,
+            date,
+        },
+        external_id,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn accounts() -> HashMap<String, String> {
+        HashMap::from([("RON".to_string(), "acc-ron".to_string()), ("EUR".to_string(), "acc-eur".to_string())])
+    }
+
+    fn targets(accounts: &HashMap<String, String>) -> PresetTargets<'_> {
+        PresetTargets { accounts, default_category_id: "cat", fee_category_id: "cat-fee" }
+    }
+
+    #[test]
+    fn revolut_statement_with_fees_and_exchanges() {
+        let csv = "Type,Product,Started Date,Completed Date,Description,Amount,Fee,Currency,State,Balance\r\n\
+                   CARD_PAYMENT,Current,2024-03-01 10:15:30,2024-03-02 08:00:00,Lidl,-45.90,0.00,RON,COMPLETED,954.10\r\n\
+                   EXCHANGE,Current,2024-03-03 12:00:00,2024-03-03 12:00:00,Exchanged to EUR,-500.00,2.50,RON,COMPLETED,451.60\r\n\
+                   EXCHANGE,Current,2024-03-03 12:00:00,2024-03-03 12:00:00,Exchanged to EUR,100.00,0.00,EUR,COMPLETED,100.00\r\n\
+                   TOPUP,Current,2024-03-04 09:00:00,,Top-up,200.00,0.00,RON,REVERTED,451.60\r\n\
+                   CARD_PAYMENT,Current,2024-03-05 09:00:00,2024-03-05 09:00:00,Starbucks,-4.00,0.00,USD,COMPLETED,0.00\r\n";
+        let accounts = accounts();
+        let rows = parse_revolut(csv.as_bytes(), &targets(&accounts)).unwrap();
+        assert_eq!(rows.iter().map(|row| row.line).collect::<Vec<_>>(), [2, 3, 3, 5, 6]);
+
+        let lidl = rows[0].transaction.as_ref().unwrap();
+        assert_eq!((lidl.account_id.as_str(), lidl.amount.minor_units, lidl.transaction_type.as_str()), ("acc-ron", 4590, "expense"));
+        assert_eq!(lidl.date, "2024-03-02T08:00:00.000Z");
+
+        let (fee, exchange) = if rows[1].transaction.is_some() { (&rows[1], &rows[2]) } else { (&rows[2], &rows[1]) };
+        let fee = fee.transaction.as_ref().unwrap();
+        assert_eq!((fee.description.as_str(), fee.category_id.as_str(), fee.amount.minor_units), ("Fee: Exchanged to EUR", "cat-fee", 250));
+        let exchange = &exchange.transfer.as_ref().unwrap().transfer;
+        assert_eq!((exchange.from_account_id.as_str(), exchange.to_account_id.as_str()), ("acc-ron", "acc-eur"));
+        assert_eq!((exchange.from_amount.minor_units, exchange.to_amount.as_ref().unwrap().minor_units), (50000, 10000));
+
+        assert_eq!((rows[3].error.as_deref(), rows[3].skipped.as_deref()), (None, Some("State is REVERTED")));
+        assert_eq!(rows[4].error.as_deref(), Some("No account selected for USD"));
+    }
+
+    #[test]
+    fn revolut_exchange_without_partner_fails() {
+        let csv = "Type,Started Date,Description,Amount,Fee,Currency,State\n\
+                   EXCHANGE,2024-03-03 12:00:00,Exchanged to EUR,-500.00,0,RON,COMPLETED\n";
+        let accounts = accounts();
+        let rows = parse_revolut(csv.as_bytes(), &targets(&accounts)).unwrap();
+        assert_eq!(rows[0].error.as_deref(), Some("Exchange without its other currency leg"));
+    }
+
+    #[test]
+    fn wise_history_with_directions_and_fees() {
+        let csv = "ID,Status,Direction,Created on,Finished on,Source fee amount,Source fee currency,Target fee amount,Target fee currency,\
+Source name,Source amount (after fees),Source currency,Target name,Target amount (after fees),Target currency,Exchange rate,Reference\n\
+TRANSFER-1,COMPLETED,OUT,2024-04-01 09:00:00,2024-04-01 09:05:00,1.20,EUR,,,Ioan,98.80,EUR,Landlord,98.80,EUR,1,Rent April\n\
+TRANSFER-2,COMPLETED,IN,2024-04-02 09:00:00,2024-04-02 09:00:00,,,,,Client SRL,500.00,RON,Ioan,500.00,RON,1,Invoice 7\n\
+BALANCE-3,COMPLETED,NEUTRAL,2024-04-03 09:00:00,2024-04-03 09:00:00,0.50,EUR,,,Ioan,20.00,EUR,Ioan,99.50,RON,4.975,\n\
+TRANSFER-4,CANCELLED,OUT,2024-04-04 09:00:00,,,,,,Ioan,5.00,EUR,Shop,5.00,EUR,1,\n";
+        let accounts = accounts();
+        let rows = parse_wise(csv.as_bytes(), &targets(&accounts)).unwrap();
+        assert_eq!(rows.len(), 6);
+
+        let fee = rows[0].transaction.as_ref().unwrap();
+        assert_eq!((fee.amount.minor_units, fee.external_id.as_deref()), (120, Some("TRANSFER-1:fee")));
+        let rent = rows[1].transaction.as_ref().unwrap();
+        assert_eq!((rent.description.as_str(), rent.account_id.as_str(), rent.transaction_type.as_str()), ("Landlord Rent April", "acc-eur", "expense"));
+        assert_eq!(rent.date, "2024-04-01T09:05:00.000Z");
+        let invoice = rows[2].transaction.as_ref().unwrap();
+        assert_eq!((invoice.description.as_str(), invoice.account_id.as_str(), invoice.amount.minor_units), ("Client SRL Invoice 7", "acc-ron", 50000));
+
+        assert_eq!(rows[3].transaction.as_ref().unwrap().external_id.as_deref(), Some("BALANCE-3:fee"));
+        let conversion = rows[4].transfer.as_ref().unwrap();
+        assert_eq!(conversion.transfer.description, "Exchange EUR → RON");
+        assert_eq!((conversion.transfer.from_amount.minor_units, conversion.external_id.as_deref()), (2000, Some("BALANCE-3")));
+        assert_eq!((rows[5].error.as_deref(), rows[5].skipped.as_deref()), (None, Some("Status is CANCELLED")));
+    }
+
+    #[test]
+    fn rejects_exports_from_another_provider() {
+        let accounts = accounts();
+        let revolut = "Type,Product,Started Date,Completed Date,Description,Amount,Fee,Currency,State,Balance\n";
+        assert!(parse_wise(revolut.as_bytes(), &targets(&accounts)).is_err());
+        assert!(parse_revolut(b"Date,Amount\n2024-01-01,1\n", &targets(&accounts)).is_err());
+    }
+}
@@ -0,0 +1,474 @@
+// import_qif.rs
+// Quicken Interchange Format for bank and cash accounts: import with category mapping and
+// splits, and export of one account's history
+
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::path::Path;
+use crate::database::{Database, NewTransfer};
+use crate::error::AppError;
+use crate::import::{decode_text, ImportResult, ImportTransaction, ImportTransfer};
+use crate::import_csv::parse_date;
+use crate::money::{minor_unit_exponent, Money};
+use crate::timestamp;
+
+// Two-digit years are tried first: %Y would read "24" as the year 24
+const DEFAULT_DATE_FORMATS: [&str; 5] = ["%m/%d/%y", "%m/%d/%Y", "%d.%m.%y", "%d.%m.%Y", "%Y-%m-%d"];
+const EXPORT_DATE_FORMAT: &str = "%m/%d/%Y";
+
+// One record between `^` lines of a !Type:Bank, !Type:Cash or !Type:CCard section
+#[derive(Debug, Clone, Default)]
+pub struct QifRecord {
+    pub line: usize, // line of the record's first field
+    pub date: String,
+    pub amount: String,
+    pub payee: String,
+    pub memo: String,
+    pub category: String, // "Food:Groceries", or "[Account]" for a transfer
+    pub cleared: String,  // "", "*", "c", "X" or "R"
+    pub splits: Vec<QifSplit>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct QifSplit {
+    pub category: String,
+    pub memo: String,
+    pub amount: String,
+}
+
+impl Database {
+    // Unknown category names are created when `create_categories` is set and fall back to
+    // the default category otherwise
+    pub async fn import_qif(
+        &self,
+        file_path: &str,
+        account_id: &str,
+        default_category_id: &str,
+        create_categories: bool,
+        date_format: Option<String>,
+    ) -> Result<ImportResult, AppError> {
+        let bytes = std::fs::read(Path::new(file_path))?;
+        let encoding = if std::str::from_utf8(&bytes).is_ok() { "utf-8" } else { "windows-1252" };
+        let records = parse_qif(&decode_text(&bytes, encoding)?);
+        let currency = self.account_currency(account_id).await?;
+
+        let formats: Vec<String> = match date_format {
+            Some(format) => vec![format],
+            None => DEFAULT_DATE_FORMATS.iter().map(|f| f.to_string()).collect(),
+        };
+
+        let mut categories: HashMap<String, String> = self
+            .get_categories()
+            .await?
+            .into_iter()
+            .map(|category| (category.name.to_lowercase(), category.id))
+            .collect();
+        let accounts: HashMap<String, String> = self
+            .get_accounts()
+            .await?
+            .into_iter()
+            .map(|account| (account.name.to_lowercase(), account.id))
+            .collect();
+
+        let mut transactions = Vec::new();
+        let mut transfers = Vec::new();
+        let mut errors = Vec::new();
+
+        for record in records {
+            let line = record.line;
+            let date = match parse_date(&record.date.replace('\'', "/").replace(' ', ""), &formats) {
+                Some(date) => date,
+                None => {
+                    errors.push(format!("Line {}: invalid date {:?}", line, record.date));
+                    continue;
+                }
+            };
+            let total = match parse_qif_amount(&record.amount, &currency) {
+                Ok(total) => total,
+                Err(e) => {
+                    errors.push(format!("Line {}: {}", line, e));
+                    continue;
+                }
+            };
+
+            // A split record becomes one transaction per split line
+            let items = if record.splits.is_empty() {
+                vec![(record.category.clone(), record.memo.clone(), total)]
+            } else {
+                let items = match split_items(&record, &currency) {
+                    Ok(items) => items,
+                    Err(e) => {
+                        errors.push(format!("Line {}: {}", line, e));
+                        continue;
+                    }
+                };
+                let sum = items.iter().map(|(_, _, amount)| amount.minor_units).sum::<i64>();
+                if sum != total.minor_units {
+                    let sum = Money::new(sum, currency.clone());
+                    errors.push(format!("Line {}: splits add up to {}, record total is {}", line, sum, total));
+                    continue;
+                }
+                items
+            };
+
+            let cleared_status = match record.cleared.as_str() {
+                "*" | "c" | "C" => Some("cleared".to_string()),
+                "X" | "x" | "R" => Some("reconciled".to_string()),
+                _ => None,
+            };
+
+            for (category, memo, amount) in items {
+                if amount.is_zero() {
+                    continue;
+                }
+                let description = [record.payee.as_str(), memo.as_str()]
+                    .iter()
+                    .filter(|part| !part.is_empty())
+                    .copied()
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                // "Category/Class": classes have no equivalent here
+                let category = category.split('/').next().unwrap_or_default().trim().to_string();
+
+                if let Some(other) = category.strip_prefix('[').and_then(|c| c.strip_suffix(']')) {
+                    let other_id = match accounts.get(&other.to_lowercase()) {
+                        Some(id) if id != account_id => id.clone(),
+                        Some(_) => {
+                            errors.push(format!("Line {}: transfer to the account being imported", line));
+                            continue;
+                        }
+                        None => {
+                            errors.push(format!("Line {}: unknown account {:?}", line, other));
+                            continue;
+                        }
+                    };
+                    let (from_account_id, to_account_id) = if amount.is_negative() {
+                        (account_id.to_string(), other_id)
+                    } else {
+                        (other_id, account_id.to_string())
+                    };
+                    transfers.push(ImportTransfer {
+                        transfer: NewTransfer {
+                            from_account_id,
+                            to_account_id,
+                            from_amount: amount.abs(),
+                            to_amount: None,
+                            exchange_rate: None,
+                            category_id: None,
+                            description,
+                            date,
+                        },
+                        external_id: None,
+                    });
+                    continue;
+                }
+
+                let transaction_type = if amount.is_negative() { "expense" } else { "income" };
+                let category_id = match self
+                    .qif_category(&mut categories, &category, transaction_type, create_categories)
+                    .await
+                {
+                    Ok(Some(id)) => id,
+                    Ok(None) => default_category_id.to_string(),
+                    Err(e) => {
+                        errors.push(format!("Line {}: {}", line, e));
+                        continue;
+                    }
+                };
+
+                transactions.push(ImportTransaction {
+                    account_id: account_id.to_string(),
+                    category_id,
+                    amount: amount.abs(),
+                    description,
+                    transaction_type: transaction_type.to_string(),
+                    date: timestamp::format(&date),
+                    external_id: None,
+                    value_date: None,
+                    counterparty_name: None,
+                    counterparty_iban: None,
+                    cleared_status: cleared_status.clone(),
+                    allow_duplicate: false,
+                });
+            }
+        }
+
+        let mut result = self.import_batch(Some(file_path), transactions, &[], transfers).await?;
+        result.errors.extend(errors);
+        Ok(result)
+    }
+
+    // Matches the full name first, then the last segment of "Parent:Child"
+    async fn qif_category(
+        &self,
+        categories: &mut HashMap<String, String>,
+        name: &str,
+        category_type: &str,
+        create: bool,
+    ) -> Result<Option<String>, AppError> {
+        if name.is_empty() {
+            return Ok(None);
+        }
+        let key = name.to_lowercase();
+        let leaf = key.rsplit(':').next().unwrap_or_default().trim();
+        if let Some(id) = categories.get(&key).or_else(|| categories.get(leaf)) {
+            return Ok(Some(id.clone()));
+        }
+        if !create {
+            return Ok(None);
+        }
+
+        let category = self
+            .create_category(name.to_string(), "📁".to_string(), category_type.to_string(), "#94a3b8".to_string())
+            .await?;
+        categories.insert(key, category.id.clone());
+        Ok(Some(category.id))
+    }
+
+    // Writes the account's transactions oldest first and returns how many were written
+    pub async fn export_qif(&self, account_id: &str, file_path: &str, date_format: Option<String>) -> Result<usize, AppError> {
+        let account = self
+            .get_accounts()
+            .await?
+            .into_iter()
+            .find(|account| account.id == account_id)
+            .ok_or_else(|| AppError::not_found("account"))?;
+        let categories: HashMap<String, String> = self
+            .get_categories()
+            .await?
+            .into_iter()
+            .map(|category| (category.id, category.name))
+            .collect();
+        let transactions = sqlx::query_as::<_, crate::database::Transaction>(
+            "SELECT * FROM transactions WHERE account_id = ? ORDER BY date, created_at",
+        )
+        .bind(account_id)
+        .fetch_all(self.get_pool())
+        .await?;
+
+        let date_format = date_format.unwrap_or_else(|| EXPORT_DATE_FORMAT.to_string());
+        let mut out = String::new();
+        let section = if account.account_type == "cash" { "Cash" } else { "Bank" };
+        let _ = writeln!(out, "!Type:{}", section);
+
+        for tx in &transactions {
+            let amount = if tx.transaction_type == "expense" { tx.amount.negate() } else { tx.amount.clone() };
+            let category = match &tx.transfer_id {
+                Some(transfer_id) => {
+                    let other: Option<String> = sqlx::query_scalar(
+                        "SELECT a.name FROM transactions t JOIN accounts a ON a.id = t.account_id
+                         WHERE t.transfer_id = ? AND t.id != ?",
+                    )
+                    .bind(transfer_id)
+                    .bind(&tx.id)
+                    .fetch_optional(self.get_pool())
+                    .await?;
+                    other.map(|name| format!("[{}]", name)).unwrap_or_default()
+                }
+                None => categories.get(&tx.category_id).cloned().unwrap_or_default(),
+            };
+
+            let _ = writeln!(out, "D{}", format_date(&tx.date, &date_format));
+            let _ = writeln!(out, "T{}", amount.to_decimal_string());
+            if !tx.description.is_empty() {
+                let _ = writeln!(out, "P{}", single_line(&tx.description));
+            }
+            if !category.is_empty() {
+                let _ = writeln!(out, "L{}", single_line(&category));
+            }
+            match tx.cleared_status.as_str() {
+                "cleared" => { let _ = writeln!(out, "C*"); }
+                "reconciled" => { let _ = writeln!(out, "CX"); }
+                _ => {}
+            }
+            let _ = writeln!(out, "^");
+        }
+
+        std::fs::write(Path::new(file_path), out)?;
+        Ok(transactions.len())
+    }
+}
+
+pub fn parse_qif(text: &str) -> Vec<QifRecord> {
+    let mut records = Vec::new();
+    let mut in_transactions = false;
+    let mut record = QifRecord::default();
+
+    for (index, raw) in text.lines().enumerate() {
+        let line = raw.trim_end();
+        if line.is_empty() {
+            continue;
+        }
+
+        if let Some(header) = line.strip_prefix('!') {
+            // !Option and !Clear toggle Quicken settings and do not start a section
+            if header.starts_with("Option") || header.starts_with("Clear") {
+                continue;
+            }
+            let kind = header.split_once(':').map(|(_, kind)| kind.trim().to_lowercase()).unwrap_or_default();
+            in_transactions = header.starts_with("Type") && matches!(kind.as_str(), "bank" | "cash" | "ccard");
+            record = QifRecord::default();
+            continue;
+        }
+        if !in_transactions {
+            continue;
+        }
+
+        let (code, value) = line.split_at(line.chars().next().map(char::len_utf8).unwrap_or(0));
+        let value = value.trim().to_string();
+        if record.line == 0 && code != "^" {
+            record.line = index + 1;
+        }
+
+        match code {
+            "D" => record.date = value,
+            "T" | "U" => record.amount = value,
+            "P" => record.payee = value,
+            "M" => record.memo = value,
+            "L" => record.category = value,
+            "C" => record.cleared = value,
+            "S" => record.splits.push(QifSplit { category: value, ..QifSplit::default() }),
+            "E" => {
+                if let Some(split) = record.splits.last_mut() {
+                    split.memo = value;
+                }
+            }
+            "$" => {
+                if let Some(split) = record.splits.last_mut() {
+                    split.amount = value;
+                }
+            }
+            "^" if record.line != 0 => records.push(std::mem::take(&mut record)),
+            _ => {} // N (check number), A (address), % (split percentage), ...
+        }
+    }
+
+    records
+}
+
+fn split_items(record: &QifRecord, currency: &str) -> Result<Vec<(String, String, Money)>, AppError> {
+    record
+        .splits
+        .iter()
+        .map(|split| {
+            let amount = parse_qif_amount(&split.amount, currency)?;
+            let memo = if split.memo.is_empty() { record.memo.clone() } else { split.memo.clone() };
+            Ok((split.category.clone(), memo, amount))
+        })
+        .collect()
+}
+
+// The last '.' or ',' is the decimal separator, so both "1,234.56" and "1.234,56" parse.
+// Followed by exactly three digits it groups thousands instead ("1,500"), unless the currency
+// itself has three decimals.
+fn parse_qif_amount(value: &str, currency: &str) -> Result<Money, AppError> {
+    let decimal = value.rfind(['.', ',']).filter(|&i| {
+        let fraction = value[i + 1..].trim();
+        fraction.len() != 3 || !fraction.chars().all(|c| c.is_ascii_digit()) || minor_unit_exponent(currency) == 3
+    });
+    let normalized: String = value
+        .char_indices()
+        .filter_map(|(i, c)| match c {
+            '.' | ',' if Some(i) == decimal => Some('.'),
+            '.' | ',' => None,
+            c if c.is_whitespace() => None,
+            c => Some(c),
+        })
+        .collect();
+
+    Ok(Money::parse(&normalized, currency)?)
+}
+
+fn format_date(date: &DateTime<Utc>, format: &str) -> String {
+    date.format(format).to_string()
+}
+
+fn single_line(value: &str) -> String {
+    value.replace(['\r', '\n'], " ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const QIF: &str = "!Option:AutoSwitch\r\n\
+!Account\r\n\
+NChecking\r\n\
+TBank\r\n\
+^\r\n\
+!Clear:AutoSwitch\r\n\
+!Type:Bank\r\n\
+D12/31'23\r\n\
+T-1,234.56\r\n\
+N1001\r\n\
+PLandlord\r\n\
+MRent\r\n\
+LHousing:Rent\r\n\
+CX\r\n\
+^\r\n\
+D01/02'24\r\n\
+U-100.00\r\n\
+T-100.00\r\n\
+PMega Image\r\n\
+MWeekly shop\r\n\
+SFood:Groceries\r\n\
+$-80.00\r\n\
+SHousehold\r\n\
+EDetergent\r\n\
+$-20.00\r\n\
+^\r\n\
+D01/03/2024\r\n\
+T500.00\r\n\
+L[Savings]\r\n\
+^\r\n\
+!Type:Memorized\r\n\
+PNetflix\r\n\
+T-9.99\r\n\
+^\r\n";
+
+    #[test]
+    fn bank_section_with_splits_and_transfers() {
+        let records = parse_qif(QIF);
+        assert_eq!(records.len(), 3);
+
+        let rent = &records[0];
+        assert_eq!(rent.line, 8);
+        assert_eq!((rent.payee.as_str(), rent.memo.as_str(), rent.category.as_str(), rent.cleared.as_str()), ("Landlord", "Rent", "Housing:Rent", "X"));
+        assert_eq!(parse_qif_amount(&rent.amount, "RON").unwrap().minor_units, -123456);
+
+        let shop = &records[1];
+        let items = split_items(shop, "RON").unwrap();
+        assert_eq!(items.len(), 2);
+        assert_eq!((items[0].0.as_str(), items[0].1.as_str(), items[0].2.minor_units), ("Food:Groceries", "Weekly shop", -8000));
+        assert_eq!((items[1].0.as_str(), items[1].1.as_str(), items[1].2.minor_units), ("Household", "Detergent", -2000));
+
+        assert_eq!((records[2].category.as_str(), records[2].amount.as_str()), ("[Savings]", "500.00"));
+    }
+
+    #[test]
+    fn quicken_dates_and_amounts() {
+        let formats: Vec<String> = DEFAULT_DATE_FORMATS.iter().map(|f| f.to_string()).collect();
+        let parse = |value: &str| parse_date(&value.replace('\'', "/").replace(' ', ""), &formats).map(|d| d.date_naive().to_string());
+        assert_eq!(parse("12/31'23").as_deref(), Some("2023-12-31"));
+        assert_eq!(parse(" 1/ 2'24").as_deref(), Some("2024-01-02"));
+        assert_eq!(parse("01/03/2024").as_deref(), Some("2024-01-03"));
+        assert_eq!(parse("03.01.2024").as_deref(), Some("2024-01-03"));
+
+        assert_eq!(parse_qif_amount("1.234,56", "EUR").unwrap().minor_units, 123456);
+        assert_eq!(parse_qif_amount("-1,234.56", "EUR").unwrap().minor_units, -123456);
+        assert_eq!(parse_qif_amount("1 500", "JPY").unwrap().minor_units, 1500);
+        assert_eq!(parse_qif_amount("1,500", "EUR").unwrap().minor_units, 150000);
+        assert_eq!(parse_qif_amount("-1.500", "JPY").unwrap().minor_units, -1500);
+        assert_eq!(parse_qif_amount("1,500", "KWD").unwrap().minor_units, 1500);
+        assert_eq!(parse_qif_amount("1,234,567.5", "EUR").unwrap().minor_units, 123456750);
+        assert!(parse_qif_amount("abc", "EUR").is_err());
+    }
+
+    #[test]
+    fn export_text_stays_on_one_line() {
+        assert_eq!(single_line("Rent\r\nApril"), "Rent  April");
+        let date = timestamp::parse("2024-01-02").unwrap();
+        assert_eq!(format_date(&date, EXPORT_DATE_FORMAT), "01/02/2024");
+    }
+}
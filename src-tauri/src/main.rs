@@ -1,44 +1,61 @@
 // Prevents additional console window on Windows in release, DO NOT REMOVE!!
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
+mod backup;
+mod budgets;
 mod database;
+mod error;
+mod import;
+mod import_batches;
+mod import_camt;
+mod import_csv;
+mod import_dedup;
+mod import_mt940;
+mod import_ofx;
+mod import_presets;
+mod import_qif;
 mod migrate_categories;
-
+mod migrations;
+mod money;
+mod recurring;
+mod rules;
+mod search;
+mod suggestions;
+mod timestamp;
+mod transaction_query;
+
+use std::collections::HashMap;
 use std::sync::Arc;
 use tokio::sync::Mutex;
 use tauri::{State, Manager};
-use database::{Database, Account, Transaction, Category, Tag};
-use chrono::{DateTime, Utc};
-use serde::{Deserialize, Serialize};
+use error::AppError;
+use backup::{BackupInfo, BackupSettings, RestoreReport};
+use database::{Database, Account, Transaction, Category, Tag, BalanceDiscrepancy, Transfer, NewTransfer};
+use budgets::{Budget, BudgetStatus, NewBudget};
+use import::{BalanceReconciliation, ImportPreview, ImportResult, ImportTransaction, ImportTransfer, StatementBalance};
+use import_batches::ImportBatch;
+use import_presets::Provider;
+use import_csv::{CsvMapping, CsvProfile};
+use import_dedup::DuplicateSettings;
+use money::Money;
+use recurring::{GeneratedTransaction, NewRecurringRule, Occurrence, OccurrenceChanges, RecurringRule};
+use rules::{NewRule, Rule, RuleChange};
+use search::SearchHit;
+use suggestions::Suggestions;
+use transaction_query::{TransactionFilter, TransactionPage};
+use chrono::{Local, NaiveDate};
 
 type DatabaseState = Arc<Mutex<Database>>;
 
-#[derive(Debug, Deserialize, Serialize)]
-struct ImportTransaction {
-    account_id: String,
-    category_id: String,
-    amount: f64,
-    description: String,
-    transaction_type: String,
-    date: String, // ISO format from frontend
-}
-
-#[derive(Debug, Serialize)]
-struct ImportResult {
-    inserted: usize,
-    skipped: usize,
-    errors: Vec<String>,
-}
-
 #[tauri::command]
 fn greet(name: &str) -> String {
     format!("Hello, {}! You've been greeted from Rust!", name)
 }
 
 #[tauri::command]
-async fn init_database(db: State<'_, DatabaseState>) -> Result<(), String> {
+async fn init_database(db: State<'_, DatabaseState>) -> Result<(), AppError> {
     let db = db.lock().await;
-    db.init_schema().await.map_err(|e| e.to_string())?;
+    db.init_schema().await?;
     Ok(())
 }
 
@@ -48,17 +65,16 @@ async fn create_account(
     name: String,
     account_type: String,
     currency: String,
-) -> Result<Account, String> {
+) -> Result<Account, AppError> {
     let db = db.lock().await;
     db.create_account(name, account_type, currency)
         .await
-        .map_err(|e| e.to_string())
 }
 
 #[tauri::command]
-async fn get_accounts(db: State<'_, DatabaseState>) -> Result<Vec<Account>, String> {
+async fn get_accounts(db: State<'_, DatabaseState>) -> Result<Vec<Account>, AppError> {
     let db = db.lock().await;
-    db.get_accounts().await.map_err(|e| e.to_string())
+    db.get_accounts().await
 }
 
 #[tauri::command]
@@ -68,153 +84,218 @@ async fn update_account(
     name: String,
     account_type: String,
     currency: String,
-) -> Result<Account, String> {
+) -> Result<Account, AppError> {
     let db = db.lock().await;
     db.update_account(id, name, account_type, currency)
         .await
-        .map_err(|e| e.to_string())
 }
 
 #[tauri::command]
-async fn delete_account(db: State<'_, DatabaseState>, id: String) -> Result<(), String> {
+async fn delete_account(db: State<'_, DatabaseState>, id: String) -> Result<(), AppError> {
     let db = db.lock().await;
-    db.delete_account(id).await.map_err(|e| e.to_string())
+    db.delete_account(id).await
 }
 
 #[tauri::command]
-async fn backup_database(app: tauri::AppHandle) -> Result<String, String> {
-    use std::fs;
-    use chrono::Local;
+async fn recalculate_balances(db: State<'_, DatabaseState>) -> Result<Vec<BalanceDiscrepancy>, AppError> {
+    let db = db.lock().await;
+    db.recalculate_balances().await
+}
 
-    // Get paths using Tauri API
-    let app_data_dir = app.path().app_local_data_dir()
-        .map_err(|e| format!("Failed to get app data directory: {}", e))?;
-    let db_path = app_data_dir.join("money-zen.db");
+#[tauri::command]
+async fn backup_database(app: tauri::AppHandle, db: State<'_, DatabaseState>) -> Result<BackupInfo, AppError> {
+    let backup_dir = backup_dir(&app)?;
+    let db = db.lock().await;
+    db.create_backup(&backup_dir, &app.package_info().version.to_string()).await
+}
 
-    // Create backup directory in Documents
-    let documents_dir = app.path().document_dir()
-        .map_err(|e| format!("Failed to get documents directory: {}", e))?;
-    let backup_dir = documents_dir.join("MoneyZen Backups");
-    fs::create_dir_all(&backup_dir)
-        .map_err(|e| format!("Failed to create backup directory: {}", e))?;
+#[tauri::command]
+async fn list_backups(app: tauri::AppHandle) -> Result<Vec<BackupInfo>, AppError> {
+    backup::list_backups(&backup_dir(&app)?)
+}
 
-    // Generate timestamped filename
-    let timestamp = Local::now().format("%Y-%m-%d_%H-%M-%S");
-    let backup_filename = format!("backup-{}.db", timestamp);
-    let backup_path = backup_dir.join(&backup_filename);
+#[tauri::command]
+async fn get_backup_settings(db: State<'_, DatabaseState>) -> Result<BackupSettings, AppError> {
+    let db = db.lock().await;
+    db.get_backup_settings().await
+}
 
-    // Copy database file
-    fs::copy(&db_path, &backup_path)
-        .map_err(|e| format!("Failed to copy database: {}", e))?;
+#[tauri::command]
+async fn save_backup_settings(
+    db: State<'_, DatabaseState>,
+    settings: BackupSettings,
+) -> Result<BackupSettings, AppError> {
+    let db = db.lock().await;
+    db.save_backup_settings(settings).await
+}
 
-    // Return success with backup path
-    Ok(format!("Backup created: {}", backup_path.display()))
+#[tauri::command]
+async fn restore_database(app: tauri::AppHandle, db: State<'_, DatabaseState>, backup_path: String) -> Result<RestoreReport, AppError> {
+    // The current database is backed up next to the user's other backups before the swap
+    let backup_dir = backup_dir(&app)?;
+    let mut db = db.lock().await;
+    db.restore_backup(std::path::Path::new(&backup_path), &backup_dir, &app.package_info().version.to_string()).await
 }
 
 #[tauri::command]
-async fn restore_database(app: tauri::AppHandle, backup_path: String) -> Result<String, String> {
-    use std::fs;
+async fn batch_insert_transactions(
+    db: State<'_, DatabaseState>,
+    transactions: Vec<ImportTransaction>,
+    statement_balances: Option<Vec<StatementBalance>>,
+    transfers: Option<Vec<ImportTransfer>>,
+    file_path: Option<String>,
+) -> Result<ImportResult, AppError> {
+    let db = db.lock().await;
+    db.import_batch(
+        file_path.as_deref(),
+        transactions,
+        &statement_balances.unwrap_or_default(),
+        transfers.unwrap_or_default(),
+    )
+    .await
+}
 
-    // Get active database path using Tauri API
-    let app_data_dir = app.path().app_local_data_dir()
-        .map_err(|e| format!("Failed to get app data directory: {}", e))?;
-    let db_path = app_data_dir.join("money-zen.db");
+#[tauri::command]
+async fn get_import_batches(db: State<'_, DatabaseState>) -> Result<Vec<ImportBatch>, AppError> {
+    let db = db.lock().await;
+    db.get_import_batches().await
+}
 
-    // Verify backup file exists
-    let backup = std::path::Path::new(&backup_path);
-    if !backup.exists() {
-        return Err("Backup file not found".to_string());
-    }
+#[tauri::command]
+async fn revert_import_batch(db: State<'_, DatabaseState>, id: String) -> Result<ImportBatch, AppError> {
+    let db = db.lock().await;
+    db.revert_import_batch(id).await
+}
 
-    // Copy backup to active database location (overwrite)
-    fs::copy(&backup, &db_path)
-        .map_err(|e| format!("Failed to restore database: {}", e))?;
+#[tauri::command]
+async fn preview_csv_import(
+    db: State<'_, DatabaseState>,
+    file_path: String,
+    account_id: String,
+    default_category_id: String,
+    mapping: CsvMapping,
+) -> Result<ImportPreview, AppError> {
+    let db = db.lock().await;
+    db.preview_csv_import(&file_path, &account_id, &default_category_id, &mapping).await
+}
 
-    Ok(format!("Database restored from: {}", backup_path))
+#[tauri::command]
+async fn preview_ofx_import(
+    db: State<'_, DatabaseState>,
+    file_path: String,
+    account_id: String,
+    default_category_id: String,
+    bank_account: Option<String>,
+) -> Result<ImportPreview, AppError> {
+    let db = db.lock().await;
+    db.preview_ofx_import(&file_path, &account_id, &default_category_id, bank_account).await
 }
 
 #[tauri::command]
-async fn batch_insert_transactions(
+async fn preview_camt_import(
     db: State<'_, DatabaseState>,
-    transactions: Vec<ImportTransaction>,
-) -> Result<ImportResult, String> {
-    println!("🔍 batch_insert_transactions called with {} transactions", transactions.len());
-
-    let db = db.lock().await;
-    let mut inserted = 0;
-    let mut skipped = 0;
-    let mut errors = Vec::new();
-
-    for tx in transactions {
-        println!("Processing transaction: date={}, amount={}, desc={}",
-                 tx.date, tx.amount, tx.description);
-
-        // Parse date - handle YYYY-MM-DD format
-        let date = if tx.date.contains('T') {
-            // RFC3339 format (with time)
-            match DateTime::parse_from_rfc3339(&tx.date) {
-                Ok(d) => d.with_timezone(&Utc),
-                Err(_) => {
-                    println!("❌ Date parsing failed for: {}", tx.date);
-                    errors.push(format!("Invalid date: {}", tx.date));
-                    continue;
-                }
-            }
-        } else {
-            // Simple YYYY-MM-DD format
-            match chrono::NaiveDate::parse_from_str(&tx.date, "%Y-%m-%d") {
-                Ok(naive_date) => {
-                    let datetime = naive_date.and_hms_opt(0, 0, 0).unwrap();
-                    DateTime::<Utc>::from_naive_utc_and_offset(datetime, Utc)
-                },
-                Err(_) => {
-                    println!("❌ Date parsing failed for: {}", tx.date);
-                    errors.push(format!("Invalid date: {}", tx.date));
-                    continue;
-                }
-            }
-        };
-
-        // Check duplicate (same date, amount, description)
-        match db.transaction_exists(&date.to_rfc3339(), tx.amount, &tx.description).await {
-            Ok(true) => {
-                println!("⏭️ Duplicate found, skipping transaction");
-                skipped += 1;
-                continue;
-            }
-            Ok(false) => {
-                // Insert new transaction
-                match db.create_transaction(
-                    tx.account_id,
-                    tx.category_id,
-                    tx.amount,
-                    tx.description,
-                    tx.transaction_type,
-                    date,
-                    None
-                ).await {
-                    Ok(_) => {
-                        inserted += 1;
-                        println!("✅ Transaction inserted successfully");
-                    },
-                    Err(e) => {
-                        println!("❌ Failed to insert transaction: {:?}", e);
-                        errors.push(format!("Failed to insert: {}", e));
-                    },
-                }
-            }
-            Err(e) => {
-                errors.push(format!("Database error checking duplicate: {}", e));
-                continue;
-            }
-        }
-    }
+    file_path: String,
+    account_id: String,
+    default_category_id: String,
+    bank_account: Option<String>,
+) -> Result<ImportPreview, AppError> {
+    let db = db.lock().await;
+    db.preview_camt_import(&file_path, &account_id, &default_category_id, bank_account).await
+}
 
-    Ok(ImportResult {
-        inserted,
-        skipped,
-        errors,
-    })
+#[tauri::command]
+async fn preview_mt940_import(
+    db: State<'_, DatabaseState>,
+    file_path: String,
+    account_id: String,
+    default_category_id: String,
+) -> Result<ImportPreview, AppError> {
+    let db = db.lock().await;
+    db.preview_mt940_import(&file_path, &account_id, &default_category_id).await
+}
+
+#[tauri::command]
+async fn preview_provider_import(
+    db: State<'_, DatabaseState>,
+    file_path: String,
+    provider: Provider,
+    accounts: HashMap<String, String>,
+    default_category_id: String,
+    fee_category_id: Option<String>,
+) -> Result<ImportPreview, AppError> {
+    let db = db.lock().await;
+    db.preview_provider_import(&file_path, provider, accounts, &default_category_id, fee_category_id).await
+}
+
+#[tauri::command]
+async fn import_qif(
+    db: State<'_, DatabaseState>,
+    file_path: String,
+    account_id: String,
+    default_category_id: String,
+    create_categories: bool,
+    date_format: Option<String>,
+) -> Result<ImportResult, AppError> {
+    let db = db.lock().await;
+    db.import_qif(&file_path, &account_id, &default_category_id, create_categories, date_format).await
+}
+
+#[tauri::command]
+async fn export_qif(
+    db: State<'_, DatabaseState>,
+    account_id: String,
+    file_path: String,
+    date_format: Option<String>,
+) -> Result<usize, AppError> {
+    let db = db.lock().await;
+    db.export_qif(&account_id, &file_path, date_format).await
+}
+
+#[tauri::command]
+async fn get_balance_reconciliation(
+    db: State<'_, DatabaseState>,
+    account_id: String,
+) -> Result<Vec<BalanceReconciliation>, AppError> {
+    let db = db.lock().await;
+    db.get_balance_reconciliation(account_id).await
+}
+
+#[tauri::command]
+async fn get_duplicate_settings(db: State<'_, DatabaseState>) -> Result<DuplicateSettings, AppError> {
+    let db = db.lock().await;
+    db.get_duplicate_settings().await
+}
+
+#[tauri::command]
+async fn save_duplicate_settings(
+    db: State<'_, DatabaseState>,
+    settings: DuplicateSettings,
+) -> Result<DuplicateSettings, AppError> {
+    let db = db.lock().await;
+    db.save_duplicate_settings(settings).await
+}
+
+#[tauri::command]
+async fn save_csv_profile(
+    db: State<'_, DatabaseState>,
+    id: Option<String>,
+    name: String,
+    mapping: CsvMapping,
+) -> Result<CsvProfile, AppError> {
+    let db = db.lock().await;
+    db.save_csv_profile(id, name, mapping).await
+}
+
+#[tauri::command]
+async fn get_csv_profiles(db: State<'_, DatabaseState>) -> Result<Vec<CsvProfile>, AppError> {
+    let db = db.lock().await;
+    db.get_csv_profiles().await
+}
+
+#[tauri::command]
+async fn delete_csv_profile(db: State<'_, DatabaseState>, id: String) -> Result<(), AppError> {
+    let db = db.lock().await;
+    db.delete_csv_profile(id).await
 }
 
 #[tauri::command]
@@ -222,20 +303,18 @@ async fn create_transaction(
     db: State<'_, DatabaseState>,
     account_id: String,
     category_id: String,
-    amount: f64,
+    amount: Money,
     description: String,
     transaction_type: String,
     date: String,
     tag_ids: Option<Vec<String>>,
-) -> Result<Transaction, String> {
+) -> Result<Transaction, AppError> {
     let db = db.lock().await;
-    let parsed_date = DateTime::parse_from_rfc3339(&date)
-        .map_err(|e| format!("Invalid date format: {}", e))?
-        .with_timezone(&Utc);
+    let parsed_date = timestamp::parse(&date)
+        .ok_or_else(|| AppError::validation("date", format!("unrecognized date {}", date)))?;
 
     db.create_transaction(account_id, category_id, amount, description, transaction_type, parsed_date, tag_ids)
         .await
-        .map_err(|e| e.to_string())
 }
 
 #[tauri::command]
@@ -244,41 +323,37 @@ async fn update_transaction(
     id: String,
     account_id: String,
     category_id: String,
-    amount: f64,
+    amount: Money,
     description: String,
     transaction_type: String,
     date: String,
-) -> Result<Transaction, String> {
+) -> Result<Transaction, AppError> {
     let db = db.lock().await;
-    let parsed_date = DateTime::parse_from_rfc3339(&date)
-        .map_err(|e| format!("Invalid date format: {}", e))?
-        .with_timezone(&Utc);
+    let parsed_date = timestamp::parse(&date)
+        .ok_or_else(|| AppError::validation("date", format!("unrecognized date {}", date)))?;
 
     db.update_transaction(id, account_id, category_id, amount, description, transaction_type, parsed_date)
         .await
-        .map_err(|e| e.to_string())
 }
 
 #[tauri::command]
 async fn delete_transaction(
     id: String,
     db: State<'_, DatabaseState>,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
     let db = db.lock().await;
     db.delete_transaction(id)
         .await
-        .map_err(|e| e.to_string())
 }
 
 #[tauri::command]
 async fn delete_multiple_transactions(
     transaction_ids: Vec<String>,
     db: State<'_, DatabaseState>,
-) -> Result<usize, String> {
+) -> Result<usize, AppError> {
     let db = db.lock().await;
     db.delete_multiple_transactions(transaction_ids)
         .await
-        .map_err(|e| e.to_string())
 }
 
 #[tauri::command]
@@ -287,17 +362,70 @@ async fn bulk_update_transaction_tags(
     tags_to_add: Option<Vec<String>>,
     tags_to_remove: Option<Vec<String>>,
     db: State<'_, DatabaseState>,
-) -> Result<usize, String> {
+) -> Result<usize, AppError> {
     let db = db.lock().await;
     db.bulk_update_transaction_tags(transaction_ids, tags_to_add, tags_to_remove)
         .await
-        .map_err(|e| e.to_string())
+}
+
+// Transfer commands
+#[tauri::command]
+async fn create_transfer(
+    db: State<'_, DatabaseState>,
+    transfer: NewTransfer,
+) -> Result<Transfer, AppError> {
+    let db = db.lock().await;
+    db.create_transfer(transfer).await
+}
+
+#[tauri::command]
+async fn update_transfer(
+    db: State<'_, DatabaseState>,
+    id: String,
+    transfer: NewTransfer,
+) -> Result<Transfer, AppError> {
+    let db = db.lock().await;
+    db.update_transfer(id, transfer).await
+}
+
+#[tauri::command]
+async fn delete_transfer(
+    db: State<'_, DatabaseState>,
+    id: String,
+) -> Result<(), AppError> {
+    let db = db.lock().await;
+    db.delete_transfer(id).await
+}
+
+#[tauri::command]
+async fn get_transfers(db: State<'_, DatabaseState>) -> Result<Vec<Transfer>, AppError> {
+    let db = db.lock().await;
+    db.get_transfers().await
 }
 
 #[tauri::command]
-async fn get_transactions(db: State<'_, DatabaseState>) -> Result<Vec<Transaction>, String> {
+async fn get_transactions(db: State<'_, DatabaseState>) -> Result<Vec<Transaction>, AppError> {
     let db = db.lock().await;
-    db.get_transactions().await.map_err(|e| e.to_string())
+    db.get_transactions().await
+}
+
+#[tauri::command]
+async fn query_transactions(
+    db: State<'_, DatabaseState>,
+    filter: TransactionFilter,
+) -> Result<TransactionPage, AppError> {
+    let db = db.lock().await;
+    db.query_transactions(filter).await
+}
+
+#[tauri::command]
+async fn search_transactions(
+    db: State<'_, DatabaseState>,
+    query: String,
+    limit: Option<i64>,
+) -> Result<Vec<SearchHit>, AppError> {
+    let db = db.lock().await;
+    db.search_transactions(&query, limit).await
 }
 
 #[tauri::command]
@@ -305,27 +433,27 @@ async fn get_transactions_by_month(
     db: State<'_, DatabaseState>,
     year: i32,
     month: i32,
-) -> Result<Vec<Transaction>, String> {
+) -> Result<Vec<Transaction>, AppError> {
     let db = db.lock().await;
-    db.get_transactions_by_month(year, month).await.map_err(|e| e.to_string())
+    db.get_transactions_by_month(year, month).await
 }
 
 #[tauri::command]
 async fn get_transactions_by_account(
     db: State<'_, DatabaseState>,
     account_id: String,
-) -> Result<Vec<Transaction>, String> {
+) -> Result<Vec<Transaction>, AppError> {
     let db = db.lock().await;
-    db.get_transactions_by_account(account_id).await.map_err(|e| e.to_string())
+    db.get_transactions_by_account(account_id).await
 }
 
 #[tauri::command]
 async fn get_transactions_by_category(
     db: State<'_, DatabaseState>,
     category_id: String,
-) -> Result<Vec<Transaction>, String> {
+) -> Result<Vec<Transaction>, AppError> {
     let db = db.lock().await;
-    db.get_transactions_by_category(category_id).await.map_err(|e| e.to_string())
+    db.get_transactions_by_category(category_id).await
 }
 
 #[tauri::command]
@@ -333,15 +461,15 @@ async fn get_transactions_by_date_range(
     db: State<'_, DatabaseState>,
     start_date: String,
     end_date: String,
-) -> Result<Vec<Transaction>, String> {
+) -> Result<Vec<Transaction>, AppError> {
     let db = db.lock().await;
-    db.get_transactions_by_date_range(start_date, end_date).await.map_err(|e| e.to_string())
+    db.get_transactions_by_date_range(start_date, end_date).await
 }
 
 #[tauri::command]
-async fn get_categories(db: State<'_, DatabaseState>) -> Result<Vec<Category>, String> {
+async fn get_categories(db: State<'_, DatabaseState>) -> Result<Vec<Category>, AppError> {
     let db = db.lock().await;
-    db.get_categories().await.map_err(|e| e.to_string())
+    db.get_categories().await
 }
 
 #[tauri::command]
@@ -351,9 +479,9 @@ async fn create_category(
     icon: String,
     category_type: String,
     color: String,
-) -> Result<Category, String> {
+) -> Result<Category, AppError> {
     let db = db.lock().await;
-    db.create_category(name, icon, category_type, color).await.map_err(|e| e.to_string())
+    db.create_category(name, icon, category_type, color).await
 }
 
 #[tauri::command]
@@ -364,18 +492,185 @@ async fn update_category(
     icon: String,
     category_type: String,
     color: String,
-) -> Result<Category, String> {
+) -> Result<Category, AppError> {
     let db = db.lock().await;
-    db.update_category(id, name, icon, category_type, color).await.map_err(|e| e.to_string())
+    db.update_category(id, name, icon, category_type, color).await
 }
 
 #[tauri::command]
 async fn delete_category(
     db: State<'_, DatabaseState>,
     id: String,
-) -> Result<(), String> {
+    reassign_to: Option<String>,
+) -> Result<(), AppError> {
+    let db = db.lock().await;
+    db.delete_category(id, reassign_to).await
+}
+
+// Suggestion commands
+#[tauri::command]
+async fn suggest_categories(
+    db: State<'_, DatabaseState>,
+    description: String,
+    transaction_type: Option<String>,
+    limit: Option<usize>,
+) -> Result<Suggestions, AppError> {
+    let db = db.lock().await;
+    db.suggest_categories(description, transaction_type, limit).await
+}
+
+// Rule commands
+#[tauri::command]
+async fn create_rule(db: State<'_, DatabaseState>, rule: NewRule) -> Result<Rule, AppError> {
+    let db = db.lock().await;
+    db.create_rule(rule).await
+}
+
+#[tauri::command]
+async fn update_rule(
+    db: State<'_, DatabaseState>,
+    id: String,
+    rule: NewRule,
+) -> Result<Rule, AppError> {
+    let db = db.lock().await;
+    db.update_rule(id, rule).await
+}
+
+#[tauri::command]
+async fn delete_rule(db: State<'_, DatabaseState>, id: String) -> Result<(), AppError> {
+    let db = db.lock().await;
+    db.delete_rule(id).await
+}
+
+#[tauri::command]
+async fn get_rules(db: State<'_, DatabaseState>) -> Result<Vec<Rule>, AppError> {
+    let db = db.lock().await;
+    db.get_rules().await
+}
+
+#[tauri::command]
+async fn apply_rules(db: State<'_, DatabaseState>, dry_run: bool) -> Result<Vec<RuleChange>, AppError> {
+    let db = db.lock().await;
+    db.apply_rules(dry_run).await
+}
+
+// Budget commands
+#[tauri::command]
+async fn create_budget(
+    db: State<'_, DatabaseState>,
+    budget: NewBudget,
+) -> Result<Budget, AppError> {
+    let db = db.lock().await;
+    db.create_budget(budget).await
+}
+
+#[tauri::command]
+async fn update_budget(
+    db: State<'_, DatabaseState>,
+    id: String,
+    budget: NewBudget,
+) -> Result<Budget, AppError> {
+    let db = db.lock().await;
+    db.update_budget(id, budget).await
+}
+
+#[tauri::command]
+async fn delete_budget(
+    db: State<'_, DatabaseState>,
+    id: String,
+) -> Result<(), AppError> {
+    let db = db.lock().await;
+    db.delete_budget(id).await
+}
+
+#[tauri::command]
+async fn get_budgets(db: State<'_, DatabaseState>) -> Result<Vec<Budget>, AppError> {
+    let db = db.lock().await;
+    db.get_budgets().await
+}
+
+#[tauri::command]
+async fn get_budget_report(
+    db: State<'_, DatabaseState>,
+    year: i32,
+    month: u32,
+) -> Result<Vec<BudgetStatus>, AppError> {
+    let db = db.lock().await;
+    db.get_budget_report(year, month).await
+}
+
+// Recurring transaction commands
+#[tauri::command]
+async fn create_recurring_rule(
+    db: State<'_, DatabaseState>,
+    rule: NewRecurringRule,
+) -> Result<RecurringRule, AppError> {
+    let db = db.lock().await;
+    db.create_recurring_rule(rule).await
+}
+
+#[tauri::command]
+async fn update_recurring_rule(
+    db: State<'_, DatabaseState>,
+    id: String,
+    rule: NewRecurringRule,
+) -> Result<RecurringRule, AppError> {
+    let db = db.lock().await;
+    db.update_recurring_rule(id, rule).await
+}
+
+#[tauri::command]
+async fn delete_recurring_rule(
+    db: State<'_, DatabaseState>,
+    id: String,
+) -> Result<(), AppError> {
+    let db = db.lock().await;
+    db.delete_recurring_rule(id).await
+}
+
+#[tauri::command]
+async fn get_recurring_rules(db: State<'_, DatabaseState>) -> Result<Vec<RecurringRule>, AppError> {
+    let db = db.lock().await;
+    db.get_recurring_rules().await
+}
+
+#[tauri::command]
+async fn get_recurring_occurrences(
+    db: State<'_, DatabaseState>,
+    rule_id: String,
+    until: NaiveDate,
+) -> Result<Vec<Occurrence>, AppError> {
+    let db = db.lock().await;
+    db.get_recurring_occurrences(rule_id, until).await
+}
+
+#[tauri::command]
+async fn skip_recurring_occurrence(
+    db: State<'_, DatabaseState>,
+    rule_id: String,
+    date: NaiveDate,
+) -> Result<(), AppError> {
+    let db = db.lock().await;
+    db.skip_recurring_occurrence(rule_id, date).await
+}
+
+#[tauri::command]
+async fn edit_recurring_occurrence(
+    db: State<'_, DatabaseState>,
+    rule_id: String,
+    date: NaiveDate,
+    changes: OccurrenceChanges,
+) -> Result<(), AppError> {
     let db = db.lock().await;
-    db.delete_category(id).await.map_err(|e| e.to_string())
+    db.edit_recurring_occurrence(rule_id, date, changes).await
+}
+
+#[tauri::command]
+async fn generate_recurring_transactions(
+    db: State<'_, DatabaseState>,
+) -> Result<Vec<GeneratedTransaction>, AppError> {
+    let db = db.lock().await;
+    db.generate_recurring_transactions(Local::now().date_naive()).await
 }
 
 // Tag commands
@@ -385,9 +680,9 @@ async fn create_tag(
     name: String,
     icon: String,
     color: String,
-) -> Result<Tag, String> {
+) -> Result<Tag, AppError> {
     let db = db.lock().await;
-    db.create_tag(name, icon, color).await.map_err(|e| e.to_string())
+    db.create_tag(name, icon, color).await
 }
 
 #[tauri::command]
@@ -397,26 +692,26 @@ async fn update_tag(
     name: String,
     icon: String,
     color: String,
-) -> Result<Tag, String> {
+) -> Result<Tag, AppError> {
     let db = db.lock().await;
-    db.update_tag(id, name, icon, color).await.map_err(|e| e.to_string())
+    db.update_tag(id, name, icon, color).await
 }
 
 #[tauri::command]
 async fn delete_tag(
     db: State<'_, DatabaseState>,
     id: String,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
     let db = db.lock().await;
-    db.delete_tag(id).await.map_err(|e| e.to_string())
+    db.delete_tag(id).await
 }
 
 #[tauri::command]
 async fn get_tags(
     db: State<'_, DatabaseState>,
-) -> Result<Vec<Tag>, String> {
+) -> Result<Vec<Tag>, AppError> {
     let db = db.lock().await;
-    db.get_tags().await.map_err(|e| e.to_string())
+    db.get_tags().await
 }
 
 // Transaction-Tag relationship commands
@@ -425,9 +720,9 @@ async fn add_tags_to_transaction(
     db: State<'_, DatabaseState>,
     transaction_id: String,
     tag_ids: Vec<String>,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
     let db = db.lock().await;
-    db.add_tags_to_transaction(transaction_id, tag_ids).await.map_err(|e| e.to_string())
+    db.add_tags_to_transaction(transaction_id, tag_ids).await
 }
 
 #[tauri::command]
@@ -435,37 +730,44 @@ async fn remove_tags_from_transaction(
     db: State<'_, DatabaseState>,
     transaction_id: String,
     tag_ids: Vec<String>,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
     let db = db.lock().await;
-    db.remove_tags_from_transaction(transaction_id, tag_ids).await.map_err(|e| e.to_string())
+    db.remove_tags_from_transaction(transaction_id, tag_ids).await
 }
 
 #[tauri::command]
 async fn get_transaction_tags(
     db: State<'_, DatabaseState>,
     transaction_id: String,
-) -> Result<Vec<Tag>, String> {
+) -> Result<Vec<Tag>, AppError> {
     let db = db.lock().await;
-    db.get_transaction_tags(transaction_id).await.map_err(|e| e.to_string())
+    db.get_transaction_tags(transaction_id).await
 }
 
 #[tauri::command]
 async fn get_transactions_by_tag(
     db: State<'_, DatabaseState>,
     tag_id: String,
-) -> Result<Vec<Transaction>, String> {
+) -> Result<Vec<Transaction>, AppError> {
     let db = db.lock().await;
-    db.get_transactions_by_tag(tag_id).await.map_err(|e| e.to_string())
+    db.get_transactions_by_tag(tag_id).await
 }
 
 #[tauri::command]
 async fn migrate_nomad_categories(
     db: State<'_, DatabaseState>
-) -> Result<String, String> {
+) -> Result<String, AppError> {
     let db = db.lock().await;
     migrate_categories::migrate_nomad_categories(db.get_pool()).await
 }
 
+// Backups go to Documents, out of the app data directory
+fn backup_dir(app: &tauri::AppHandle) -> Result<std::path::PathBuf, AppError> {
+    let documents_dir = app.path().document_dir()
+        .map_err(|e| AppError::io(format!("Failed to get documents directory: {}", e)))?;
+    Ok(documents_dir.join("MoneyZen Backups"))
+}
+
 #[tokio::main]
 async fn main() {
     tauri::Builder::default()
@@ -493,11 +795,21 @@ async fn main() {
                     let db = db_state.lock().await;
                     if let Err(e) = db.init_schema().await {
                         eprintln!("Failed to initialize database schema: {}", e);
+                    } else if let Err(e) = db.generate_recurring_transactions(Local::now().date_naive()).await {
+                        eprintln!("Failed to generate recurring transactions: {}", e);
                     }
                 }
 
                 // Store the database state in the app
-                handle.manage(db_state);
+                handle.manage(db_state.clone());
+
+                match backup_dir(&handle) {
+                    Ok(dir) => {
+                        let app_version = handle.package_info().version.to_string();
+                        tauri::async_runtime::spawn(backup::run_backup_schedule(db_state, dir, app_version));
+                    }
+                    Err(e) => eprintln!("Automatic backups disabled: {}", e),
+                }
             });
 
             Ok(())
@@ -509,15 +821,40 @@ async fn main() {
             get_accounts,
             update_account,
             delete_account,
+            recalculate_balances,
             backup_database,
+            list_backups,
+            get_backup_settings,
+            save_backup_settings,
             restore_database,
             batch_insert_transactions,
+            get_import_batches,
+            revert_import_batch,
+            preview_csv_import,
+            preview_ofx_import,
+            preview_camt_import,
+            preview_mt940_import,
+            preview_provider_import,
+            import_qif,
+            export_qif,
+            get_balance_reconciliation,
+            get_duplicate_settings,
+            save_duplicate_settings,
+            save_csv_profile,
+            get_csv_profiles,
+            delete_csv_profile,
             create_transaction,
             update_transaction,
             delete_transaction,
             delete_multiple_transactions,
             bulk_update_transaction_tags,
+            create_transfer,
+            update_transfer,
+            delete_transfer,
+            get_transfers,
             get_transactions,
+            query_transactions,
+            search_transactions,
             get_transactions_by_month,
             get_transactions_by_account,
             get_transactions_by_category,
@@ -526,6 +863,25 @@ async fn main() {
             create_category,
             update_category,
             delete_category,
+            suggest_categories,
+            create_rule,
+            update_rule,
+            delete_rule,
+            get_rules,
+            apply_rules,
+            create_budget,
+            update_budget,
+            delete_budget,
+            get_budgets,
+            get_budget_report,
+            create_recurring_rule,
+            update_recurring_rule,
+            delete_recurring_rule,
+            get_recurring_rules,
+            get_recurring_occurrences,
+            skip_recurring_occurrence,
+            edit_recurring_occurrence,
+            generate_recurring_transactions,
             get_tags,
             create_tag,
             update_tag,
@@ -536,6 +892,32 @@ async fn main() {
             get_transactions_by_tag,
             migrate_nomad_categories
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|handle, event| {
+            if let tauri::RunEvent::Exit = event {
+                backup_on_exit(handle);
+            }
+        });
+}
+
+// One last automatic backup before the process ends
+fn backup_on_exit(handle: &tauri::AppHandle) {
+    let (Some(db_state), Ok(dir)) = (handle.try_state::<DatabaseState>(), backup_dir(handle)) else {
+        return;
+    };
+    let db_state = db_state.inner().clone();
+    let app_version = handle.package_info().version.to_string();
+
+    // The event loop runs inside the runtime, so block on a thread of its own
+    let result = std::thread::spawn(move || {
+        tauri::async_runtime::block_on(async move {
+            let db = db_state.lock().await;
+            db.automatic_backup(&dir, &app_version).await
+        })
+    })
+    .join();
+    if let Ok(Err(e)) = result {
+        eprintln!("❌ Backup on exit failed: {}", e);
+    }
 }
\ No newline at end of file
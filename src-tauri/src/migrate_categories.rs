@@ -3,8 +3,9 @@
 
 use sqlx::{Pool, Sqlite};
 use uuid::Uuid;
-use chrono::Utc;
+use crate::timestamp;
 use serde::{Deserialize, Serialize};
+use crate::error::AppError;
 
 #[derive(Debug, Serialize, Deserialize)]
 struct CategoryData {
@@ -34,15 +35,15 @@ struct CategoryGroupsFile {
     groups: CategoryGroups,
 }
 
-pub async fn migrate_nomad_categories(pool: &Pool<Sqlite>) -> Result<String, String> {
+pub async fn migrate_nomad_categories(pool: &Pool<Sqlite>) -> Result<String, AppError> {
     println!("🚀 Starting N-OMAD Categories Migration...");
 
     // Step 1: Read category-groups.json
     let json_content = std::fs::read_to_string("../category-groups.json")
-        .map_err(|e| format!("Failed to read category-groups.json: {}", e))?;
+        .map_err(|e| AppError::io(format!("Failed to read category-groups.json: {}", e)))?;
 
     let groups_file: CategoryGroupsFile = serde_json::from_str(&json_content)
-        .map_err(|e| format!("Failed to parse JSON: {}", e))?;
+        .map_err(|e| AppError::validation("category-groups.json", e.to_string()))?;
 
     println!("📊 Loaded category groups from JSON");
 
@@ -51,12 +52,13 @@ pub async fn migrate_nomad_categories(pool: &Pool<Sqlite>) -> Result<String, Str
 
     let existing_categories: Vec<String> = sqlx::query_scalar("SELECT name FROM categories")
         .fetch_all(pool)
-        .await
-        .map_err(|e| format!("Failed to get existing categories: {}", e))?;
+        .await?;
 
     println!("📊 Found {} existing categories", existing_categories.len());
 
-    // Step 3: Insert new categories (skip existing ones)
+    // Step 3: Insert new categories (skip existing ones) - all or nothing
+    let mut tx = pool.begin()
+        .await?;
     let mut total_inserted = 0;
 
     // Insert income categories
@@ -69,7 +71,7 @@ pub async fn migrate_nomad_categories(pool: &Pool<Sqlite>) -> Result<String, Str
             }
 
             let id = Uuid::new_v4().to_string();
-            let now = Utc::now().to_rfc3339();
+            let now = timestamp::format(&timestamp::now());
 
             sqlx::query(
                 "INSERT INTO categories (id, name, color, icon, category_type, created_at)
@@ -81,9 +83,8 @@ pub async fn migrate_nomad_categories(pool: &Pool<Sqlite>) -> Result<String, Str
             .bind(&cat.icon)
             .bind("income")
             .bind(&now)
-            .execute(pool)
-            .await
-            .map_err(|e| format!("Failed to insert category {}: {}", cat.name, e))?;
+            .execute(&mut *tx)
+            .await?;
 
             println!("  ✅ {} {} ({})", cat.icon, cat.name, cat.color);
             total_inserted += 1;
@@ -100,7 +101,7 @@ pub async fn migrate_nomad_categories(pool: &Pool<Sqlite>) -> Result<String, Str
             }
 
             let id = Uuid::new_v4().to_string();
-            let now = Utc::now().to_rfc3339();
+            let now = timestamp::format(&timestamp::now());
 
             sqlx::query(
                 "INSERT INTO categories (id, name, color, icon, category_type, created_at)
@@ -112,15 +113,17 @@ pub async fn migrate_nomad_categories(pool: &Pool<Sqlite>) -> Result<String, Str
             .bind(&cat.icon)
             .bind("expense")
             .bind(&now)
-            .execute(pool)
-            .await
-            .map_err(|e| format!("Failed to insert category {}: {}", cat.name, e))?;
+            .execute(&mut *tx)
+            .await?;
 
             println!("  ✅ {} {} ({})", cat.icon, cat.name, cat.color);
             total_inserted += 1;
         }
     }
 
+    tx.commit()
+        .await?;
+
     println!("\n📈 MIGRATION COMPLETE!");
     println!("   ✅ Categories inserted: {}", total_inserted);
 
@@ -0,0 +1,296 @@
+// migrations.rs
+// Versioned schema migrations embedded in the binary
+
+use sqlx::{sqlite::SqliteConnection, Row, SqlitePool};
+use crate::timestamp;
+use sha2::{Digest, Sha256};
+use std::fmt;
+
+pub struct Migration {
+    pub version: i64,
+    pub name: &'static str,
+    pub sql: &'static str,
+}
+
+// Ordered by version. Never edit a migration once released - add a new one instead.
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        name: "initial_schema",
+        sql: include_str!("../migrations/0001_initial_schema.sql"),
+    },
+    Migration {
+        version: 2,
+        name: "account_owner",
+        sql: include_str!("../migrations/0002_account_owner.sql"),
+    },
+    Migration {
+        version: 3,
+        name: "money_minor_units",
+        sql: include_str!("../migrations/0003_money_minor_units.sql"),
+    },
+    Migration {
+        version: 4,
+        name: "transfers",
+        sql: include_str!("../migrations/0004_transfers.sql"),
+    },
+    Migration {
+        version: 5,
+        name: "canonical_timestamps",
+        sql: include_str!("../migrations/0005_canonical_timestamps.sql"),
+    },
+    Migration {
+        version: 6,
+        name: "transactions_fts",
+        sql: include_str!("../migrations/0006_transactions_fts.sql"),
+    },
+    Migration {
+        version: 7,
+        name: "budgets",
+        sql: include_str!("../migrations/0007_budgets.sql"),
+    },
+    Migration {
+        version: 8,
+        name: "recurring_rules",
+        sql: include_str!("../migrations/0008_recurring_rules.sql"),
+    },
+    Migration {
+        version: 9,
+        name: "csv_profiles",
+        sql: include_str!("../migrations/0009_csv_profiles.sql"),
+    },
+    Migration {
+        version: 10,
+        name: "statement_import",
+        sql: include_str!("../migrations/0010_statement_import.sql"),
+    },
+    Migration {
+        version: 11,
+        name: "transaction_counterparty",
+        sql: include_str!("../migrations/0011_transaction_counterparty.sql"),
+    },
+    Migration {
+        version: 12,
+        name: "cleared_status",
+        sql: include_str!("../migrations/0012_cleared_status.sql"),
+    },
+    Migration {
+        version: 13,
+        name: "duplicate_matching",
+        sql: include_str!("../migrations/0013_duplicate_matching.sql"),
+    },
+    Migration {
+        version: 14,
+        name: "import_batches",
+        sql: include_str!("../migrations/0014_import_batches.sql"),
+    },
+    Migration {
+        version: 15,
+        name: "rules",
+        sql: include_str!("../migrations/0015_rules.sql"),
+    },
+    Migration {
+        version: 16,
+        name: "backup_settings",
+        sql: include_str!("../migrations/0016_backup_settings.sql"),
+    },
+    Migration {
+        version: 17,
+        name: "transfer_category_type",
+        sql: include_str!("../migrations/0017_transfer_category_type.sql"),
+    },
+];
+
+#[derive(Debug)]
+pub enum MigrationError {
+    Database(sqlx::Error),
+    ChecksumMismatch { version: i64, name: String },
+    UnknownVersion(i64),
+    DatabaseTooNew { found: i64, supported: i64 },
+    ForeignKeyViolation { version: i64 },
+}
+
+impl fmt::Display for MigrationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MigrationError::Database(e) => write!(f, "{}", e),
+            MigrationError::ChecksumMismatch { version, name } => write!(
+                f,
+                "Migration {:04}_{} was modified after it was applied",
+                version, name
+            ),
+            MigrationError::UnknownVersion(version) => {
+                write!(f, "Database contains unknown migration {:04}", version)
+            }
+            MigrationError::DatabaseTooNew { found, supported } => write!(
+                f,
+                "Database schema version {} is newer than this app supports ({}). Please update MoneyZen.",
+                found, supported
+            ),
+            MigrationError::ForeignKeyViolation { version } => write!(
+                f,
+                "Migration {:04} left foreign key violations, rolled back",
+                version
+            ),
+        }
+    }
+}
+
+impl std::error::Error for MigrationError {}
+
+impl From<sqlx::Error> for MigrationError {
+    fn from(e: sqlx::Error) -> Self {
+        MigrationError::Database(e)
+    }
+}
+
+pub fn latest_version() -> i64 {
+    MIGRATIONS.last().map(|m| m.version).unwrap_or(0)
+}
+
+fn checksum(sql: &str) -> String {
+    // Normalize line endings so a CRLF checkout produces the same checksum
+    let normalized = sql.replace("\r\n", "\n");
+    format!("{:x}", Sha256::digest(normalized.as_bytes()))
+}
+
+pub async fn run_migrations(pool: &SqlitePool) -> Result<(), MigrationError> {
+    let mut conn = pool.acquire().await?;
+
+    // Table rebuilds need foreign keys off, and the pragma is a no-op inside a transaction
+    sqlx::query("PRAGMA foreign_keys = OFF")
+        .execute(&mut *conn)
+        .await?;
+
+    let result = apply_pending(&mut conn).await;
+
+    sqlx::query("PRAGMA foreign_keys = ON")
+        .execute(&mut *conn)
+        .await?;
+
+    result
+}
+
+async fn apply_pending(conn: &mut SqliteConnection) -> Result<(), MigrationError> {
+    let tracked: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name = 'schema_migrations'"
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS schema_migrations (
+            version INTEGER PRIMARY KEY,
+            name TEXT NOT NULL,
+            checksum TEXT NOT NULL,
+            applied_at TEXT NOT NULL
+        )
+        "#
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    if tracked == 0 {
+        baseline_legacy_schema(conn).await?;
+    }
+
+    let applied = sqlx::query("SELECT version, checksum FROM schema_migrations ORDER BY version")
+        .fetch_all(&mut *conn)
+        .await?;
+
+    let supported = latest_version();
+    let mut applied_versions = Vec::with_capacity(applied.len());
+
+    for row in &applied {
+        let version: i64 = row.get("version");
+        let stored_checksum: String = row.get("checksum");
+
+        if version > supported {
+            return Err(MigrationError::DatabaseTooNew { found: version, supported });
+        }
+
+        let migration = MIGRATIONS
+            .iter()
+            .find(|m| m.version == version)
+            .ok_or(MigrationError::UnknownVersion(version))?;
+
+        if checksum(migration.sql) != stored_checksum {
+            return Err(MigrationError::ChecksumMismatch {
+                version,
+                name: migration.name.to_string(),
+            });
+        }
+
+        applied_versions.push(version);
+    }
+
+    for migration in MIGRATIONS.iter().filter(|m| !applied_versions.contains(&m.version)) {
+        apply(conn, migration).await?;
+        println!("✅ Applied migration {:04}_{}", migration.version, migration.name);
+    }
+
+    Ok(())
+}
+
+async fn apply(conn: &mut SqliteConnection, migration: &Migration) -> Result<(), MigrationError> {
+    let mut tx = sqlx::Connection::begin(&mut *conn).await?;
+
+    sqlx::Executor::execute(&mut *tx, migration.sql).await?;
+
+    let violations = sqlx::query("PRAGMA foreign_key_check")
+        .fetch_all(&mut *tx)
+        .await?;
+    if !violations.is_empty() {
+        tx.rollback().await?;
+        return Err(MigrationError::ForeignKeyViolation { version: migration.version });
+    }
+
+    record(&mut tx, migration).await?;
+    tx.commit().await?;
+
+    Ok(())
+}
+
+async fn record(conn: &mut SqliteConnection, migration: &Migration) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "INSERT INTO schema_migrations (version, name, checksum, applied_at) VALUES (?, ?, ?, ?)"
+    )
+    .bind(migration.version)
+    .bind(migration.name)
+    .bind(checksum(migration.sql))
+    .bind(timestamp::format(&timestamp::now()))
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(())
+}
+
+// Databases created before schema_migrations existed may already have the owner column
+// from 0002, which cannot be added twice. 0001 only uses IF NOT EXISTS so it is always safe to run.
+async fn baseline_legacy_schema(conn: &mut SqliteConnection) -> Result<(), MigrationError> {
+    let columns = sqlx::query("PRAGMA table_info(accounts)")
+        .fetch_all(&mut *conn)
+        .await?;
+
+    if columns.is_empty() {
+        // Fresh database, nothing to baseline
+        return Ok(());
+    }
+
+    let has_owner_column = columns.iter().any(|row| {
+        let column_name: String = row.get("name");
+        column_name == "owner"
+    });
+
+    if has_owner_column {
+        let owner_migration = MIGRATIONS
+            .iter()
+            .find(|m| m.version == 2)
+            .ok_or(MigrationError::UnknownVersion(2))?;
+        record(conn, owner_migration).await?;
+        println!("✅ Existing database already has owner column, marked migration 0002 as applied");
+    }
+
+    Ok(())
+}
@@ -0,0 +1,282 @@
+// money.rs
+// Exact money amounts stored as integer minor units (bani, cents) plus ISO 4217 currency
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Hash)]
+pub struct Money {
+    pub minor_units: i64,
+    pub currency: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MoneyError {
+    InvalidAmount(String),
+    CurrencyMismatch { expected: String, found: String },
+    Overflow,
+}
+
+impl fmt::Display for MoneyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MoneyError::InvalidAmount(input) => write!(f, "Invalid amount: {}", input),
+            MoneyError::CurrencyMismatch { expected, found } => {
+                write!(f, "Currency mismatch: expected {}, found {}", expected, found)
+            }
+            MoneyError::Overflow => write!(f, "Amount is too large"),
+        }
+    }
+}
+
+impl std::error::Error for MoneyError {}
+
+// Number of decimal places for a currency. Must stay in sync with migration 0003.
+pub fn minor_unit_exponent(currency: &str) -> u32 {
+    match currency {
+        "JPY" | "KRW" | "ISK" | "CLP" | "VND" | "XAF" | "XOF" => 0,
+        "BHD" | "IQD" | "JOD" | "KWD" | "LYD" | "OMR" | "TND" => 3,
+        _ => 2,
+    }
+}
+
+impl Money {
+    pub fn new(minor_units: i64, currency: impl Into<String>) -> Self {
+        Money {
+            minor_units,
+            currency: currency.into(),
+        }
+    }
+
+    pub fn zero(currency: impl Into<String>) -> Self {
+        Money::new(0, currency)
+    }
+
+    /// Parses a decimal amount such as "1234.5", "-12,30" or "1 234.56" without going through f64.
+    pub fn parse(input: &str, currency: impl Into<String>) -> Result<Self, MoneyError> {
+        let currency = currency.into();
+        let exponent = minor_unit_exponent(&currency) as usize;
+        let invalid = || MoneyError::InvalidAmount(input.to_string());
+
+        let cleaned: String = input
+            .trim()
+            .chars()
+            .filter(|c| !c.is_whitespace() && *c != '\'')
+            .map(|c| if c == ',' { '.' } else { c })
+            .collect();
+
+        let (negative, digits) = match cleaned.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, cleaned.strip_prefix('+').unwrap_or(&cleaned)),
+        };
+
+        let (whole, fraction) = match digits.split_once('.') {
+            Some((whole, fraction)) => (whole, fraction),
+            None => (digits, ""),
+        };
+
+        if (whole.is_empty() && fraction.is_empty())
+            || !whole.chars().all(|c| c.is_ascii_digit())
+            || !fraction.chars().all(|c| c.is_ascii_digit())
+            || fraction.len() > exponent
+        {
+            return Err(invalid());
+        }
+
+        let padded = format!("{}{:0<width$}", whole, fraction, width = exponent);
+        let magnitude: i64 = if padded.is_empty() {
+            0
+        } else {
+            padded.parse().map_err(|_| MoneyError::Overflow)?
+        };
+
+        Ok(Money::new(if negative { -magnitude } else { magnitude }, currency))
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.minor_units == 0
+    }
+
+    pub fn is_negative(&self) -> bool {
+        self.minor_units < 0
+    }
+
+    pub fn abs(&self) -> Money {
+        Money::new(self.minor_units.abs(), self.currency.clone())
+    }
+
+    pub fn negate(&self) -> Money {
+        Money::new(-self.minor_units, self.currency.clone())
+    }
+
+    pub fn checked_add(&self, other: &Money) -> Result<Money, MoneyError> {
+        self.ensure_same_currency(other)?;
+        self.minor_units
+            .checked_add(other.minor_units)
+            .map(|minor_units| Money::new(minor_units, self.currency.clone()))
+            .ok_or(MoneyError::Overflow)
+    }
+
+    pub fn ensure_currency(&self, currency: &str) -> Result<(), MoneyError> {
+        if self.currency == currency {
+            Ok(())
+        } else {
+            Err(MoneyError::CurrencyMismatch {
+                expected: currency.to_string(),
+                found: self.currency.clone(),
+            })
+        }
+    }
+
+    fn ensure_same_currency(&self, other: &Money) -> Result<(), MoneyError> {
+        other.ensure_currency(&self.currency)
+    }
+
+    /// Converts at a stated decimal rate such as "4.9750", where one unit of `self` buys `rate`
+    /// units of `currency`. The result is rounded half away from zero to the target minor unit.
+    pub fn convert(&self, rate: &str, currency: impl Into<String>) -> Result<Money, MoneyError> {
+        let currency = currency.into();
+        let (rate_units, rate_scale) = parse_rate(rate)?;
+
+        let numerator = (self.minor_units as i128)
+            .checked_mul(rate_units)
+            .and_then(|n| n.checked_mul(10i128.pow(minor_unit_exponent(&currency))))
+            .ok_or(MoneyError::Overflow)?;
+        let denominator = 10i128.pow(rate_scale + minor_unit_exponent(&self.currency));
+
+        let minor_units = i64::try_from(divide_rounded(numerator, denominator))
+            .map_err(|_| MoneyError::Overflow)?;
+
+        Ok(Money::new(minor_units, currency))
+    }
+
+    /// Rate implied by two amounts (`other` per one unit of `self`), to six decimal places
+    pub fn rate_to(&self, other: &Money) -> Option<String> {
+        if self.minor_units == 0 {
+            return None;
+        }
+
+        let numerator = (other.minor_units as i128)
+            * 10i128.pow(minor_unit_exponent(&self.currency))
+            * 1_000_000;
+        let denominator = (self.minor_units as i128) * 10i128.pow(minor_unit_exponent(&other.currency));
+        let scaled = divide_rounded(numerator, denominator);
+
+        let sign = if scaled < 0 { "-" } else { "" };
+        let magnitude = scaled.unsigned_abs();
+        Some(format!("{}{}.{:06}", sign, magnitude / 1_000_000, magnitude % 1_000_000))
+    }
+
+    /// Decimal representation without currency, e.g. "-12.30"
+    pub fn to_decimal_string(&self) -> String {
+        let exponent = minor_unit_exponent(&self.currency);
+        let sign = if self.minor_units < 0 { "-" } else { "" };
+        let magnitude = self.minor_units.unsigned_abs();
+
+        if exponent == 0 {
+            return format!("{}{}", sign, magnitude);
+        }
+
+        let scale = 10u64.pow(exponent);
+        format!(
+            "{}{}.{:0width$}",
+            sign,
+            magnitude / scale,
+            magnitude % scale,
+            width = exponent as usize
+        )
+    }
+}
+
+impl fmt::Display for Money {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.to_decimal_string(), self.currency)
+    }
+}
+
+// Splits "4.9750" into (49750, 4)
+fn parse_rate(rate: &str) -> Result<(i128, u32), MoneyError> {
+    let invalid = || MoneyError::InvalidAmount(rate.to_string());
+    let rate = rate.trim().replace(',', ".");
+
+    let (whole, fraction) = rate.split_once('.').unwrap_or((&rate, ""));
+    if (whole.is_empty() && fraction.is_empty())
+        || !whole.chars().all(|c| c.is_ascii_digit())
+        || !fraction.chars().all(|c| c.is_ascii_digit())
+        || fraction.len() > 12
+    {
+        return Err(invalid());
+    }
+
+    let units: i128 = format!("{}{}", whole, fraction).parse().map_err(|_| invalid())?;
+    if units == 0 {
+        return Err(invalid());
+    }
+
+    Ok((units, fraction.len() as u32))
+}
+
+// Integer division rounding half away from zero
+fn divide_rounded(numerator: i128, denominator: i128) -> i128 {
+    let quotient = numerator / denominator;
+    let remainder = numerator % denominator;
+
+    if remainder.abs() * 2 >= denominator.abs() {
+        quotient + numerator.signum() * denominator.signum()
+    } else {
+        quotient
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_uses_currency_exponent() {
+        assert_eq!(Money::parse("1234.5", "RON").unwrap().minor_units, 123450);
+        assert_eq!(Money::parse("-12,30", "EUR").unwrap().minor_units, -1230);
+        assert_eq!(Money::parse("1 234.56", "RON").unwrap().minor_units, 123456);
+        assert_eq!(Money::parse("+.5", "RON").unwrap().minor_units, 50);
+        assert_eq!(Money::parse("1500", "JPY").unwrap().minor_units, 1500);
+        assert_eq!(Money::parse("1.234", "KWD").unwrap().minor_units, 1234);
+    }
+
+    #[test]
+    fn parse_rejects_extra_precision_and_garbage() {
+        assert!(Money::parse("1.5", "JPY").is_err());
+        assert!(Money::parse("1.2345", "KWD").is_err());
+        assert!(Money::parse("1.005", "RON").is_err());
+        assert!(Money::parse("", "RON").is_err());
+        assert!(Money::parse("12a", "RON").is_err());
+        assert_eq!(Money::parse("99999999999999999999", "RON"), Err(MoneyError::Overflow));
+    }
+
+    #[test]
+    fn convert_rounds_half_away_from_zero() {
+        let eur = Money::parse("100.00", "EUR").unwrap();
+        assert_eq!(eur.convert("4.9750", "RON").unwrap(), Money::new(49750, "RON"));
+
+        // 0.01 * 0.5 = 0.005 rounds away from zero either side
+        assert_eq!(Money::new(1, "EUR").convert("0.5", "USD").unwrap().minor_units, 1);
+        assert_eq!(Money::new(-1, "EUR").convert("0.5", "USD").unwrap().minor_units, -1);
+        assert_eq!(Money::new(3, "EUR").convert("0.1", "USD").unwrap().minor_units, 0);
+    }
+
+    #[test]
+    fn convert_between_exponents() {
+        assert_eq!(Money::parse("1.00", "EUR").unwrap().convert("161.5", "JPY").unwrap().minor_units, 162);
+        assert_eq!(Money::new(100, "JPY").convert("0.0061", "EUR").unwrap().minor_units, 61);
+        assert_eq!(Money::parse("2.50", "EUR").unwrap().convert("0.3333", "KWD").unwrap().minor_units, 833);
+        assert!(Money::new(100, "EUR").convert("0", "RON").is_err());
+        assert!(Money::new(100, "EUR").convert("abc", "RON").is_err());
+    }
+
+    #[test]
+    fn decimal_string_round_trips() {
+        assert_eq!(Money::new(-1230, "RON").to_decimal_string(), "-12.30");
+        assert_eq!(Money::new(1500, "JPY").to_decimal_string(), "1500");
+        assert_eq!(Money::new(5, "KWD").to_decimal_string(), "0.005");
+        assert_eq!(Money::parse("0.005", "KWD").unwrap(), Money::new(5, "KWD"));
+    }
+}
@@ -0,0 +1,801 @@
+// recurring.rs
+// Recurring transaction rules and the generator that materializes due occurrences
+
+use serde::{Deserialize, Serialize};
+use sqlx::{sqlite::{SqliteConnection, SqliteRow}, FromRow, Row};
+use chrono::{DateTime, Datelike, Duration, NaiveDate, NaiveTime, Utc, Weekday};
+use std::collections::HashMap;
+use uuid::Uuid;
+use crate::database::{Database, NewTransaction, Transaction};
+use crate::error::AppError;
+use crate::money::Money;
+use crate::timestamp;
+
+// Guards the schedule walk against rules that can never produce another date
+const MAX_SCHEDULE_STEPS: i64 = 100_000;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Frequency {
+    Daily,
+    Weekly,
+    Monthly,
+    Yearly,
+}
+
+impl Frequency {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Frequency::Daily => "daily",
+            Frequency::Weekly => "weekly",
+            Frequency::Monthly => "monthly",
+            Frequency::Yearly => "yearly",
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RecurringRule {
+    pub id: String,
+    pub account_id: String,
+    pub category_id: String,
+    pub amount: Money,
+    pub description: String,
+    pub transaction_type: String, // "income" or "expense"
+    pub tag_ids: Vec<String>,
+    pub frequency: Frequency,
+    pub interval: i64,                // every n days/weeks/months/years
+    pub weekday: Option<u32>,         // 0 = Monday; weekly and nth-weekday monthly rules
+    pub week_of_month: Option<i32>,   // 1-5 or -1 for the last; monthly rules only
+    pub start_date: NaiveDate,
+    pub end_date: Option<NaiveDate>,  // inclusive
+    pub max_occurrences: Option<i64>,
+    pub active: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct NewRecurringRule {
+    pub account_id: String,
+    pub category_id: String,
+    pub amount: Money,
+    pub description: String,
+    pub transaction_type: String,
+    #[serde(default)]
+    pub tag_ids: Vec<String>,
+    pub frequency: Frequency,
+    pub interval: Option<i64>, // defaults to 1
+    pub weekday: Option<u32>,
+    pub week_of_month: Option<i32>,
+    pub start_date: NaiveDate,
+    pub end_date: Option<NaiveDate>,
+    pub max_occurrences: Option<i64>,
+    pub active: Option<bool>, // defaults to true
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum OccurrenceStatus {
+    Scheduled, // nothing recorded yet
+    Pending,   // edited ahead of time, not generated yet
+    Generated,
+    Skipped,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct Occurrence {
+    pub rule_id: String,
+    pub date: NaiveDate,
+    pub status: OccurrenceStatus,
+    pub transaction_id: Option<String>,
+    pub amount: Money,
+    pub description: String,
+    pub category_id: String,
+}
+
+// Changes to a single occurrence; None keeps the rule's value
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct OccurrenceChanges {
+    pub amount: Option<Money>,
+    pub description: Option<String>,
+    pub category_id: Option<String>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct GeneratedTransaction {
+    pub rule_id: String,
+    pub occurrence_date: NaiveDate,
+    pub transaction: Transaction,
+}
+
+// What is stored in recurring_occurrences for one date
+struct OccurrenceRecord {
+    status: OccurrenceStatus,
+    transaction_id: Option<String>,
+    amount: Option<i64>,
+    description: Option<String>,
+    category_id: Option<String>,
+}
+
+impl<'r> FromRow<'r, SqliteRow> for RecurringRule {
+    fn from_row(row: &'r SqliteRow) -> Result<Self, sqlx::Error> {
+        let frequency: String = row.try_get("frequency")?;
+        let frequency = match frequency.as_str() {
+            "daily" => Frequency::Daily,
+            "weekly" => Frequency::Weekly,
+            "monthly" => Frequency::Monthly,
+            "yearly" => Frequency::Yearly,
+            other => return Err(decode_error("frequency", format!("unknown frequency {:?}", other))),
+        };
+        let tag_ids: String = row.try_get("tag_ids")?;
+        let tag_ids = serde_json::from_str(&tag_ids).map_err(|e| decode_error("tag_ids", e.to_string()))?;
+        let end_date = match row.try_get::<Option<String>, _>("end_date")? {
+            Some(_) => Some(timestamp::decode(row, "end_date")?.date_naive()),
+            None => None,
+        };
+
+        Ok(RecurringRule {
+            id: row.try_get("id")?,
+            account_id: row.try_get("account_id")?,
+            category_id: row.try_get("category_id")?,
+            amount: Money::new(row.try_get("amount")?, row.try_get::<String, _>("currency")?),
+            description: row.try_get("description")?,
+            transaction_type: row.try_get("transaction_type")?,
+            tag_ids,
+            frequency,
+            interval: row.try_get("interval")?,
+            weekday: row.try_get("weekday")?,
+            week_of_month: row.try_get("week_of_month")?,
+            start_date: timestamp::decode(row, "start_date")?.date_naive(),
+            end_date,
+            max_occurrences: row.try_get("max_occurrences")?,
+            active: row.try_get("active")?,
+            created_at: timestamp::decode(row, "created_at")?,
+            updated_at: timestamp::decode(row, "updated_at")?,
+        })
+    }
+}
+
+impl Database {
+    pub async fn create_recurring_rule(&self, new_rule: NewRecurringRule) -> Result<RecurringRule, AppError> {
+        validate_rule(&new_rule)?;
+        let id = Uuid::new_v4().to_string();
+        let now = timestamp::now();
+
+        let mut tx = self.begin().await?;
+        Self::ensure_account_currency(&mut tx, &new_rule.account_id, &new_rule.amount).await?;
+        ensure_tags_exist(&mut tx, &new_rule.tag_ids).await?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO recurring_rules (id, account_id, category_id, amount, currency, description,
+                                         transaction_type, tag_ids, frequency, interval, weekday,
+                                         week_of_month, start_date, end_date, max_occurrences, active,
+                                         created_at, updated_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            "#
+        )
+        .bind(&id)
+        .bind(&new_rule.account_id)
+        .bind(&new_rule.category_id)
+        .bind(new_rule.amount.minor_units)
+        .bind(&new_rule.amount.currency)
+        .bind(&new_rule.description)
+        .bind(&new_rule.transaction_type)
+        .bind(tag_ids_json(&new_rule.tag_ids))
+        .bind(new_rule.frequency.as_str())
+        .bind(new_rule.interval.unwrap_or(1))
+        .bind(new_rule.weekday)
+        .bind(new_rule.week_of_month)
+        .bind(date_key(new_rule.start_date))
+        .bind(new_rule.end_date.map(date_key))
+        .bind(new_rule.max_occurrences)
+        .bind(new_rule.active.unwrap_or(true))
+        .bind(timestamp::format(&now))
+        .bind(timestamp::format(&now))
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        self.get_recurring_rule(&id).await
+    }
+
+    // Occurrences already generated or recorded keep their state
+    pub async fn update_recurring_rule(&self, id: String, changes: NewRecurringRule) -> Result<RecurringRule, AppError> {
+        validate_rule(&changes)?;
+
+        let mut tx = self.begin().await?;
+        Self::ensure_account_currency(&mut tx, &changes.account_id, &changes.amount).await?;
+        ensure_tags_exist(&mut tx, &changes.tag_ids).await?;
+
+        let result = sqlx::query(
+            r#"
+            UPDATE recurring_rules
+            SET account_id = ?, category_id = ?, amount = ?, currency = ?, description = ?,
+                transaction_type = ?, tag_ids = ?, frequency = ?, interval = ?, weekday = ?,
+                week_of_month = ?, start_date = ?, end_date = ?, max_occurrences = ?, active = ?,
+                updated_at = ?
+            WHERE id = ?
+            "#
+        )
+        .bind(&changes.account_id)
+        .bind(&changes.category_id)
+        .bind(changes.amount.minor_units)
+        .bind(&changes.amount.currency)
+        .bind(&changes.description)
+        .bind(&changes.transaction_type)
+        .bind(tag_ids_json(&changes.tag_ids))
+        .bind(changes.frequency.as_str())
+        .bind(changes.interval.unwrap_or(1))
+        .bind(changes.weekday)
+        .bind(changes.week_of_month)
+        .bind(date_key(changes.start_date))
+        .bind(changes.end_date.map(date_key))
+        .bind(changes.max_occurrences)
+        .bind(changes.active.unwrap_or(true))
+        .bind(timestamp::format(&timestamp::now()))
+        .bind(&id)
+        .execute(&mut *tx)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(AppError::not_found("recurring rule"));
+        }
+
+        tx.commit().await?;
+
+        self.get_recurring_rule(&id).await
+    }
+
+    // Transactions generated by the rule stay in place
+    pub async fn delete_recurring_rule(&self, id: String) -> Result<(), AppError> {
+        let result = sqlx::query("DELETE FROM recurring_rules WHERE id = ?")
+            .bind(&id)
+            .execute(self.get_pool())
+            .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(AppError::not_found("recurring rule"));
+        }
+
+        Ok(())
+    }
+
+    pub async fn get_recurring_rules(&self) -> Result<Vec<RecurringRule>, AppError> {
+        let rules = sqlx::query_as::<_, RecurringRule>("SELECT * FROM recurring_rules ORDER BY description")
+            .fetch_all(self.get_pool())
+            .await?;
+
+        Ok(rules)
+    }
+
+    async fn get_recurring_rule(&self, id: &str) -> Result<RecurringRule, AppError> {
+        sqlx::query_as::<_, RecurringRule>("SELECT * FROM recurring_rules WHERE id = ?")
+            .bind(id)
+            .fetch_optional(self.get_pool())
+            .await?
+            .ok_or_else(|| AppError::not_found("recurring rule"))
+    }
+
+    // Scheduled dates of a rule up to `until`, with what has happened to each
+    pub async fn get_recurring_occurrences(&self, rule_id: String, until: NaiveDate) -> Result<Vec<Occurrence>, AppError> {
+        let rule = self.get_recurring_rule(&rule_id).await?;
+        let mut conn = self.get_pool().acquire().await?;
+        let records = occurrence_records(&mut conn, &rule.id).await?;
+
+        let occurrences = schedule(&rule, until)
+            .into_iter()
+            .map(|date| {
+                let record = records.get(&date);
+                Occurrence {
+                    rule_id: rule.id.clone(),
+                    date,
+                    status: record.map(|r| r.status).unwrap_or(OccurrenceStatus::Scheduled),
+                    transaction_id: record.and_then(|r| r.transaction_id.clone()),
+                    amount: record
+                        .and_then(|r| r.amount)
+                        .map(|amount| Money::new(amount, rule.amount.currency.clone()))
+                        .unwrap_or_else(|| rule.amount.clone()),
+                    description: record
+                        .and_then(|r| r.description.clone())
+                        .unwrap_or_else(|| rule.description.clone()),
+                    category_id: record
+                        .and_then(|r| r.category_id.clone())
+                        .unwrap_or_else(|| rule.category_id.clone()),
+                }
+            })
+            .collect();
+
+        Ok(occurrences)
+    }
+
+    // Marks one scheduled date so the generator leaves it out
+    pub async fn skip_recurring_occurrence(&self, rule_id: String, date: NaiveDate) -> Result<(), AppError> {
+        let rule = self.get_recurring_rule(&rule_id).await?;
+        ensure_scheduled(&rule, date)?;
+
+        let mut tx = self.begin().await?;
+        let records = occurrence_records(&mut tx, &rule.id).await?;
+        if let Some(OccurrenceStatus::Generated) = records.get(&date).map(|r| r.status) {
+            return Err(AppError::conflict(
+                "This occurrence was already generated; delete its transaction instead",
+            ));
+        }
+
+        upsert_occurrence(&mut tx, &rule.id, date, OccurrenceStatus::Skipped, &OccurrenceChanges::default()).await?;
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    // Edits one occurrence: the generated transaction if there is one, otherwise the values
+    // the generator will use for that date
+    pub async fn edit_recurring_occurrence(
+        &self,
+        rule_id: String,
+        date: NaiveDate,
+        changes: OccurrenceChanges,
+    ) -> Result<(), AppError> {
+        let rule = self.get_recurring_rule(&rule_id).await?;
+        ensure_scheduled(&rule, date)?;
+        if let Some(amount) = &changes.amount {
+            amount.ensure_currency(&rule.amount.currency)?;
+        }
+
+        let mut tx = self.begin().await?;
+        let records = occurrence_records(&mut tx, &rule.id).await?;
+        let record = records.get(&date);
+
+        match record {
+            Some(OccurrenceRecord { status: OccurrenceStatus::Generated, transaction_id: Some(transaction_id), .. }) => {
+                let current = sqlx::query_as::<_, Transaction>("SELECT * FROM transactions WHERE id = ?")
+                    .bind(transaction_id)
+                    .fetch_one(&mut *tx)
+                    .await?;
+
+                let updated = NewTransaction {
+                    account_id: current.account_id,
+                    category_id: changes.category_id.clone().unwrap_or(current.category_id),
+                    amount: changes.amount.clone().unwrap_or(current.amount),
+                    description: changes.description.clone().unwrap_or(current.description),
+                    transaction_type: current.transaction_type,
+                    date: current.date,
+                };
+                Self::update_transaction_in(&mut tx, transaction_id, updated).await?;
+            }
+            Some(OccurrenceRecord { status: OccurrenceStatus::Generated, .. }) => {
+                return Err(AppError::conflict("The transaction generated for this occurrence was deleted"));
+            }
+            _ => {
+                // Merge with earlier edits of the same pending occurrence
+                let merged = OccurrenceChanges {
+                    amount: changes.amount.clone().or_else(|| {
+                        record.and_then(|r| r.amount).map(|amount| Money::new(amount, rule.amount.currency.clone()))
+                    }),
+                    description: changes.description.clone().or_else(|| record.and_then(|r| r.description.clone())),
+                    category_id: changes.category_id.clone().or_else(|| record.and_then(|r| r.category_id.clone())),
+                };
+                upsert_occurrence(&mut tx, &rule.id, date, OccurrenceStatus::Pending, &merged).await?;
+            }
+        }
+
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    // Creates a transaction for every occurrence due on or before `today` that has not been
+    // generated or skipped yet. Safe to run any number of times.
+    pub async fn generate_recurring_transactions(&self, today: NaiveDate) -> Result<Vec<GeneratedTransaction>, AppError> {
+        let rules = sqlx::query_as::<_, RecurringRule>("SELECT * FROM recurring_rules WHERE active = 1")
+            .fetch_all(self.get_pool())
+            .await?;
+
+        // Each rule commits on its own, so one broken rule does not hold back the others
+        let mut generated = Vec::new();
+        for rule in rules {
+            match self.generate_for_rule(&rule, today).await {
+                Ok(transactions) => generated.extend(transactions),
+                Err(e) => eprintln!("❌ Recurring rule {} ({}) failed: {}", rule.id, rule.description, e),
+            }
+        }
+
+        Ok(generated)
+    }
+
+    async fn generate_for_rule(&self, rule: &RecurringRule, today: NaiveDate) -> Result<Vec<GeneratedTransaction>, AppError> {
+        let mut generated = Vec::new();
+        let mut tx = self.begin().await?;
+
+        let records = occurrence_records(&mut tx, &rule.id).await?;
+        // Tags deleted since the rule was saved are dropped rather than failing the insert
+        let tag_ids = Self::existing_tag_ids_in(&mut tx, &rule.tag_ids).await?;
+
+        for date in schedule(rule, today) {
+            let record = records.get(&date);
+            if matches!(record.map(|r| r.status), Some(OccurrenceStatus::Generated | OccurrenceStatus::Skipped)) {
+                continue;
+            }
+
+            let amount = record
+                .and_then(|r| r.amount)
+                .map(|amount| Money::new(amount, rule.amount.currency.clone()))
+                .unwrap_or_else(|| rule.amount.clone());
+            let new_transaction = NewTransaction {
+                account_id: rule.account_id.clone(),
+                category_id: record
+                    .and_then(|r| r.category_id.clone())
+                    .unwrap_or_else(|| rule.category_id.clone()),
+                amount,
+                description: record
+                    .and_then(|r| r.description.clone())
+                    .unwrap_or_else(|| rule.description.clone()),
+                transaction_type: rule.transaction_type.clone(),
+                date: date.and_time(NaiveTime::MIN).and_utc(),
+            };
+
+            let transaction = Self::create_transaction_in(&mut tx, new_transaction, &tag_ids).await?;
+            sqlx::query(
+                r#"
+                INSERT INTO recurring_occurrences (rule_id, occurrence_date, status, transaction_id, created_at, updated_at)
+                VALUES (?, ?, 'generated', ?, ?, ?)
+                ON CONFLICT (rule_id, occurrence_date)
+                DO UPDATE SET status = 'generated', transaction_id = excluded.transaction_id, updated_at = excluded.updated_at
+                "#
+            )
+            .bind(&rule.id)
+            .bind(date_key(date))
+            .bind(&transaction.id)
+            .bind(timestamp::format(&transaction.created_at))
+            .bind(timestamp::format(&transaction.created_at))
+            .execute(&mut *tx)
+            .await?;
+
+            generated.push(GeneratedTransaction {
+                rule_id: rule.id.clone(),
+                occurrence_date: date,
+                transaction,
+            });
+        }
+
+        tx.commit().await?;
+        Ok(generated)
+    }
+}
+
+async fn ensure_tags_exist(conn: &mut SqliteConnection, tag_ids: &[String]) -> Result<(), AppError> {
+    if Database::existing_tag_ids_in(conn, tag_ids).await?.len() != tag_ids.len() {
+        return Err(AppError::not_found("tag"));
+    }
+
+    Ok(())
+}
+
+async fn occurrence_records(
+    conn: &mut SqliteConnection,
+    rule_id: &str,
+) -> Result<HashMap<NaiveDate, OccurrenceRecord>, AppError> {
+    let rows = sqlx::query("SELECT * FROM recurring_occurrences WHERE rule_id = ?")
+        .bind(rule_id)
+        .fetch_all(&mut *conn)
+        .await?;
+
+    let mut records = HashMap::with_capacity(rows.len());
+    for row in rows {
+        let status = match row.try_get::<String, _>("status")?.as_str() {
+            "pending" => OccurrenceStatus::Pending,
+            "generated" => OccurrenceStatus::Generated,
+            _ => OccurrenceStatus::Skipped,
+        };
+
+        records.insert(
+            timestamp::decode(&row, "occurrence_date")?.date_naive(),
+            OccurrenceRecord {
+                status,
+                transaction_id: row.try_get("transaction_id")?,
+                amount: row.try_get("amount")?,
+                description: row.try_get("description")?,
+                category_id: row.try_get("category_id")?,
+            },
+        );
+    }
+
+    Ok(records)
+}
+
+async fn upsert_occurrence(
+    conn: &mut SqliteConnection,
+    rule_id: &str,
+    date: NaiveDate,
+    status: OccurrenceStatus,
+    changes: &OccurrenceChanges,
+) -> Result<(), AppError> {
+    let status = match status {
+        OccurrenceStatus::Pending | OccurrenceStatus::Scheduled => "pending",
+        OccurrenceStatus::Generated => "generated",
+        OccurrenceStatus::Skipped => "skipped",
+    };
+    let now = timestamp::format(&timestamp::now());
+
+    sqlx::query(
+        r#"
+        INSERT INTO recurring_occurrences (rule_id, occurrence_date, status, amount, description,
+                                           category_id, created_at, updated_at)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+        ON CONFLICT (rule_id, occurrence_date)
+        DO UPDATE SET status = excluded.status, amount = excluded.amount, description = excluded.description,
+                      category_id = excluded.category_id, updated_at = excluded.updated_at
+        "#
+    )
+    .bind(rule_id)
+    .bind(date_key(date))
+    .bind(status)
+    .bind(changes.amount.as_ref().map(|amount| amount.minor_units))
+    .bind(&changes.description)
+    .bind(&changes.category_id)
+    .bind(&now)
+    .bind(&now)
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(())
+}
+
+// Scheduled dates from the rule start up to and including `until`
+pub fn schedule(rule: &RecurringRule, until: NaiveDate) -> Vec<NaiveDate> {
+    let last = match rule.end_date {
+        Some(end_date) => end_date.min(until),
+        None => until,
+    };
+    let interval = rule.interval.max(1);
+    let mut dates = Vec::new();
+
+    for step in 0..MAX_SCHEDULE_STEPS {
+        if rule.max_occurrences.is_some_and(|max| dates.len() as i64 >= max) {
+            break;
+        }
+
+        let candidate = match rule.frequency {
+            Frequency::Daily => Some(rule.start_date + Duration::days(step * interval)),
+            Frequency::Weekly => {
+                let first = match rule.weekday.and_then(weekday_from_index) {
+                    Some(weekday) => next_weekday(rule.start_date, weekday),
+                    None => rule.start_date,
+                };
+                Some(first + Duration::weeks(step * interval))
+            }
+            Frequency::Monthly => {
+                let (year, month) = add_months(rule.start_date, step * interval);
+                match (rule.week_of_month, rule.weekday.and_then(weekday_from_index)) {
+                    (Some(week), Some(weekday)) => nth_weekday(year, month, week, weekday),
+                    _ => Some(clamped_date(year, month, rule.start_date.day())),
+                }
+            }
+            Frequency::Yearly => {
+                let (year, month) = add_months(rule.start_date, step * interval * 12);
+                Some(clamped_date(year, month, rule.start_date.day()))
+            }
+        };
+
+        let Some(date) = candidate else {
+            continue; // e.g. no 5th Friday that month
+        };
+        if date > last {
+            break;
+        }
+        if date >= rule.start_date {
+            dates.push(date);
+        }
+    }
+
+    dates
+}
+
+fn validate_rule(rule: &NewRecurringRule) -> Result<(), AppError> {
+    if rule.amount.minor_units <= 0 {
+        return Err(AppError::validation("amount", "Amount must be positive"));
+    }
+    if rule.transaction_type != "income" && rule.transaction_type != "expense" {
+        return Err(AppError::validation("transaction_type", "Must be income or expense"));
+    }
+    if rule.interval.is_some_and(|interval| interval < 1) {
+        return Err(AppError::validation("interval", "Must be at least 1"));
+    }
+    if rule.weekday.is_some_and(|weekday| weekday > 6) {
+        return Err(AppError::validation("weekday", "Must be between 0 (Monday) and 6 (Sunday)"));
+    }
+    if let Some(week) = rule.week_of_month {
+        if rule.frequency != Frequency::Monthly || rule.weekday.is_none() {
+            return Err(AppError::validation("week_of_month", "Only valid for monthly rules with a weekday"));
+        }
+        if !(week == -1 || (1..=5).contains(&week)) {
+            return Err(AppError::validation("week_of_month", "Must be 1 to 5, or -1 for the last week"));
+        }
+    }
+    if rule.end_date.is_some_and(|end_date| end_date < rule.start_date) {
+        return Err(AppError::validation("end_date", "Must not be before the start date"));
+    }
+    if rule.max_occurrences.is_some_and(|max| max < 1) {
+        return Err(AppError::validation("max_occurrences", "Must be at least 1"));
+    }
+
+    Ok(())
+}
+
+fn ensure_scheduled(rule: &RecurringRule, date: NaiveDate) -> Result<(), AppError> {
+    if schedule(rule, date).last() == Some(&date) {
+        Ok(())
+    } else {
+        Err(AppError::validation("date", format!("{} is not a scheduled date of this rule", date)))
+    }
+}
+
+fn date_key(date: NaiveDate) -> String {
+    timestamp::format(&date.and_time(NaiveTime::MIN).and_utc())
+}
+
+fn tag_ids_json(tag_ids: &[String]) -> String {
+    serde_json::to_string(tag_ids).unwrap_or_else(|_| "[]".to_string())
+}
+
+fn decode_error(column: &str, message: String) -> sqlx::Error {
+    sqlx::Error::ColumnDecode {
+        index: column.to_string(),
+        source: message.into(),
+    }
+}
+
+fn weekday_from_index(index: u32) -> Option<Weekday> {
+    Weekday::try_from(index as u8).ok()
+}
+
+fn next_weekday(from: NaiveDate, weekday: Weekday) -> NaiveDate {
+    let days_ahead = (7 + weekday.num_days_from_monday() as i64 - from.weekday().num_days_from_monday() as i64) % 7;
+    from + Duration::days(days_ahead)
+}
+
+fn add_months(date: NaiveDate, months: i64) -> (i32, u32) {
+    let index = date.year() as i64 * 12 + date.month0() as i64 + months;
+    (index.div_euclid(12) as i32, index.rem_euclid(12) as u32 + 1)
+}
+
+// Day of month, moved back to the last day for shorter months (31st -> 30th, 28th or 29th)
+fn clamped_date(year: i32, month: u32, day: u32) -> NaiveDate {
+    (1..=day)
+        .rev()
+        .find_map(|d| NaiveDate::from_ymd_opt(year, month, d))
+        .unwrap_or(NaiveDate::MIN)
+}
+
+// n-th given weekday of a month, or with n = -1 the last one
+fn nth_weekday(year: i32, month: u32, n: i32, weekday: Weekday) -> Option<NaiveDate> {
+    if n == -1 {
+        let (next_year, next_month) = if month == 12 { (year + 1, 1) } else { (year, month + 1) };
+        let last_day = NaiveDate::from_ymd_opt(next_year, next_month, 1)? - Duration::days(1);
+        let days_back = (7 + last_day.weekday().num_days_from_monday() as i64 - weekday.num_days_from_monday() as i64) % 7;
+        return Some(last_day - Duration::days(days_back));
+    }
+
+    let first = next_weekday(NaiveDate::from_ymd_opt(year, month, 1)?, weekday);
+    let date = first + Duration::weeks(n as i64 - 1);
+    (date.month() == month).then_some(date)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    fn rule(frequency: Frequency, start_date: NaiveDate) -> RecurringRule {
+        RecurringRule {
+            id: "rule".to_string(),
+            account_id: "account".to_string(),
+            category_id: "category".to_string(),
+            amount: Money::new(1000, "RON"),
+            description: "Rent".to_string(),
+            transaction_type: "expense".to_string(),
+            tag_ids: Vec::new(),
+            frequency,
+            interval: 1,
+            weekday: None,
+            week_of_month: None,
+            start_date,
+            end_date: None,
+            max_occurrences: None,
+            active: true,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn monthly_on_the_31st_clamps_to_short_months() {
+        let rule = rule(Frequency::Monthly, date(2024, 1, 31));
+
+        assert_eq!(
+            schedule(&rule, date(2024, 5, 1)),
+            vec![date(2024, 1, 31), date(2024, 2, 29), date(2024, 3, 31), date(2024, 4, 30)]
+        );
+        assert_eq!(clamped_date(2023, 2, 31), date(2023, 2, 28));
+        assert_eq!(clamped_date(2024, 2, 31), date(2024, 2, 29));
+        assert_eq!(clamped_date(2024, 6, 31), date(2024, 6, 30));
+    }
+
+    #[test]
+    fn yearly_on_leap_day_falls_back_to_the_28th() {
+        let rule = rule(Frequency::Yearly, date(2024, 2, 29));
+
+        assert_eq!(
+            schedule(&rule, date(2028, 3, 1)),
+            vec![date(2024, 2, 29), date(2025, 2, 28), date(2026, 2, 28), date(2027, 2, 28), date(2028, 2, 29)]
+        );
+    }
+
+    #[test]
+    fn add_months_crosses_year_boundaries() {
+        assert_eq!(add_months(date(2024, 11, 30), 3), (2025, 2));
+        assert_eq!(add_months(date(2024, 1, 15), -1), (2023, 12));
+        assert_eq!(add_months(date(2024, 1, 15), 24), (2026, 1));
+    }
+
+    #[test]
+    fn last_weekday_of_the_month() {
+        let mut rule = rule(Frequency::Monthly, date(2024, 1, 1));
+        rule.weekday = Some(4); // Friday
+        rule.week_of_month = Some(-1);
+
+        assert_eq!(
+            schedule(&rule, date(2024, 3, 31)),
+            vec![date(2024, 1, 26), date(2024, 2, 23), date(2024, 3, 29)]
+        );
+        assert_eq!(nth_weekday(2024, 12, -1, Weekday::Tue), Some(date(2024, 12, 31)));
+    }
+
+    #[test]
+    fn months_without_a_fifth_weekday_are_skipped() {
+        let mut rule = rule(Frequency::Monthly, date(2024, 1, 1));
+        rule.weekday = Some(4); // Friday
+        rule.week_of_month = Some(5);
+
+        assert_eq!(schedule(&rule, date(2024, 6, 30)), vec![date(2024, 3, 29), date(2024, 5, 31)]);
+        assert_eq!(nth_weekday(2024, 2, 5, Weekday::Fri), None);
+        assert_eq!(nth_weekday(2024, 2, 1, Weekday::Fri), Some(date(2024, 2, 2)));
+    }
+
+    #[test]
+    fn max_occurrences_and_end_date_stop_at_whichever_comes_first() {
+        let mut rule = rule(Frequency::Daily, date(2024, 1, 1));
+        rule.max_occurrences = Some(3);
+        rule.end_date = Some(date(2024, 1, 10));
+        assert_eq!(schedule(&rule, date(2024, 12, 31)), vec![date(2024, 1, 1), date(2024, 1, 2), date(2024, 1, 3)]);
+
+        rule.max_occurrences = Some(10);
+        rule.end_date = Some(date(2024, 1, 2));
+        assert_eq!(schedule(&rule, date(2024, 12, 31)), vec![date(2024, 1, 1), date(2024, 1, 2)]);
+
+        // `until` before either limit
+        assert_eq!(schedule(&rule, date(2024, 1, 1)), vec![date(2024, 1, 1)]);
+    }
+
+    #[test]
+    fn weekly_rule_starts_on_the_next_matching_weekday() {
+        let mut rule = rule(Frequency::Weekly, date(2024, 1, 3)); // a Wednesday
+        rule.weekday = Some(0); // Monday
+        rule.interval = 2;
+
+        assert_eq!(
+            schedule(&rule, date(2024, 2, 5)),
+            vec![date(2024, 1, 8), date(2024, 1, 22), date(2024, 2, 5)]
+        );
+
+        // Starting on the weekday itself keeps the start date
+        rule.start_date = date(2024, 1, 8);
+        assert_eq!(schedule(&rule, date(2024, 1, 21)), vec![date(2024, 1, 8)]);
+    }
+}
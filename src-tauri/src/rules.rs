@@ -0,0 +1,667 @@
+// rules.rs
+// Categorization rules: match on description, payee, amount range, account and type, and set
+// category, tags, owner or description. Applied to every import and on demand to existing
+// transactions.
+
+use chrono::{DateTime, Utc};
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+use sqlx::{sqlite::{SqliteConnection, SqliteRow}, FromRow, Row};
+use std::collections::HashSet;
+use uuid::Uuid;
+use crate::database::{Database, Transaction};
+use crate::error::AppError;
+use crate::import::ImportTransaction;
+use crate::money::Money;
+use crate::timestamp;
+
+// Every condition that is set must hold; text matches ignore case
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct RuleConditions {
+    pub description_contains: Option<String>,
+    pub description_regex: Option<String>,
+    pub payee_contains: Option<String>, // counterparty name, or the description when there is none
+    pub min_amount: Option<Money>,      // inclusive, compared with the unsigned amount
+    pub max_amount: Option<Money>,
+    pub account_id: Option<String>,
+    pub transaction_type: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct RuleActions {
+    pub category_id: Option<String>,
+    pub tag_ids: Vec<String>,
+    pub owner: Option<String>,
+    pub description: Option<String>, // replaces the description
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Rule {
+    pub id: String,
+    pub name: String,
+    pub priority: i64, // lower runs first
+    pub enabled: bool,
+    pub conditions: RuleConditions,
+    pub actions: RuleActions,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct NewRule {
+    pub name: String,
+    #[serde(default)]
+    pub priority: i64,
+    pub enabled: Option<bool>, // defaults to true
+    pub conditions: RuleConditions,
+    pub actions: RuleActions,
+}
+
+// What the matching rules change on one transaction. For each field the first rule in
+// priority order that sets it wins; tags from every matching rule are added.
+#[derive(Debug, Serialize, Clone, Default)]
+pub struct RuleOutcome {
+    pub rule_ids: Vec<String>,
+    pub category_id: Option<String>,
+    pub description: Option<String>,
+    pub owner: Option<String>,
+    pub tag_ids: Vec<String>,
+}
+
+// One existing transaction apply_rules changed, or would change on a dry run
+#[derive(Debug, Serialize, Clone)]
+pub struct RuleChange {
+    pub transaction: Transaction, // as it was before
+    pub outcome: RuleOutcome,
+}
+
+// The fields rules look at, shared by import rows and stored transactions
+pub struct RuleSubject<'a> {
+    pub account_id: &'a str,
+    pub description: &'a str,
+    pub counterparty_name: Option<&'a str>,
+    pub amount: &'a Money,
+    pub transaction_type: &'a str,
+}
+
+// Enabled rules in priority order with their regexes compiled once
+pub struct RuleSet {
+    rules: Vec<(Rule, Option<Regex>)>,
+}
+
+impl<'r> FromRow<'r, SqliteRow> for Rule {
+    fn from_row(row: &'r SqliteRow) -> Result<Self, sqlx::Error> {
+        let conditions: String = row.try_get("conditions")?;
+        let conditions = serde_json::from_str(&conditions).map_err(|e| sqlx::Error::ColumnDecode {
+            index: "conditions".to_string(),
+            source: e.into(),
+        })?;
+        let actions: String = row.try_get("actions")?;
+        let actions = serde_json::from_str(&actions).map_err(|e| sqlx::Error::ColumnDecode {
+            index: "actions".to_string(),
+            source: e.into(),
+        })?;
+
+        Ok(Rule {
+            id: row.try_get("id")?,
+            name: row.try_get("name")?,
+            priority: row.try_get("priority")?,
+            enabled: row.try_get("enabled")?,
+            conditions,
+            actions,
+            created_at: timestamp::decode(row, "created_at")?,
+            updated_at: timestamp::decode(row, "updated_at")?,
+        })
+    }
+}
+
+impl<'a> From<&'a ImportTransaction> for RuleSubject<'a> {
+    fn from(tx: &'a ImportTransaction) -> Self {
+        RuleSubject {
+            account_id: &tx.account_id,
+            description: &tx.description,
+            counterparty_name: tx.counterparty_name.as_deref(),
+            amount: &tx.amount,
+            transaction_type: &tx.transaction_type,
+        }
+    }
+}
+
+impl<'a> From<&'a Transaction> for RuleSubject<'a> {
+    fn from(tx: &'a Transaction) -> Self {
+        RuleSubject {
+            account_id: &tx.account_id,
+            description: &tx.description,
+            counterparty_name: tx.counterparty_name.as_deref(),
+            amount: &tx.amount,
+            transaction_type: &tx.transaction_type,
+        }
+    }
+}
+
+impl RuleOutcome {
+    pub fn is_empty(&self) -> bool {
+        self.rule_ids.is_empty()
+    }
+
+    // Drops the parts that would not change the transaction
+    fn against(mut self, tx: &Transaction, existing_tags: &[String]) -> RuleOutcome {
+        if self.category_id.as_deref() == Some(tx.category_id.as_str()) {
+            self.category_id = None;
+        }
+        if self.description.as_deref() == Some(tx.description.as_str()) {
+            self.description = None;
+        }
+        if self.owner.is_some() && self.owner == tx.owner {
+            self.owner = None;
+        }
+        self.tag_ids.retain(|tag_id| !existing_tags.contains(tag_id));
+        self
+    }
+
+    fn changes_anything(&self) -> bool {
+        self.category_id.is_some() || self.description.is_some() || self.owner.is_some() || !self.tag_ids.is_empty()
+    }
+}
+
+impl RuleSet {
+    pub fn evaluate(&self, subject: &RuleSubject) -> RuleOutcome {
+        let mut outcome = RuleOutcome::default();
+
+        for (rule, regex) in &self.rules {
+            if !matches(&rule.conditions, regex.as_ref(), subject) {
+                continue;
+            }
+            let actions = &rule.actions;
+            outcome.rule_ids.push(rule.id.clone());
+            if outcome.category_id.is_none() {
+                outcome.category_id = actions.category_id.clone();
+            }
+            if outcome.description.is_none() {
+                outcome.description = actions.description.clone();
+            }
+            if outcome.owner.is_none() {
+                outcome.owner = actions.owner.clone();
+            }
+            for tag_id in &actions.tag_ids {
+                if !outcome.tag_ids.contains(tag_id) {
+                    outcome.tag_ids.push(tag_id.clone());
+                }
+            }
+        }
+
+        outcome
+    }
+
+    // Sets category and description on an import row; tags and owner are returned for the insert
+    pub fn apply_to_import(&self, tx: &mut ImportTransaction) -> RuleOutcome {
+        let outcome = self.evaluate(&RuleSubject::from(&*tx));
+        if let Some(category_id) = &outcome.category_id {
+            tx.category_id = category_id.clone();
+        }
+        if let Some(description) = &outcome.description {
+            tx.description = description.clone();
+        }
+        outcome
+    }
+}
+
+impl Database {
+    pub async fn create_rule(&self, new_rule: NewRule) -> Result<Rule, AppError> {
+        validate_rule(&new_rule)?;
+        self.ensure_rule_references(&new_rule).await?;
+        let id = Uuid::new_v4().to_string();
+        let now = timestamp::now();
+
+        sqlx::query(
+            r#"
+            INSERT INTO rules (id, name, priority, enabled, conditions, actions, created_at, updated_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+            "#
+        )
+        .bind(&id)
+        .bind(new_rule.name.trim())
+        .bind(new_rule.priority)
+        .bind(new_rule.enabled.unwrap_or(true))
+        .bind(to_json(&new_rule.conditions)?)
+        .bind(to_json(&new_rule.actions)?)
+        .bind(timestamp::format(&now))
+        .bind(timestamp::format(&now))
+        .execute(self.get_pool())
+        .await?;
+
+        self.get_rule(&id).await
+    }
+
+    pub async fn update_rule(&self, id: String, changes: NewRule) -> Result<Rule, AppError> {
+        validate_rule(&changes)?;
+        self.ensure_rule_references(&changes).await?;
+
+        let result = sqlx::query(
+            r#"
+            UPDATE rules
+            SET name = ?, priority = ?, enabled = ?, conditions = ?, actions = ?, updated_at = ?
+            WHERE id = ?
+            "#
+        )
+        .bind(changes.name.trim())
+        .bind(changes.priority)
+        .bind(changes.enabled.unwrap_or(true))
+        .bind(to_json(&changes.conditions)?)
+        .bind(to_json(&changes.actions)?)
+        .bind(timestamp::format(&timestamp::now()))
+        .bind(&id)
+        .execute(self.get_pool())
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(AppError::not_found("rule"));
+        }
+
+        self.get_rule(&id).await
+    }
+
+    // Ids in a rule are plain JSON, so nothing else keeps them pointing at existing rows
+    async fn ensure_rule_references(&self, rule: &NewRule) -> Result<(), AppError> {
+        let mut conn = self.get_pool().acquire().await?;
+
+        if let Some(account_id) = &rule.conditions.account_id {
+            let found: Option<String> = sqlx::query_scalar("SELECT id FROM accounts WHERE id = ?")
+                .bind(account_id)
+                .fetch_optional(&mut *conn)
+                .await?;
+            found.ok_or_else(|| AppError::not_found("account"))?;
+        }
+        if let Some(category_id) = &rule.actions.category_id {
+            let found: Option<String> = sqlx::query_scalar("SELECT id FROM categories WHERE id = ?")
+                .bind(category_id)
+                .fetch_optional(&mut *conn)
+                .await?;
+            found.ok_or_else(|| AppError::not_found("category"))?;
+        }
+        if Database::existing_tag_ids_in(&mut conn, &rule.actions.tag_ids).await?.len() != rule.actions.tag_ids.len() {
+            return Err(AppError::not_found("tag"));
+        }
+
+        Ok(())
+    }
+
+    pub async fn delete_rule(&self, id: String) -> Result<(), AppError> {
+        let result = sqlx::query("DELETE FROM rules WHERE id = ?")
+            .bind(&id)
+            .execute(self.get_pool())
+            .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(AppError::not_found("rule"));
+        }
+
+        Ok(())
+    }
+
+    pub async fn get_rules(&self) -> Result<Vec<Rule>, AppError> {
+        let rules = sqlx::query_as::<_, Rule>("SELECT * FROM rules ORDER BY priority, created_at")
+            .fetch_all(self.get_pool())
+            .await?;
+
+        Ok(rules)
+    }
+
+    async fn get_rule(&self, id: &str) -> Result<Rule, AppError> {
+        sqlx::query_as::<_, Rule>("SELECT * FROM rules WHERE id = ?")
+            .bind(id)
+            .fetch_optional(self.get_pool())
+            .await?
+            .ok_or_else(|| AppError::not_found("rule"))
+    }
+
+    pub async fn rule_set(&self) -> Result<RuleSet, AppError> {
+        // A category or tag deleted after the rule was saved is dropped from its actions
+        let category_ids: HashSet<String> = sqlx::query_scalar("SELECT id FROM categories")
+            .fetch_all(self.get_pool())
+            .await?
+            .into_iter()
+            .collect();
+        let tag_ids: HashSet<String> = sqlx::query_scalar("SELECT id FROM tags")
+            .fetch_all(self.get_pool())
+            .await?
+            .into_iter()
+            .collect();
+
+        let rules = self
+            .get_rules()
+            .await?
+            .into_iter()
+            .filter(|rule| rule.enabled)
+            .map(|mut rule| {
+                let actions = &mut rule.actions;
+                if actions.category_id.as_ref().is_some_and(|id| !category_ids.contains(id)) {
+                    actions.category_id = None;
+                }
+                actions.tag_ids.retain(|id| tag_ids.contains(id));
+                // Saved rules were validated, so a regex that no longer compiles cannot happen
+                let regex = rule.conditions.description_regex.as_deref().and_then(|r| compile(r).ok());
+                (rule, regex)
+            })
+            .collect();
+
+        Ok(RuleSet { rules })
+    }
+
+    // Runs every enabled rule over existing transactions. Transfer legs are left alone. With
+    // dry_run nothing is written and the result shows what would change.
+    pub async fn apply_rules(&self, dry_run: bool) -> Result<Vec<RuleChange>, AppError> {
+        let rule_set = self.rule_set().await?;
+        let transactions = sqlx::query_as::<_, Transaction>(
+            "SELECT * FROM transactions WHERE transfer_id IS NULL ORDER BY date, created_at"
+        )
+        .fetch_all(self.get_pool())
+        .await?;
+
+        let mut changes = Vec::new();
+        for tx in transactions {
+            let outcome = rule_set.evaluate(&RuleSubject::from(&tx));
+            if outcome.is_empty() {
+                continue;
+            }
+            let existing_tags: Vec<String> = sqlx::query_scalar("SELECT tag_id FROM transaction_tags WHERE transaction_id = ?")
+                .bind(&tx.id)
+                .fetch_all(self.get_pool())
+                .await?;
+            let outcome = outcome.against(&tx, &existing_tags);
+            if outcome.changes_anything() {
+                changes.push(RuleChange { transaction: tx, outcome });
+            }
+        }
+
+        if !dry_run {
+            let mut conn = self.begin().await?;
+            for change in &changes {
+                Self::apply_outcome_in(&mut conn, &change.transaction.id, &change.outcome).await?;
+            }
+            conn.commit().await?;
+        }
+
+        Ok(changes)
+    }
+
+    pub(crate) async fn apply_outcome_in(
+        conn: &mut SqliteConnection,
+        transaction_id: &str,
+        outcome: &RuleOutcome,
+    ) -> Result<(), AppError> {
+        sqlx::query(
+            r#"
+            UPDATE transactions
+            SET category_id = COALESCE(?, category_id),
+                description = COALESCE(?, description),
+                owner = COALESCE(?, owner),
+                updated_at = ?
+            WHERE id = ?
+            "#
+        )
+        .bind(&outcome.category_id)
+        .bind(&outcome.description)
+        .bind(&outcome.owner)
+        .bind(timestamp::format(&timestamp::now()))
+        .bind(transaction_id)
+        .execute(&mut *conn)
+        .await?;
+
+        Self::add_tags_to_transaction_in(conn, transaction_id, &outcome.tag_ids).await
+    }
+}
+
+fn matches(conditions: &RuleConditions, regex: Option<&Regex>, subject: &RuleSubject) -> bool {
+    let description = subject.description.to_lowercase();
+
+    if let Some(text) = &conditions.description_contains {
+        if !description.contains(&text.to_lowercase()) {
+            return false;
+        }
+    }
+    if conditions.description_regex.is_some() && !regex.is_some_and(|regex| regex.is_match(subject.description)) {
+        return false;
+    }
+    if let Some(text) = &conditions.payee_contains {
+        let payee = subject.counterparty_name.map(str::to_lowercase).unwrap_or_else(|| description.clone());
+        if !payee.contains(&text.to_lowercase()) {
+            return false;
+        }
+    }
+    if let Some(min) = &conditions.min_amount {
+        if min.currency != subject.amount.currency || subject.amount.minor_units.abs() < min.minor_units {
+            return false;
+        }
+    }
+    if let Some(max) = &conditions.max_amount {
+        if max.currency != subject.amount.currency || subject.amount.minor_units.abs() > max.minor_units {
+            return false;
+        }
+    }
+    if conditions.account_id.as_deref().is_some_and(|id| id != subject.account_id) {
+        return false;
+    }
+    if conditions.transaction_type.as_deref().is_some_and(|t| t != subject.transaction_type) {
+        return false;
+    }
+
+    true
+}
+
+fn compile(pattern: &str) -> Result<Regex, regex::Error> {
+    RegexBuilder::new(pattern).case_insensitive(true).build()
+}
+
+fn to_json<T: Serialize>(value: &T) -> Result<String, AppError> {
+    serde_json::to_string(value).map_err(|e| AppError::validation("rule", e.to_string()))
+}
+
+fn validate_rule(rule: &NewRule) -> Result<(), AppError> {
+    if rule.name.trim().is_empty() {
+        return Err(AppError::validation("name", "must not be empty"));
+    }
+
+    let c = &rule.conditions;
+    let has_condition = c.description_contains.is_some()
+        || c.description_regex.is_some()
+        || c.payee_contains.is_some()
+        || c.min_amount.is_some()
+        || c.max_amount.is_some()
+        || c.account_id.is_some()
+        || c.transaction_type.is_some();
+    if !has_condition {
+        return Err(AppError::validation("conditions", "a rule needs at least one condition"));
+    }
+    if let Some(pattern) = &c.description_regex {
+        compile(pattern).map_err(|e| AppError::validation("description_regex", e.to_string()))?;
+    }
+    if let (Some(min), Some(max)) = (&c.min_amount, &c.max_amount) {
+        if min.currency != max.currency || min.minor_units > max.minor_units {
+            return Err(AppError::validation("max_amount", "must be in the same currency and not below min_amount"));
+        }
+    }
+    if let Some(transaction_type) = &c.transaction_type {
+        if transaction_type != "income" && transaction_type != "expense" {
+            return Err(AppError::validation("transaction_type", "must be \"income\" or \"expense\""));
+        }
+    }
+
+    let a = &rule.actions;
+    if a.category_id.is_none() && a.tag_ids.is_empty() && a.owner.is_none() && a.description.is_none() {
+        return Err(AppError::validation("actions", "a rule needs at least one action"));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(id: &str, conditions: RuleConditions, actions: RuleActions) -> Rule {
+        Rule {
+            id: id.to_string(),
+            name: id.to_string(),
+            priority: 0,
+            enabled: true,
+            conditions,
+            actions,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    fn subject<'a>(description: &'a str, counterparty_name: Option<&'a str>, amount: &'a Money) -> RuleSubject<'a> {
+        RuleSubject {
+            account_id: "ac1",
+            description,
+            counterparty_name,
+            amount,
+            transaction_type: "expense",
+        }
+    }
+
+    fn contains(text: &str) -> RuleConditions {
+        RuleConditions { description_contains: Some(text.to_string()), ..RuleConditions::default() }
+    }
+
+    #[test]
+    fn first_matching_rule_wins_each_field_and_tags_add_up() {
+        let rule_set = RuleSet {
+            rules: vec![
+                (rule("groceries", contains("lidl"), RuleActions {
+                    category_id: Some("food".to_string()),
+                    tag_ids: vec!["t1".to_string(), "t2".to_string()],
+                    ..RuleActions::default()
+                }), None),
+                (rule("fuel", contains("benzina"), RuleActions {
+                    category_id: Some("transport".to_string()),
+                    ..RuleActions::default()
+                }), None),
+                (rule("cluj", contains("cluj"), RuleActions {
+                    category_id: Some("travel".to_string()),
+                    description: Some("Lidl".to_string()),
+                    tag_ids: vec!["t2".to_string(), "t3".to_string()],
+                    ..RuleActions::default()
+                }), None),
+            ],
+        };
+
+        let amount = Money::new(-12345, "RON");
+        let outcome = rule_set.evaluate(&subject("LIDL CLUJ-NAPOCA", None, &amount));
+        assert_eq!(outcome.rule_ids, ["groceries", "cluj"]);
+        assert_eq!(outcome.category_id.as_deref(), Some("food"));
+        assert_eq!(outcome.description.as_deref(), Some("Lidl"));
+        assert_eq!(outcome.tag_ids, ["t1", "t2", "t3"]);
+
+        assert!(rule_set.evaluate(&subject("Kaufland", None, &amount)).is_empty());
+    }
+
+    #[test]
+    fn amount_range_needs_the_same_currency() {
+        let conditions = RuleConditions {
+            min_amount: Some(Money::new(1000, "RON")),
+            max_amount: Some(Money::new(2000, "RON")),
+            ..RuleConditions::default()
+        };
+        let matches_amount = |amount: Money| matches(&conditions, None, &subject("Rent", None, &amount));
+
+        assert!(matches_amount(Money::new(-1500, "RON"))); // unsigned
+        assert!(matches_amount(Money::new(1000, "RON")));
+        assert!(matches_amount(Money::new(2000, "RON")));
+        assert!(!matches_amount(Money::new(999, "RON")));
+        assert!(!matches_amount(Money::new(2001, "RON")));
+        assert!(!matches_amount(Money::new(1500, "EUR")));
+    }
+
+    #[test]
+    fn payee_falls_back_to_the_description() {
+        let conditions = RuleConditions { payee_contains: Some("lidl".to_string()), ..RuleConditions::default() };
+        let amount = Money::new(-500, "RON");
+
+        assert!(matches(&conditions, None, &subject("LIDL CLUJ", None, &amount)));
+        assert!(matches(&conditions, None, &subject("POS 1234", Some("Lidl Romania SCS"), &amount)));
+        assert!(!matches(&conditions, None, &subject("LIDL CLUJ", Some("Kaufland"), &amount)));
+    }
+
+    #[test]
+    fn regex_ignores_case() {
+        let conditions = RuleConditions { description_regex: Some(r"^netflix\b".to_string()), ..RuleConditions::default() };
+        let regex = compile(conditions.description_regex.as_deref().unwrap()).unwrap();
+        let amount = Money::new(-4999, "RON");
+
+        assert!(matches(&conditions, Some(&regex), &subject("NETFLIX.COM", None, &amount)));
+        assert!(!matches(&conditions, Some(&regex), &subject("PAYPAL *NETFLIX", None, &amount)));
+    }
+
+    #[test]
+    fn outcome_against_drops_what_is_already_set() {
+        let tx = Transaction {
+            id: "tx1".to_string(),
+            account_id: "ac1".to_string(),
+            category_id: "food".to_string(),
+            amount: Money::new(-12345, "RON"),
+            description: "Lidl".to_string(),
+            transaction_type: "expense".to_string(),
+            transfer_id: None,
+            external_id: None,
+            counterparty_name: None,
+            counterparty_iban: None,
+            date: Utc::now(),
+            value_date: None,
+            cleared_status: "uncleared".to_string(),
+            owner: Some("ioan".to_string()),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        };
+        let outcome = RuleOutcome {
+            rule_ids: vec!["groceries".to_string()],
+            category_id: Some("food".to_string()),
+            description: Some("Lidl".to_string()),
+            owner: Some("ioan".to_string()),
+            tag_ids: vec!["t1".to_string(), "t2".to_string()],
+        };
+
+        let unchanged = outcome.clone().against(&tx, &["t1".to_string(), "t2".to_string()]);
+        assert!(!unchanged.changes_anything());
+
+        let changed = outcome.against(&tx, &["t1".to_string()]);
+        assert_eq!(changed.tag_ids, ["t2"]);
+        assert!(changed.category_id.is_none());
+    }
+
+    #[tokio::test]
+    async fn rule_set_runs_in_priority_order() {
+        let dir = std::env::temp_dir().join(format!("money-zen-rules-{}", uuid::Uuid::new_v4()));
+        let db = Database::new(dir.join("rules.db")).await.unwrap();
+        db.init_schema().await.unwrap();
+        let categories = db.get_categories().await.unwrap();
+
+        // created first but runs last
+        let late = db.create_rule(NewRule {
+            name: "late".to_string(),
+            priority: 10,
+            enabled: None,
+            conditions: contains("lidl"),
+            actions: RuleActions { category_id: Some(categories[0].id.clone()), ..RuleActions::default() },
+        }).await.unwrap();
+        let early = db.create_rule(NewRule {
+            name: "early".to_string(),
+            priority: 1,
+            enabled: None,
+            conditions: contains("lidl"),
+            actions: RuleActions { category_id: Some(categories[1].id.clone()), ..RuleActions::default() },
+        }).await.unwrap();
+
+        let amount = Money::new(-12345, "RON");
+        let outcome = db.rule_set().await.unwrap().evaluate(&subject("LIDL CLUJ", None, &amount));
+        assert_eq!(outcome.rule_ids, [early.id, late.id]);
+        assert_eq!(outcome.category_id, Some(categories[1].id.clone()));
+
+        db.close().await;
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
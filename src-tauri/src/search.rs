@@ -0,0 +1,96 @@
+// search.rs
+// Full-text search over transaction descriptions (FTS5 index from migration 0006)
+
+use serde::Serialize;
+use sqlx::{FromRow, Row};
+use crate::database::{Database, Transaction};
+use crate::error::AppError;
+
+const DEFAULT_LIMIT: i64 = 50;
+const MAX_LIMIT: i64 = 500;
+
+// Private-use characters mark matches inside the snippet until it has been HTML-escaped
+const MATCH_START: char = '\u{E000}';
+const MATCH_END: char = '\u{E001}';
+
+#[derive(Debug, Serialize, Clone)]
+pub struct SearchHit {
+    pub transaction: Transaction,
+    pub snippet: String, // HTML-escaped description with matches wrapped in <mark>
+    pub rank: f64,       // bm25, lower is better
+}
+
+impl Database {
+    // Every word of `query` must match as a prefix, so "lid clu" finds "POS 123 LIDL CLUJ"
+    pub async fn search_transactions(&self, query: &str, limit: Option<i64>) -> Result<Vec<SearchHit>, AppError> {
+        let match_expression = match fts_match_expression(query) {
+            Some(expression) => expression,
+            None => return Ok(Vec::new()),
+        };
+        let limit = limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+
+        let rows = sqlx::query(
+            r#"
+            SELECT t.*,
+                   snippet(transactions_fts, 1, char(57344), char(57345), '…', 16) AS snippet,
+                   bm25(transactions_fts) AS score
+            FROM transactions_fts
+            INNER JOIN transactions t ON t.id = transactions_fts.transaction_id
+            WHERE transactions_fts MATCH ?
+            ORDER BY score, t.date DESC
+            LIMIT ?
+            "#
+        )
+        .bind(&match_expression)
+        .bind(limit)
+        .fetch_all(self.get_pool())
+        .await?;
+
+        let hits = rows.iter().map(|row| {
+            let snippet: String = row.try_get("snippet")?;
+
+            Ok(SearchHit {
+                transaction: Transaction::from_row(row)?,
+                snippet: highlight(&snippet),
+                rank: row.try_get("score")?,
+            })
+        }).collect::<Result<Vec<_>, sqlx::Error>>()?;
+
+        Ok(hits)
+    }
+}
+
+// Turns free text into an FTS5 expression of quoted prefix terms, so user input
+// such as `AND`, `-` or `"` is never interpreted as query syntax
+fn fts_match_expression(query: &str) -> Option<String> {
+    let terms: Vec<String> = query
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|term| !term.is_empty())
+        .map(|term| format!("\"{}\"*", term))
+        .collect();
+
+    if terms.is_empty() {
+        None
+    } else {
+        Some(terms.join(" "))
+    }
+}
+
+fn highlight(snippet: &str) -> String {
+    let mut html = String::with_capacity(snippet.len() + 16);
+
+    for c in snippet.chars() {
+        match c {
+            MATCH_START => html.push_str("<mark>"),
+            MATCH_END => html.push_str("</mark>"),
+            '&' => html.push_str("&amp;"),
+            '<' => html.push_str("&lt;"),
+            '>' => html.push_str("&gt;"),
+            '"' => html.push_str("&quot;"),
+            '\'' => html.push_str("&#39;"),
+            c => html.push(c),
+        }
+    }
+
+    html
+}
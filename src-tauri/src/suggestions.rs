@@ -0,0 +1,332 @@
+// suggestions.rs
+// Category and tag suggestions learned from our own history: a multinomial naive Bayes over
+// description words for the category, and tags shared by similar past descriptions.
+
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use crate::database::Database;
+use crate::error::AppError;
+
+// Tags are suggested when at least this share of similar past transactions carry them
+const TAG_THRESHOLD: f64 = 0.5;
+// Past descriptions count as similar from this share of words in common
+const SIMILAR_DESCRIPTION: f64 = 0.5;
+
+#[derive(Debug, Serialize, Clone)]
+pub struct CategorySuggestion {
+    pub category_id: String,
+    pub name: String,
+    pub confidence: f64, // posterior probability, 0 to 1
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct TagSuggestion {
+    pub tag_id: String,
+    pub name: String,
+    pub confidence: f64, // share of similar past transactions with this tag
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct Suggestions {
+    pub categories: Vec<CategorySuggestion>, // best first
+    pub tags: Vec<TagSuggestion>,
+}
+
+struct CategoryStats {
+    category_type: String,
+    documents: usize,
+    words: usize,
+    word_counts: HashMap<String, usize>,
+}
+
+struct HistoryRow {
+    words: HashSet<String>,
+    tag_ids: Vec<String>,
+}
+
+// Trained once per import or request from every categorized transaction except transfer legs
+pub struct CategoryModel {
+    categories: HashMap<String, CategoryStats>,
+    vocabulary: HashSet<String>,
+    documents: usize,
+    history: Vec<HistoryRow>,
+}
+
+impl CategoryModel {
+    // Ranked categories for a description; empty when none of its words were seen before
+    pub fn suggest(&self, description: &str, transaction_type: Option<&str>) -> Vec<(String, f64)> {
+        let words: Vec<String> = tokenize(description)
+            .into_iter()
+            .filter(|word| self.vocabulary.contains(word))
+            .collect();
+        if words.is_empty() {
+            return Vec::new();
+        }
+
+        let vocabulary = self.vocabulary.len() as f64;
+        let scores: Vec<(String, f64)> = self
+            .categories
+            .iter()
+            .filter(|(_, stats)| transaction_type.is_none_or(|t| t == stats.category_type))
+            .map(|(category_id, stats)| {
+                let prior = (stats.documents as f64 / self.documents as f64).ln();
+                let likelihood: f64 = words
+                    .iter()
+                    .map(|word| {
+                        let count = stats.word_counts.get(word).copied().unwrap_or(0) as f64;
+                        ((count + 1.0) / (stats.words as f64 + vocabulary)).ln()
+                    })
+                    .sum();
+                (category_id.clone(), prior + likelihood)
+            })
+            .collect();
+
+        // Log scores to probabilities, shifted by the maximum to stay in range
+        let max = scores.iter().map(|(_, score)| *score).fold(f64::NEG_INFINITY, f64::max);
+        let total: f64 = scores.iter().map(|(_, score)| (score - max).exp()).sum();
+        let mut ranked: Vec<(String, f64)> = scores
+            .into_iter()
+            .map(|(category_id, score)| (category_id, (score - max).exp() / total))
+            .collect();
+        ranked.sort_by(|a, b| b.1.total_cmp(&a.1));
+        ranked
+    }
+
+    pub fn suggest_tags(&self, description: &str) -> Vec<(String, f64)> {
+        let words: HashSet<String> = tokenize(description).into_iter().collect();
+        if words.is_empty() {
+            return Vec::new();
+        }
+
+        let similar: Vec<&HistoryRow> = self
+            .history
+            .iter()
+            .filter(|row| {
+                let common = row.words.intersection(&words).count() as f64;
+                2.0 * common / (row.words.len() + words.len()) as f64 >= SIMILAR_DESCRIPTION
+            })
+            .collect();
+        if similar.is_empty() {
+            return Vec::new();
+        }
+
+        let mut counts: HashMap<&str, usize> = HashMap::new();
+        for row in &similar {
+            for tag_id in &row.tag_ids {
+                *counts.entry(tag_id.as_str()).or_insert(0) += 1;
+            }
+        }
+
+        let mut ranked: Vec<(String, f64)> = counts
+            .into_iter()
+            .map(|(tag_id, count)| (tag_id.to_string(), count as f64 / similar.len() as f64))
+            .filter(|(_, confidence)| *confidence >= TAG_THRESHOLD)
+            .collect();
+        ranked.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(&b.0)));
+        ranked
+    }
+}
+
+impl Database {
+    pub async fn category_model(&self) -> Result<CategoryModel, AppError> {
+        let rows: Vec<(String, String, String, String)> = sqlx::query_as(
+            r#"
+            SELECT t.id, t.description, t.category_id, c.category_type
+            FROM transactions t
+            JOIN categories c ON c.id = t.category_id
+            WHERE t.transfer_id IS NULL
+            "#
+        )
+        .fetch_all(self.get_pool())
+        .await?;
+
+        let tag_links: Vec<(String, String)> = sqlx::query_as("SELECT transaction_id, tag_id FROM transaction_tags")
+            .fetch_all(self.get_pool())
+            .await?;
+        let mut tags_by_transaction: HashMap<String, Vec<String>> = HashMap::new();
+        for (transaction_id, tag_id) in tag_links {
+            tags_by_transaction.entry(transaction_id).or_default().push(tag_id);
+        }
+
+        let mut model = CategoryModel {
+            categories: HashMap::new(),
+            vocabulary: HashSet::new(),
+            documents: 0,
+            history: Vec::new(),
+        };
+
+        for (id, description, category_id, category_type) in rows {
+            let words = tokenize(&description);
+            if words.is_empty() {
+                continue;
+            }
+
+            let stats = model.categories.entry(category_id).or_insert_with(|| CategoryStats {
+                category_type,
+                documents: 0,
+                words: 0,
+                word_counts: HashMap::new(),
+            });
+            stats.documents += 1;
+            stats.words += words.len();
+            for word in &words {
+                *stats.word_counts.entry(word.clone()).or_insert(0) += 1;
+                model.vocabulary.insert(word.clone());
+            }
+            model.documents += 1;
+            model.history.push(HistoryRow {
+                words: words.into_iter().collect(),
+                tag_ids: tags_by_transaction.remove(&id).unwrap_or_default(),
+            });
+        }
+
+        Ok(model)
+    }
+
+    pub async fn suggest_categories(
+        &self,
+        description: String,
+        transaction_type: Option<String>,
+        limit: Option<usize>,
+    ) -> Result<Suggestions, AppError> {
+        let model = self.category_model().await?;
+        let limit = limit.unwrap_or(3);
+
+        let names: HashMap<String, String> = self
+            .get_categories()
+            .await?
+            .into_iter()
+            .map(|category| (category.id, category.name))
+            .collect();
+        let categories = model
+            .suggest(&description, transaction_type.as_deref())
+            .into_iter()
+            .take(limit)
+            .map(|(category_id, confidence)| CategorySuggestion {
+                name: names.get(&category_id).cloned().unwrap_or_default(),
+                category_id,
+                confidence,
+            })
+            .collect();
+
+        let tag_names: HashMap<String, String> = self
+            .get_tags()
+            .await?
+            .into_iter()
+            .map(|tag| (tag.id, tag.name))
+            .collect();
+        let tags = model
+            .suggest_tags(&description)
+            .into_iter()
+            .map(|(tag_id, confidence)| TagSuggestion {
+                name: tag_names.get(&tag_id).cloned().unwrap_or_default(),
+                tag_id,
+                confidence,
+            })
+            .collect();
+
+        Ok(Suggestions { categories, tags })
+    }
+}
+
+// Lowercase words of at least two letters; numbers such as card or store ids vary per
+// transaction and would only add noise
+fn tokenize(description: &str) -> Vec<String> {
+    description
+        .to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| word.chars().count() >= 2 && !word.chars().all(|c| c.is_ascii_digit()))
+        .map(str::to_string)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // (category id, category type, description, tag ids), trained the way category_model does
+    fn model(rows: &[(&str, &str, &str, &[&str])]) -> CategoryModel {
+        let mut model = CategoryModel {
+            categories: HashMap::new(),
+            vocabulary: HashSet::new(),
+            documents: 0,
+            history: Vec::new(),
+        };
+
+        for (category_id, category_type, description, tag_ids) in rows {
+            let words = tokenize(description);
+            let stats = model.categories.entry(category_id.to_string()).or_insert_with(|| CategoryStats {
+                category_type: category_type.to_string(),
+                documents: 0,
+                words: 0,
+                word_counts: HashMap::new(),
+            });
+            stats.documents += 1;
+            stats.words += words.len();
+            for word in &words {
+                *stats.word_counts.entry(word.clone()).or_insert(0) += 1;
+                model.vocabulary.insert(word.clone());
+            }
+            model.documents += 1;
+            model.history.push(HistoryRow {
+                words: words.into_iter().collect(),
+                tag_ids: tag_ids.iter().map(|tag_id| tag_id.to_string()).collect(),
+            });
+        }
+
+        model
+    }
+
+    fn history() -> CategoryModel {
+        model(&[
+            ("food", "expense", "LIDL CLUJ", &["groceries"]),
+            ("food", "expense", "LIDL BRASOV", &["groceries", "weekend"]),
+            ("food", "expense", "KAUFLAND CLUJ", &["groceries"]),
+            ("transport", "expense", "PETROM CLUJ", &[]),
+            ("transport", "expense", "OMV BRASOV", &[]),
+            ("salary", "income", "SALARIU ACME LIDL", &[]),
+        ])
+    }
+
+    #[test]
+    fn tokenize_drops_numbers_and_single_letters() {
+        assert_eq!(tokenize("POS 1234 LIDL Cluj-Napoca 07/03 a"), ["pos", "lidl", "cluj", "napoca"]);
+        assert_eq!(tokenize("Plata B2B Brașov"), ["plata", "b2b", "brașov"]);
+        assert!(tokenize("0042 / 17.03").is_empty());
+    }
+
+    #[test]
+    fn categories_are_ranked_by_posterior() {
+        let ranked = history().suggest("Lidl Cluj 0042", Some("expense"));
+
+        let ids: Vec<&str> = ranked.iter().map(|(id, _)| id.as_str()).collect();
+        assert_eq!(ids, ["food", "transport"]);
+        assert!(ranked[0].1 > 0.8);
+        assert!((ranked.iter().map(|(_, p)| p).sum::<f64>() - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn transaction_type_limits_the_categories() {
+        let model = history();
+
+        assert_eq!(model.suggest("lidl", Some("income")), [("salary".to_string(), 1.0)]);
+        assert_eq!(model.suggest("lidl", None).len(), 3);
+        assert!(model.suggest("lidl", Some("transfer")).is_empty());
+    }
+
+    #[test]
+    fn unseen_words_suggest_nothing() {
+        let model = history();
+
+        assert!(model.suggest("NETFLIX.COM", None).is_empty());
+        assert!(model.suggest("1234 5678", None).is_empty());
+        assert!(model.suggest_tags("NETFLIX.COM").is_empty());
+        assert!(model.suggest_tags("").is_empty());
+    }
+
+    #[test]
+    fn tags_shared_by_most_similar_descriptions() {
+        // similar: both Lidl rows, Kaufland Cluj and Petrom Cluj; weekend is on one of four
+        assert_eq!(history().suggest_tags("LIDL CLUJ"), [("groceries".to_string(), 0.75)]);
+    }
+}
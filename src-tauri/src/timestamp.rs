@@ -0,0 +1,50 @@
+// timestamp.rs
+// Canonical storage format for every date/created_at/updated_at column
+
+use chrono::{DateTime, NaiveDate, NaiveDateTime, SubsecRound, Utc};
+use sqlx::{sqlite::SqliteRow, Row};
+
+// UTC with millisecond precision, e.g. "2024-05-01T10:00:00.000Z".
+// Sorts lexicographically, so range queries can compare the stored text directly.
+// Migration 0005 produces the same format with strftime('%Y-%m-%dT%H:%M:%fZ', ...).
+pub const FORMAT: &str = "%Y-%m-%dT%H:%M:%S%.3fZ";
+
+// Current time truncated to what is stored, so returned structs match a later read
+pub fn now() -> DateTime<Utc> {
+    Utc::now().trunc_subsecs(3)
+}
+
+pub fn format(value: &DateTime<Utc>) -> String {
+    value.format(FORMAT).to_string()
+}
+
+// Accepts the canonical format plus the older ones still found in existing databases:
+// RFC3339 with any offset, "YYYY-MM-DD HH:MM:SS" (UTC) and a bare "YYYY-MM-DD".
+pub fn parse(value: &str) -> Option<DateTime<Utc>> {
+    let value = value.trim();
+
+    if let Ok(parsed) = DateTime::parse_from_rfc3339(value) {
+        return Some(parsed.with_timezone(&Utc));
+    }
+
+    for pattern in ["%Y-%m-%d %H:%M:%S%.f", "%Y-%m-%dT%H:%M:%S%.f"] {
+        if let Ok(parsed) = NaiveDateTime::parse_from_str(value, pattern) {
+            return Some(parsed.and_utc());
+        }
+    }
+
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .ok()
+        .and_then(|date| date.and_hms_opt(0, 0, 0))
+        .map(|datetime| datetime.and_utc())
+}
+
+// Reads a timestamp column, failing with a decode error instead of inventing a value
+pub fn decode(row: &SqliteRow, column: &str) -> Result<DateTime<Utc>, sqlx::Error> {
+    let raw: String = row.try_get(column)?;
+
+    parse(&raw).ok_or_else(|| sqlx::Error::ColumnDecode {
+        index: column.to_string(),
+        source: format!("unrecognized timestamp {:?}", raw).into(),
+    })
+}
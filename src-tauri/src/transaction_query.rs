@@ -0,0 +1,273 @@
+// transaction_query.rs
+// Filtered, sorted and paginated transaction listing used by the transactions page
+
+use serde::{Deserialize, Serialize};
+use sqlx::{QueryBuilder, Row, Sqlite};
+use chrono::{DateTime, Utc};
+use crate::database::{Database, Transaction};
+use crate::error::AppError;
+use crate::money::Money;
+use crate::timestamp;
+
+const DEFAULT_PAGE_SIZE: i64 = 50;
+const MAX_PAGE_SIZE: i64 = 500;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum TagMatch {
+    #[default]
+    Any, // at least one of the tags
+    All, // every one of the tags
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum TransactionSort {
+    #[default]
+    DateDesc,
+    DateAsc,
+    AmountDesc,
+    AmountAsc,
+}
+
+// Empty lists and None mean "no restriction"
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct TransactionFilter {
+    pub account_ids: Vec<String>,
+    pub category_ids: Vec<String>,
+    pub tag_ids: Vec<String>,
+    pub tag_match: TagMatch,
+    pub owners: Vec<String>,
+    pub date_from: Option<DateTime<Utc>>, // inclusive
+    pub date_to: Option<DateTime<Utc>>,   // inclusive
+    pub amount_min: Option<Money>,        // inclusive; only transactions in its currency
+    pub amount_max: Option<Money>,        // inclusive; same currency as amount_min
+    pub transaction_type: Option<String>, // "income" or "expense"
+    pub search: Option<String>,           // matched against the description
+    pub sort: TransactionSort,
+    pub cursor: Option<String>, // next_cursor of the previous page
+    pub limit: Option<i64>,
+}
+
+// Income and expense of the whole filtered set in one currency, transfers excluded
+#[derive(Debug, Serialize, Clone)]
+pub struct CurrencyTotals {
+    pub currency: String,
+    pub income: Money,
+    pub expense: Money,
+    pub net: Money,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct TransactionPage {
+    pub transactions: Vec<Transaction>,
+    pub total_count: i64,
+    pub totals: Vec<CurrencyTotals>,
+    pub next_cursor: Option<String>, // None on the last page
+}
+
+impl Database {
+    pub async fn query_transactions(&self, filter: TransactionFilter) -> Result<TransactionPage, AppError> {
+        let limit = filter.limit.unwrap_or(DEFAULT_PAGE_SIZE).clamp(1, MAX_PAGE_SIZE);
+        if let (Some(min), Some(max)) = (&filter.amount_min, &filter.amount_max) {
+            if min.currency != max.currency {
+                return Err(AppError::validation("amount_max", "must be in the same currency as amount_min"));
+            }
+        }
+
+        // Page: one extra row tells whether another page follows
+        let mut page_query = QueryBuilder::<Sqlite>::new("SELECT * FROM transactions WHERE 1 = 1");
+        push_filter(&mut page_query, &filter);
+        if let Some(cursor) = &filter.cursor {
+            push_cursor(&mut page_query, filter.sort, cursor)?;
+        }
+        page_query.push(match filter.sort {
+            TransactionSort::DateDesc => " ORDER BY date DESC, id DESC",
+            TransactionSort::DateAsc => " ORDER BY date ASC, id ASC",
+            TransactionSort::AmountDesc => " ORDER BY amount DESC, id DESC",
+            TransactionSort::AmountAsc => " ORDER BY amount ASC, id ASC",
+        });
+        page_query.push(" LIMIT ").push_bind(limit + 1);
+
+        let mut transactions = page_query
+            .build_query_as::<Transaction>()
+            .fetch_all(self.get_pool())
+            .await?;
+
+        let next_cursor = if transactions.len() as i64 > limit {
+            transactions.truncate(limit as usize);
+            transactions.last().map(|last| encode_cursor(filter.sort, last))
+        } else {
+            None
+        };
+
+        // Count and sums cover the whole filtered set, independent of the cursor
+        let total_count: i64 = {
+            let mut count_query = QueryBuilder::<Sqlite>::new("SELECT COUNT(*) FROM transactions WHERE 1 = 1");
+            push_filter(&mut count_query, &filter);
+            count_query.build_query_scalar().fetch_one(self.get_pool()).await?
+        };
+
+        let mut totals_query = QueryBuilder::<Sqlite>::new(
+            r#"
+            SELECT currency,
+                   COALESCE(SUM(CASE WHEN transaction_type = 'income' THEN amount ELSE 0 END), 0) AS income,
+                   COALESCE(SUM(CASE WHEN transaction_type = 'expense' THEN amount ELSE 0 END), 0) AS expense
+            FROM transactions
+            WHERE transfer_id IS NULL
+            "#
+        );
+        push_filter(&mut totals_query, &filter);
+        totals_query.push(" GROUP BY currency ORDER BY currency");
+
+        let totals = totals_query
+            .build()
+            .fetch_all(self.get_pool())
+            .await?
+            .into_iter()
+            .map(|row| {
+                let currency: String = row.get("currency");
+                let income: i64 = row.get("income");
+                let expense: i64 = row.get("expense");
+
+                CurrencyTotals {
+                    income: Money::new(income, currency.clone()),
+                    expense: Money::new(expense, currency.clone()),
+                    net: Money::new(income - expense, currency.clone()),
+                    currency,
+                }
+            })
+            .collect();
+
+        Ok(TransactionPage {
+            transactions,
+            total_count,
+            totals,
+            next_cursor,
+        })
+    }
+}
+
+// Appends " AND ..." conditions; the query must already have a WHERE clause
+fn push_filter(query: &mut QueryBuilder<'_, Sqlite>, filter: &TransactionFilter) {
+    push_in(query, "account_id", &filter.account_ids);
+    push_in(query, "category_id", &filter.category_ids);
+
+    if !filter.owners.is_empty() {
+        // transactions.owner overrides the account owner when set
+        query.push(
+            " AND COALESCE(transactions.owner, \
+             (SELECT owner FROM accounts WHERE accounts.id = transactions.account_id)) IN (",
+        );
+        let mut owners = query.separated(", ");
+        for owner in &filter.owners {
+            owners.push_bind(owner.clone());
+        }
+        query.push(")");
+    }
+
+    if !filter.tag_ids.is_empty() {
+        query.push(" AND id IN (SELECT transaction_id FROM transaction_tags WHERE tag_id IN (");
+        let mut tags = query.separated(", ");
+        for tag_id in &filter.tag_ids {
+            tags.push_bind(tag_id.clone());
+        }
+        query.push(")");
+        if filter.tag_match == TagMatch::All {
+            let mut distinct_tags = filter.tag_ids.clone();
+            distinct_tags.sort();
+            distinct_tags.dedup();
+            query
+                .push(" GROUP BY transaction_id HAVING COUNT(DISTINCT tag_id) = ")
+                .push_bind(distinct_tags.len() as i64);
+        }
+        query.push(")");
+    }
+
+    if let Some(date_from) = &filter.date_from {
+        query.push(" AND date >= ").push_bind(timestamp::format(date_from));
+    }
+    if let Some(date_to) = &filter.date_to {
+        query.push(" AND date <= ").push_bind(timestamp::format(date_to));
+    }
+    if let Some(amount_min) = &filter.amount_min {
+        query
+            .push(" AND currency = ")
+            .push_bind(amount_min.currency.clone())
+            .push(" AND amount >= ")
+            .push_bind(amount_min.minor_units);
+    }
+    if let Some(amount_max) = &filter.amount_max {
+        query
+            .push(" AND currency = ")
+            .push_bind(amount_max.currency.clone())
+            .push(" AND amount <= ")
+            .push_bind(amount_max.minor_units);
+    }
+    if let Some(transaction_type) = &filter.transaction_type {
+        query.push(" AND transaction_type = ").push_bind(transaction_type.clone());
+    }
+
+    if let Some(search) = filter.search.as_deref().map(str::trim).filter(|s| !s.is_empty()) {
+        let pattern = format!(
+            "%{}%",
+            search.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_")
+        );
+        query.push(" AND description LIKE ").push_bind(pattern).push(" ESCAPE '\\'");
+    }
+}
+
+fn push_in(query: &mut QueryBuilder<'_, Sqlite>, column: &str, values: &[String]) {
+    if values.is_empty() {
+        return;
+    }
+
+    query.push(format!(" AND {} IN (", column));
+    let mut list = query.separated(", ");
+    for value in values {
+        list.push_bind(value.clone());
+    }
+    query.push(")");
+}
+
+// Cursor is "<sort key>|<id>" of the last row on the previous page
+fn encode_cursor(sort: TransactionSort, last: &Transaction) -> String {
+    match sort {
+        TransactionSort::DateDesc | TransactionSort::DateAsc => {
+            format!("{}|{}", timestamp::format(&last.date), last.id)
+        }
+        TransactionSort::AmountDesc | TransactionSort::AmountAsc => {
+            format!("{}|{}", last.amount.minor_units, last.id)
+        }
+    }
+}
+
+fn push_cursor(query: &mut QueryBuilder<'_, Sqlite>, sort: TransactionSort, cursor: &str) -> Result<(), AppError> {
+    let invalid = || AppError::validation("cursor", format!("malformed cursor {}", cursor));
+    let (key, id) = cursor.split_once('|').ok_or_else(invalid)?;
+
+    let (column, operator) = match sort {
+        TransactionSort::DateDesc => ("date", "<"),
+        TransactionSort::DateAsc => ("date", ">"),
+        TransactionSort::AmountDesc => ("amount", "<"),
+        TransactionSort::AmountAsc => ("amount", ">"),
+    };
+
+    // (key, id) strictly after the cursor in sort order
+    query.push(format!(" AND ({} {} ", column, operator));
+    match sort {
+        TransactionSort::DateDesc | TransactionSort::DateAsc => {
+            query.push_bind(key.to_string());
+            query.push(format!(" OR ({} = ", column)).push_bind(key.to_string());
+        }
+        TransactionSort::AmountDesc | TransactionSort::AmountAsc => {
+            let amount: i64 = key.parse().map_err(|_| invalid())?;
+            query.push_bind(amount);
+            query.push(format!(" OR ({} = ", column)).push_bind(amount);
+        }
+    }
+    query.push(format!(" AND id {} ", operator)).push_bind(id.to_string()).push("))");
+
+    Ok(())
+}
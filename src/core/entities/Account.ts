@@ -1,8 +1,10 @@
+import type { Money } from './Money';
+
 export interface Account {
   id: string;
   name: string;
   account_type: string;
-  balance: number;
+  balance: Money;
   currency: string;
   created_at: string;
   updated_at: string;
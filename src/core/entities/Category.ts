@@ -3,7 +3,7 @@ export interface Category {
   name: string;
   color: string;
   icon: string;
-  category_type: 'income' | 'expense';
+  category_type: 'income' | 'expense' | 'transfer';
   created_at: string;
   updated_at: string;
 }
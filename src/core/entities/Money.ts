@@ -0,0 +1,5 @@
+// Integer minor units (bani, cents) plus ISO 4217 currency, as serialized by money.rs
+export interface Money {
+  minor_units: number;
+  currency: string;
+}
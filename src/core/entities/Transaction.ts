@@ -1,8 +1,10 @@
+import type { Money } from './Money';
+
 export interface Transaction {
   id: string;
   account_id: string;
   category_id: string;
-  amount: number;
+  amount: Money;
   description: string;
   transaction_type: 'income' | 'expense';
   date: string;
@@ -2,3 +2,4 @@ export type { Account } from './Account';
 export type { Transaction } from './Transaction';
 export type { Category } from './Category';
 export type { Tag } from './Tag';
+export type { Money } from './Money';
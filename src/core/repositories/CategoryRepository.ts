@@ -41,12 +41,13 @@ class CategoryRepository {
     }
   }
 
-  async delete(id: string): Promise<void> {
+  // With reassignTo, transactions are moved to that category before deleting
+  async delete(id: string, reassignTo?: string): Promise<void> {
     try {
-      await invoke('delete_category', { id });
+      await invoke('delete_category', { id, reassignTo });
     } catch (error) {
       console.error('CategoryRepository.delete error:', error);
-      throw new Error('Failed to delete category');
+      throw error;
     }
   }
 }
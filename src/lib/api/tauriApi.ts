@@ -2,6 +2,40 @@ import { invoke } from '@tauri-apps/api/core';
 import { loadingStore } from '../../ui/stores/loadingStore';
 import { notificationStore } from '../../ui/stores/notificationStore';
 
+// Shape of AppError serialized by the Rust backend
+export type AppError =
+  | { kind: 'not_found'; details: { entity: string } }
+  | { kind: 'in_use'; details: { entity: string; count: number } }
+  | { kind: 'validation'; details: { field: string; reason: string } }
+  | { kind: 'conflict'; details: { message: string } }
+  | { kind: 'io'; details: { message: string } }
+  | { kind: 'database'; details: { message: string } };
+
+export function isAppError(error: unknown): error is AppError {
+  return typeof error === 'object' && error !== null && 'kind' in error;
+}
+
+export function errorMessage(error: unknown): string {
+  if (isAppError(error)) {
+    switch (error.kind) {
+      case 'not_found':
+        return `Nu a fost găsit: ${error.details.entity}`;
+      case 'in_use':
+        return `Nu se poate șterge: este folosit de ${error.details.count} tranzacții`;
+      case 'validation':
+        return `Valoare invalidă (${error.details.field}): ${error.details.reason}`;
+      case 'conflict':
+        return `Conflict: ${error.details.message}`;
+      case 'io':
+        return `Eroare de fișier: ${error.details.message}`;
+      case 'database':
+        return `Eroare de bază de date: ${error.details.message}`;
+    }
+  }
+  if (error instanceof Error) return error.message;
+  return typeof error === 'string' ? error : 'Eroare necunoscută';
+}
+
 export class TauriApi {
   private static async invokeWithLoading<T>(
     command: string,
@@ -31,9 +65,7 @@ export class TauriApi {
       console.error(`TauriApi error in ${command}:`, error);
       
       if (options?.showError !== false) {
-        notificationStore.error(
-          error instanceof Error ? error.message : 'Eroare necunoscută'
-        );
+        notificationStore.error(errorMessage(error));
       }
       
       throw error;
@@ -3,4 +3,5 @@
 export type { Account } from '../core/entities/Account';
 export type { Transaction } from '../core/entities/Transaction';
 export type { Category } from '../core/entities/Category';
-export type { Tag } from '../core/entities/Tag';
\ No newline at end of file
+export type { Tag } from '../core/entities/Tag';
+export type { Money } from '../core/entities/Money';
\ No newline at end of file
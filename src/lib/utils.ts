@@ -1,3 +1,5 @@
+import type { Money } from '../core/entities/Money';
+
 export function formatCurrency(amount: number, currency: string = 'RON'): string {
   return new Intl.NumberFormat('ro-RO', {
     style: 'currency',
@@ -6,6 +8,40 @@ export function formatCurrency(amount: number, currency: string = 'RON'): string
   }).format(amount);
 }
 
+// Decimal places per currency, in sync with minor_unit_exponent in money.rs
+export function minorUnitExponent(currency: string): number {
+  switch (currency) {
+    case 'JPY': case 'KRW': case 'ISK': case 'CLP': case 'VND': case 'XAF': case 'XOF':
+      return 0;
+    case 'BHD': case 'IQD': case 'JOD': case 'KWD': case 'LYD': case 'OMR': case 'TND':
+      return 3;
+    default:
+      return 2;
+  }
+}
+
+export function toMajorUnits(money: Money): number {
+  return money.minor_units / 10 ** minorUnitExponent(money.currency);
+}
+
+// Rounds a user-entered amount to the currency's minor units
+export function toMoney(amount: number, currency: string): Money {
+  return {
+    minor_units: Math.round(amount * 10 ** minorUnitExponent(currency)),
+    currency,
+  };
+}
+
+export function formatMoney(money: Money): string {
+  const digits = minorUnitExponent(money.currency);
+  return new Intl.NumberFormat('ro-RO', {
+    style: 'currency',
+    currency: money.currency,
+    minimumFractionDigits: digits,
+    maximumFractionDigits: digits,
+  }).format(toMajorUnits(money));
+}
+
 export function formatDate(dateString: string): string {
   const date = new Date(dateString);
   return new Intl.DateTimeFormat('ro-RO', {
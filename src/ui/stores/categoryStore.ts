@@ -3,6 +3,7 @@ import { notificationStore } from './notificationStore';
 import { writable } from 'svelte/store';
 import { categoryRepository } from '../../core/repositories/CategoryRepository';
 import type { Category } from '../../core/entities/Category';
+import { errorMessage } from '../../lib/api/tauriApi';
 
 function createCategoryStore() {
   const { subscribe, set, update } = writable<Category[]>([]);
@@ -48,14 +49,14 @@ function createCategoryStore() {
       }
     },
 
-    async remove(id: string) {
+    async remove(id: string, reassignTo?: string) {
       try {
-        await categoryRepository.delete(id);
+        await categoryRepository.delete(id, reassignTo);
         update(categories => categories.filter(c => c.id !== id));
         notificationStore.success('Category deleted');
       } catch (error) {
         console.error('Failed to delete category:', error);
-        notificationStore.error('Failed to delete category');
+        notificationStore.error(errorMessage(error));
         throw error;
       }
     }